@@ -1,14 +1,30 @@
+use bricks::convex::calipers::{self, EnclosingRectangle, Strip};
+use bricks::convex::dynamic::DynamicHull;
+use bricks::sampling::Sampler;
 use bricks::*;
-use rand::*;
 
 #[derive(Resource, Default)]
 struct Inp {
     points: Vec<Vec2>,
+    hull: DynamicHull,
+    presses: usize,
 }
 
 #[derive(Resource, Default)]
 struct Outp {
     line: Vec<Vec2>,
+    diameter: Option<(Vec2, Vec2)>,
+    width: Option<Strip>,
+    rectangle: Option<EnclosingRectangle>,
+}
+
+impl Outp {
+    fn show(&mut self, hull: Vec<Vec2>) {
+        self.diameter = calipers::diameter(&hull);
+        self.width = calipers::min_width(&hull);
+        self.rectangle = calipers::min_area_rectangle(&hull);
+        self.line = convex::hull::closed(hull);
+    }
 }
 
 bricks::game_2d!(
@@ -24,26 +40,42 @@ fn init(mut commands: Commands) {
 }
 
 fn on_mouse_click(In(point): In<Result<Vec2, ()>>, mut inp: ResMut<Inp>, mut outp: ResMut<Outp>) {
-    inp.points.push(point.unwrap_or_default());
+    let Ok(point) = point else {
+        return;
+    };
+    inp.points.push(point);
+    let _ = inp.hull.insert(point);
 
-    outp.line = convex::hull::algo(inp.points.clone());
+    outp.show(inp.hull.hull());
 }
 
 fn on_spacebar_press(mut outp: ResMut<Outp>, window: Single<&Window>, mut inp: ResMut<Inp>) {
     inp.points.clear();
+    inp.hull.clear();
 
-    let mut rng = rand::thread_rng();
-    for _ in 0..10 {
-        let sample = Vec2::new(rng.gen::<f32>() - 0.5, rng.gen::<f32>() - 0.5)
-            * Vec2::new(
-                window.resolution.physical_width() as f32 - 200.0,
-                window.resolution.physical_height() as f32 - 200.0,
-            )
-            * (1.0 / window.resolution.scale_factor());
+    let size = Vec2::new(
+        window.resolution.physical_width() as f32 - 200.0,
+        window.resolution.physical_height() as f32 - 200.0,
+    ) * (1.0 / window.resolution.scale_factor());
+    // Each press takes the next sampler, the disk radius leaves room for about as many points
+    let samplers = [
+        Sampler::Uniform,
+        Sampler::Halton,
+        Sampler::Sobol,
+        Sampler::Stratified,
+        Sampler::PoissonDisk {
+            radius: (size.x * size.y / 10.0).sqrt() * 0.8,
+        },
+    ];
+    let sampler = samplers[inp.presses % samplers.len()];
+    inp.presses += 1;
+    let bounds = Rect::from_center_size(Vec2::ZERO, size);
+    for sample in sampler.sample_2d(10, bounds, |_| true, &mut rand::thread_rng()) {
         inp.points.push(sample);
+        let _ = inp.hull.insert(sample);
     }
 
-    outp.line = convex::hull::algo(inp.points.clone());
+    outp.show(inp.hull.hull());
 }
 
 fn draw_inp(mut gizmos: Gizmos, inp: Res<Inp>) {
@@ -54,4 +86,16 @@ fn draw_inp(mut gizmos: Gizmos, inp: Res<Inp>) {
 
 fn draw_outp(mut gizmos: Gizmos, outp: Res<Outp>) {
     gizmos.linestrip_2d(outp.line.clone(), Color::linear_rgb(1.0, 0.0, 0.0));
+    if let Some(rectangle) = outp.rectangle {
+        gizmos.linestrip_2d(
+            convex::hull::closed(rectangle.corners.to_vec()),
+            Color::linear_rgb(0.0, 0.5, 1.0),
+        );
+    }
+    if let Some((a, b)) = outp.diameter {
+        gizmos.line_2d(a, b, Color::linear_rgb(1.0, 1.0, 0.0));
+    }
+    if let Some(width) = outp.width {
+        gizmos.line_2d(width.vertex, width.foot(), Color::linear_rgb(0.0, 1.0, 0.0));
+    }
 }
@@ -24,7 +24,10 @@ fn init(mut commands: Commands) {
 }
 
 fn on_mouse_click(In(point): In<Result<Vec2, ()>>, mut inp: ResMut<Inp>, mut outp: ResMut<Outp>) {
-    inp.points.push(point.unwrap_or_default());
+    let Ok(point) = point else {
+        return;
+    };
+    inp.points.push(point);
 
     outp.line = convex::spiral::algo(inp.points.clone());
 }
@@ -0,0 +1,261 @@
+//! Times searches on a graph the size of the one in `search_montage`: the baseline search from
+//! before the bookkeeping moved into arrays, with maps and sets keyed by vertex, then the current
+//! search numbering vertices by hash, by their index with arrays allocated for each query, and by
+//! their index with arrays reused across queries.
+//!
+//! The baseline settles every vertex at the first cost found, while the current search lowers the
+//! cost of a vertex in the fringe when it finds a cheaper way, so their trees differ a little.
+//!
+//! Run it with `cargo run --release --example search_bench`, debug builds mostly time the
+//! unoptimized code around the bookkeeping. Reusing the arrays only saves allocating and clearing
+//! them for each query, which is small next to a search that reaches a good part of the graph, so
+//! expect reuse to win by a few percent at most here. It matters more for short queries on large
+//! graphs.
+use bevy::prelude::*;
+use bricks::search::graph::{Graph, SearchGraph};
+use bricks::search::search::{AStar, CostGuidedWaveTreeSearch, SearchWorkspace, BFS, UCS};
+use bricks::search::spaces::CuboidWithHoldSpace;
+use ordered_float::OrderedFloat;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// Same graph without vertex numbers, so that the current search numbers its vertices by hash.
+struct Hashed<'a>(&'a Graph);
+
+impl SearchGraph for Hashed<'_> {
+    type Vertex = usize;
+
+    fn successors(&self, vertex: &usize) -> impl Iterator<Item = (usize, f32)> {
+        self.0.successors(vertex)
+    }
+
+    fn heuristic(&self, from: &usize, to: &usize) -> f32 {
+        self.0.heuristic(from, to)
+    }
+}
+
+/// `CostGuidedWaveTreeSearch` as it was before the bookkeeping moved into arrays, with the states
+/// made from positions instead of vertices.
+trait Baseline<Cost: Ord>: Sized {
+    fn as_start(my_vertex_state: Vec3, stop_vertex_state: Vec3) -> Self;
+
+    fn as_adj(
+        prev_vertex_state: Vec3,
+        my_vertex_state: Vec3,
+        stop_vertex_state: Vec3,
+        parent: &Self,
+    ) -> Self;
+
+    fn cost(&self) -> Cost;
+}
+
+struct BaselineBFS {
+    jumps_from_start: usize,
+}
+
+impl Baseline<usize> for BaselineBFS {
+    fn as_start(_: Vec3, _: Vec3) -> Self {
+        Self {
+            jumps_from_start: 0,
+        }
+    }
+
+    fn as_adj(_: Vec3, _: Vec3, _: Vec3, parent: &Self) -> Self {
+        Self {
+            jumps_from_start: parent.jumps_from_start + 1,
+        }
+    }
+
+    fn cost(&self) -> usize {
+        self.jumps_from_start
+    }
+}
+
+struct BaselineAStar<const NUM: usize, const DEN: usize> {
+    dist_from_start: f32,
+    total_cost: f32,
+}
+
+impl<const NUM: usize, const DEN: usize> Baseline<OrderedFloat<f32>> for BaselineAStar<NUM, DEN> {
+    fn as_start(my_vertex_state: Vec3, stop_vertex_state: Vec3) -> Self {
+        Self {
+            dist_from_start: 0.0,
+            total_cost: 0.0 + (my_vertex_state - stop_vertex_state).length(),
+        }
+    }
+
+    fn as_adj(
+        prev_vertex_state: Vec3,
+        my_vertex_state: Vec3,
+        stop_vertex_state: Vec3,
+        parent: &Self,
+    ) -> Self {
+        let dist_from_start =
+            parent.dist_from_start + (prev_vertex_state - my_vertex_state).length();
+        Self {
+            dist_from_start,
+            total_cost: dist_from_start
+                + (my_vertex_state - stop_vertex_state).length() * (NUM as f32 / DEN as f32),
+        }
+    }
+
+    fn cost(&self) -> OrderedFloat<f32> {
+        OrderedFloat(self.total_cost)
+    }
+}
+
+/// Result of the search before the bookkeeping moved into arrays, built in full as it was even
+/// though only the size of the tree is used.
+#[allow(dead_code)]
+struct BaselineResult {
+    start_idx: usize,
+    stop_idx: usize,
+    reached: bool,
+    parent_map: HashMap<usize, Option<usize>>,
+    fringe: HashSet<usize>,
+}
+
+/// `CostGuidedWaveTreeSearch::try_on` of the baseline, unchanged apart from going over the keys
+/// of the adjacencies, which were a set then. It settles every vertex at the first cost found.
+fn try_on_baseline<S: Baseline<C>, C: Ord>(
+    graph: &Graph,
+    start_idx: usize,
+    stop_idx: usize,
+) -> BaselineResult {
+    assert!(start_idx < graph.vertices.len());
+    assert!(stop_idx < graph.vertices.len());
+    let start_search_state =
+        S::as_start(graph.vertices[start_idx].pos, graph.vertices[stop_idx].pos);
+    let collec_alloc_size = graph.vertices.len();
+    let mut parent_map = HashMap::with_capacity(collec_alloc_size);
+    parent_map.insert(start_idx, None);
+
+    let mut fringe = BinaryHeap::with_capacity(collec_alloc_size);
+
+    struct CostOrdAndIndex<Cost: Ord> {
+        idx: usize,
+        cost: Cost,
+    }
+
+    impl<Cost: Ord> PartialEq for CostOrdAndIndex<Cost> {
+        fn eq(&self, other: &Self) -> bool {
+            self.cost == other.cost
+        }
+    }
+
+    impl<Cost: Ord> Eq for CostOrdAndIndex<Cost> {}
+
+    impl<Cost: Ord> Ord for CostOrdAndIndex<Cost> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.cost.cmp(&other.cost)
+        }
+    }
+
+    impl<Cost: Ord> PartialOrd for CostOrdAndIndex<Cost> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    fringe.push(Reverse(CostOrdAndIndex {
+        idx: start_idx,
+        cost: start_search_state.cost(),
+    }));
+    let mut tree = HashMap::with_capacity(collec_alloc_size);
+    tree.insert(start_idx, start_search_state);
+    while let Some(Reverse(CostOrdAndIndex { idx: curr_idx, .. })) = fringe.pop() {
+        if curr_idx == stop_idx {
+            return BaselineResult {
+                start_idx,
+                stop_idx,
+                parent_map,
+                fringe: fringe
+                    .into_sorted_vec()
+                    .into_iter()
+                    .map(|Reverse(CostOrdAndIndex { idx, .. })| idx)
+                    .collect(),
+                reached: true,
+            };
+        }
+        for &adj_idx in graph.vertices[curr_idx].adjacencies.keys() {
+            if !tree.contains_key(&adj_idx) {
+                let adj_search_state = S::as_adj(
+                    graph.vertices[curr_idx].pos,
+                    graph.vertices[adj_idx].pos,
+                    graph.vertices[stop_idx].pos,
+                    &tree[&curr_idx],
+                );
+                parent_map.insert(adj_idx, Some(curr_idx));
+                fringe.push(Reverse(CostOrdAndIndex {
+                    idx: adj_idx,
+                    cost: adj_search_state.cost(),
+                }));
+                tree.insert(adj_idx, adj_search_state);
+            }
+        }
+    }
+    BaselineResult {
+        start_idx,
+        stop_idx,
+        parent_map,
+        fringe: fringe
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(CostOrdAndIndex { idx, .. })| idx)
+            .collect(),
+        reached: false,
+    }
+}
+
+const QUERIES: usize = 200;
+
+fn main() {
+    let space = CuboidWithHoldSpace {
+        size: Vec3::new(6.0, 12.0, 0.5),
+        hole_radius: 6.0 / 2.1,
+    };
+    let mut graph = Graph::default();
+    graph.generate_samples(&space, 20000, 0.3);
+    let queries: Vec<(usize, usize)> = (0..QUERIES)
+        .map(|_| {
+            (
+                graph.choose_random_vertex_idx(),
+                graph.choose_random_vertex_idx(),
+            )
+        })
+        .collect();
+    println!(
+        "{} vertices, {} queries, time per query and speedup over the baseline",
+        graph.vertices.len(),
+        QUERIES
+    );
+    bench::<BFS, BaselineBFS, _>("BFS", &graph, &queries);
+    bench::<UCS, BaselineAStar<0, 1>, _>("UCS", &graph, &queries);
+    bench::<AStar, BaselineAStar<1, 1>, _>("A*", &graph, &queries);
+}
+
+fn bench<S: CostGuidedWaveTreeSearch<C>, B: Baseline<C>, C: Ord>(
+    name: &str,
+    graph: &Graph,
+    queries: &[(usize, usize)],
+) {
+    let time = |search: &mut dyn FnMut(usize, usize) -> usize| {
+        let start = Instant::now();
+        let tree: usize = queries.iter().map(|&(a, b)| search(a, b)).sum();
+        (start.elapsed() / queries.len() as u32, tree / queries.len())
+    };
+    let (baseline, baseline_tree) =
+        time(&mut |a, b| try_on_baseline::<B, C>(graph, a, b).parent_map.len());
+    let (hashed, tree) = time(&mut |a, b| S::try_on(&Hashed(graph), a, b).tree.len());
+    let (dense, _) = time(&mut |a, b| S::try_on(graph, a, b).tree.len());
+    let mut workspace = SearchWorkspace::new();
+    let (reused, _) = time(&mut |a, b| S::try_on_with(&mut workspace, graph, a, b).tree.len());
+    let speedup = |time: Duration| baseline.as_secs_f64() / time.as_secs_f64();
+    println!(
+        "{name:>4}: {tree:>5} vertices in tree ({baseline_tree:>5} in the baseline's), baseline {baseline:>10.2?}, hashed ids {hashed:>10.2?} ({:.1}x), indices {dense:>10.2?} ({:.1}x), reused {reused:>10.2?} ({:.1}x)",
+        speedup(hashed),
+        speedup(dense),
+        speedup(reused),
+    );
+}
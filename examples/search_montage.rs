@@ -1,12 +1,17 @@
 use bricks::game::threed::camera_controller::CameraController;
+use bricks::search::anytime::{ARAStar, Budget};
+use bricks::search::bidirectional::{
+    BidirectionalAStar, BidirectionalBFS, BidirectionalSearch, BidirectionalUCS,
+};
 use bricks::search::graph::Graph;
+use bricks::search::incremental::LPAStar;
 use bricks::search::path::Path;
 use bricks::search::search::{
-    AStar, AStarWeighted2, CostGuidedTreeSearchResult, CostGuidedWaveTreeSearch, WeightableAStar,
-    BFS, DFS, UCS,
+    AStar, CostGuidedTreeSearchResult, CostGuidedWaveTreeSearch, WeightedAStar, BFS, DFS, UCS,
 };
 use bricks::search::spaces::CuboidWithHoldSpace;
 use bricks::*;
+use std::time::Duration;
 
 #[derive(Resource, Default)]
 struct Searches(Vec<CostGuidedTreeSearchResult>);
@@ -15,7 +20,7 @@ struct Searches(Vec<CostGuidedTreeSearchResult>);
 struct Paths(Vec<Path>);
 
 bricks::game_3d!(
-    "bfs, dfs, ucs, A*, 2.0 weighted A*, 100.0 weighted A*",
+    "bfs, dfs, ucs, A*, 2.0 weighted A*, 100.0 weighted A*, bidirectional bfs, ucs and A*, ARA*, LPA* repairing its blocked path",
     {
         CuboidWithHoldSpace -> draw_space,
         Graph -> draw_graph,
@@ -45,18 +50,42 @@ fn on_spacebar_press(
         graph.choose_random_vertex_idx(),
         graph.choose_random_vertex_idx(),
     ];
+    // Block the middle of the first path for LPA* to repair it
+    let mut lpa = LPAStar::new(&graph, a, b);
+    if let Some(path) = lpa.path(&graph).filter(|path| path.len() > 2) {
+        graph.block_vertex(path[path.len() / 2]);
+        lpa.update(&graph);
+    }
+    let graph: &Graph = &graph;
     searches.0 = vec![
-        DFS::try_on(&graph, a, b),
-        BFS::try_on(&graph, a, b),
-        UCS::try_on(&graph, a, b),
-        AStar::try_on(&graph, a, b),
-        AStarWeighted2::try_on(&graph, a, b),
-        WeightableAStar::<100, 1>::try_on(&graph, a, b),
+        DFS::try_on(graph, a, b),
+        BFS::try_on(graph, a, b),
+        UCS::try_on(graph, a, b),
+        AStar::try_on(graph, a, b),
+        WeightedAStar::try_on_weighted(graph, a, b, 2.0),
+        WeightedAStar::try_on_weighted(graph, a, b, 100.0),
+        BidirectionalBFS::try_on(graph, a, b),
+        BidirectionalUCS::try_on(graph, a, b),
+        BidirectionalAStar::try_on(graph, a, b),
     ];
+    let anytime = ARAStar {
+        initial_weight: 5.0,
+        weight_step: 1.0,
+        budget: Budget::Time(Duration::from_millis(20)),
+    }
+    .try_on(graph, a, b);
+    for solution in &anytime.solutions {
+        info!(
+            "ARA* weight {:.1}: cost {:.3} within {:.3}x of the cheapest after {} expansions",
+            solution.weight, solution.cost, solution.bound, solution.expansions
+        );
+    }
+    searches.0.push(anytime.search);
+    searches.0.push(lpa.search(graph));
     paths.0.clear();
     for search in searches.0.iter() {
         let mut path = Path::default();
-        path.generate_for(&graph, &search);
+        path.generate_for(graph, search);
         paths.0.push(path);
     }
 }
@@ -67,7 +96,7 @@ fn draw_space(mut gizmos: Gizmos, space: Res<CuboidWithHoldSpace>) {
 
 fn draw_graph(mut gizmos: Gizmos, space: Res<CuboidWithHoldSpace>, graph: Res<Graph>) {
     for vertex in &graph.vertices {
-        for adj in &vertex.adjacencies {
+        for adj in vertex.adjacencies.keys() {
             let self_position = vertex.pos;
             let adj_position = graph.vertices[*adj].pos;
             gizmos.line(
@@ -87,7 +116,7 @@ fn draw_searches(
     searches: Res<Searches>,
 ) {
     for (idx, search) in searches.0.iter().enumerate() {
-        for (&child_idx, parent_idx) in search.parent_map.iter() {
+        for &(child_idx, parent_idx) in search.tree.iter().chain(search.backward.iter()) {
             let parent_idx = parent_idx.unwrap_or(child_idx);
             let color = if search.start_idx == parent_idx {
                 Color::srgb(1., 1., 0.)
@@ -125,6 +154,17 @@ fn draw_searches(
             .with_scale(Vec3::ONE * 0.05),
             Color::srgb(1., 0., 0.),
         );
+        if let Some(meeting_idx) = search.meeting {
+            gizmos.cuboid(
+                Transform::from_translation(
+                    graph.vertices[meeting_idx].pos
+                        + (idx as f32 + 1.) * Vec3::X * (space.size.x + 1.)
+                        + Vec3::Y * (space.size.y + 1.),
+                )
+                .with_scale(Vec3::ONE * 0.05),
+                Color::srgb(0., 1., 1.),
+            );
+        }
     }
 }
 
@@ -0,0 +1,87 @@
+use bricks::geometry::circle::Disk;
+use bricks::geometry::voronoi::Voronoi;
+use bricks::*;
+use rand::*;
+
+const BOUNDS: Rect = Rect {
+    min: Vec2::new(-200.0, -200.0),
+    max: Vec2::new(200.0, 200.0),
+};
+
+#[derive(Resource, Default)]
+struct Inp {
+    points: Vec<Vec2>,
+}
+
+#[derive(Resource, Default)]
+struct Outp {
+    cells: Vec<Vec<Vec2>>,
+    empty_circle: Option<Disk>,
+}
+
+impl Outp {
+    fn show(&mut self, points: &[Vec2]) {
+        self.cells.clear();
+        self.empty_circle = None;
+        let Ok(voronoi) = Voronoi::new(points, BOUNDS) else {
+            return;
+        };
+        self.cells = voronoi.cells().to_vec();
+        self.empty_circle = voronoi.largest_empty_circle();
+    }
+}
+
+bricks::game_2d!(
+    "voronoi",
+    {
+        Inp -> draw_inp,
+        Outp -> draw_outp,
+    }
+);
+
+fn init(mut commands: Commands) {
+    commands.spawn(Camera2d::default());
+}
+
+fn on_mouse_click(In(point): In<Result<Vec2, ()>>, mut inp: ResMut<Inp>, mut outp: ResMut<Outp>) {
+    let Ok(point) = point else {
+        return;
+    };
+    inp.points.push(point);
+
+    outp.show(&inp.points);
+}
+
+fn on_spacebar_press(mut outp: ResMut<Outp>, mut inp: ResMut<Inp>) {
+    inp.points.clear();
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..20 {
+        let sample = Vec2::new(rng.gen::<f32>(), rng.gen::<f32>()) * BOUNDS.size() + BOUNDS.min;
+        inp.points.push(sample);
+    }
+
+    outp.show(&inp.points);
+}
+
+fn draw_inp(mut gizmos: Gizmos, inp: Res<Inp>) {
+    for point in &inp.points {
+        gizmos.circle_2d(Isometry2d::from_translation(*point), 3.0, Color::WHITE);
+    }
+}
+
+fn draw_outp(mut gizmos: Gizmos, outp: Res<Outp>) {
+    for cell in &outp.cells {
+        gizmos.linestrip_2d(
+            convex::hull::closed(cell.clone()),
+            Color::linear_rgb(1.0, 0.0, 0.0),
+        );
+    }
+    if let Some(disk) = outp.empty_circle {
+        gizmos.circle_2d(
+            Isometry2d::from_translation(disk.center),
+            disk.radius,
+            Color::linear_rgb(0.0, 1.0, 0.0),
+        );
+    }
+}
@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use bevy::render::mesh::Mesh;
 use bevy::render::mesh::{Indices, PrimitiveTopology};
 use bevy::{sprite::MaterialMesh2dBundle};
+use bricks::geometry::intersect::{segment_segment, Intersection};
 use rand::prelude::*;
 
 pub struct Walk<'a>(pub &'a [Vec3]);
@@ -60,12 +61,7 @@ fn update(
             )
         });
         let [p, q, r, s] = points;
-        fn sign(p: Vec2, q: Vec2, a: Vec2) -> f32 {
-            (a.x - p.x) * (q.y - p.y) - (a.y - p.y) * (q.x - p.x)
-        }
-        let p_opp_q = sign(r, s, p) * sign(r, s, q) < 0.;
-        let r_opp_s = sign(p, q, r) * sign(p, q, s) < 0.;
-        let does_intersect = p_opp_q && r_opp_s;
+        let does_intersect = segment_segment(p, q, r, s) != Intersection::None;
         for point in points {
             commands.spawn((
                 InputPoint,
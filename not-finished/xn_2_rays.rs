@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use bevy::render::mesh::Mesh;
 use bevy::render::mesh::{Indices, PrimitiveTopology};
 use bevy::{sprite::MaterialMesh2dBundle};
+use bricks::geometry::intersect::{ray_ray, Intersection};
 use rand::prelude::*;
 
 pub struct Walk<'a>(pub &'a [Vec3]);
@@ -60,15 +61,8 @@ fn update(
                 0.,
             )
         });
-        let [p1, p2, q1, q2] = points;
-        let r1 = (p2 - p1).normalize();
-        let r2 = (q2 - q1).normalize();
-        let base = (q1 - p1).normalize();
-        let ray1_towards_ray2 = r1.dot(base);
-        let ray2_towards_ray1 = r2.dot(-base);
-        let towards_each_other = ray1_towards_ray2 + ray2_towards_ray1 > 0.;
-        let same_side_of_base = r1.cross(base).z * r2.cross(-base).z < 0.;
-        let does_intersect = towards_each_other && same_side_of_base;
+        let [p1, p2, q1, q2] = points.map(|p| p.truncate());
+        let does_intersect = ray_ray(p1, p2 - p1, q1, q2 - q1) != Intersection::None;
         for ray in points.chunks(2) {
             commands.spawn((
                 InputPoint,
@@ -0,0 +1,358 @@
+//! Rotating calipers over the output of a [`super::hull::ConvexHull`], i.e. a strictly convex
+//! polygon in counter clockwise order. The calipers go around the hull once, so everything is
+//! linear in the hull size apart from sorting the antipodal pairs. All functions return `None` only
+//! for an empty hull, a hull of one or two points gives zero widths and flat rectangles.
+use crate::geometry::predicates::orient2d;
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+/// Two parallel lines enclosing the hull, one through `edge` and the other through `vertex`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Strip {
+    pub edge: [Vec2; 2],
+    pub vertex: Vec2,
+    pub width: f32,
+}
+
+impl Strip {
+    /// Projection of the vertex on the line through the edge.
+    pub fn foot(&self) -> Vec2 {
+        let [a, b] = self.edge.map(|p| p.as_dvec2());
+        let p = self.vertex.as_dvec2();
+        let u = (b - a).try_normalize().unwrap_or(DVec2::ZERO);
+        (a + u * (p - a).dot(u)).as_vec2()
+    }
+}
+
+/// Rectangle with one side flush with a hull edge, corners in counter clockwise order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnclosingRectangle {
+    pub corners: [Vec2; 4],
+}
+
+impl EnclosingRectangle {
+    pub fn area(&self) -> f32 {
+        let [a, b, _, d] = self.corners;
+        a.distance(b) * a.distance(d)
+    }
+
+    pub fn perimeter(&self) -> f32 {
+        let [a, b, _, d] = self.corners;
+        2.0 * (a.distance(b) + a.distance(d))
+    }
+}
+
+/// Index pairs of vertices that admit parallel supporting lines, each pair listed once.
+pub fn antipodal_pairs(hull: &[Vec2]) -> Vec<(usize, usize)> {
+    let n = hull.len();
+    if n < 3 {
+        return (n == 2).then_some((0, 1)).into_iter().collect();
+    }
+    let mut pairs = vec![];
+    let mut j = 1;
+    for i in 0..n {
+        let next_i = (i + 1) % n;
+        // Advance j while the next vertex is farther from edge i
+        while edge_turn(hull, i, j) > 0.0 {
+            j = (j + 1) % n;
+        }
+        pairs.push((i, j));
+        pairs.push((next_i, j));
+        if edge_turn(hull, i, j) == 0.0 {
+            // Edge j is parallel to edge i, both of its end points are antipodal to both of edge i's
+            pairs.push((i, (j + 1) % n));
+            pairs.push((next_i, (j + 1) % n));
+        }
+    }
+    let mut pairs: Vec<(usize, usize)> = pairs
+        .into_iter()
+        .filter(|(a, b)| a != b)
+        .map(|(a, b)| (a.min(b), a.max(b)))
+        .collect();
+    pairs.sort();
+    pairs.dedup();
+    pairs
+}
+
+/// Farthest pair of points.
+pub fn diameter(hull: &[Vec2]) -> Option<(Vec2, Vec2)> {
+    if hull.len() < 3 {
+        return Some((*hull.first()?, *hull.last()?));
+    }
+    antipodal_pairs(hull)
+        .into_iter()
+        .map(|(a, b)| (hull[a], hull[b]))
+        .max_by(|(a, b), (c, d)| {
+            let ab = a.as_dvec2().distance_squared(b.as_dvec2());
+            ab.total_cmp(&c.as_dvec2().distance_squared(d.as_dvec2()))
+        })
+}
+
+/// Narrowest strip enclosing the hull.
+pub fn min_width(hull: &[Vec2]) -> Option<Strip> {
+    let n = hull.len();
+    if n < 3 {
+        return Some(Strip {
+            edge: [*hull.first()?, *hull.last()?],
+            vertex: hull[0],
+            width: 0.0,
+        });
+    }
+    let mut j = 1;
+    (0..n)
+        .map(|i| {
+            while edge_turn(hull, i, j) > 0.0 {
+                j = (j + 1) % n;
+            }
+            let (a, b) = (hull[i].as_dvec2(), hull[(i + 1) % n].as_dvec2());
+            let width = (b - a).normalize().perp_dot(hull[j].as_dvec2() - a);
+            Strip {
+                edge: [hull[i], hull[(i + 1) % n]],
+                vertex: hull[j],
+                width: width as f32,
+            }
+        })
+        .min_by(|s, t| s.width.total_cmp(&t.width))
+}
+
+/// Enclosing rectangle of least area.
+pub fn min_area_rectangle(hull: &[Vec2]) -> Option<EnclosingRectangle> {
+    enclosing_rectangles(hull)
+        .into_iter()
+        .min_by(|(_, a), (_, b)| a.area.total_cmp(&b.area))
+        .map(|(rectangle, _)| rectangle)
+}
+
+/// Enclosing rectangle of least perimeter.
+pub fn min_perimeter_rectangle(hull: &[Vec2]) -> Option<EnclosingRectangle> {
+    enclosing_rectangles(hull)
+        .into_iter()
+        .min_by(|(_, a), (_, b)| a.perimeter.total_cmp(&b.perimeter))
+        .map(|(rectangle, _)| rectangle)
+}
+
+struct Measures {
+    area: f64,
+    perimeter: f64,
+}
+
+/// The rectangle flush with every edge, with its measures computed in f64 for the comparisons.
+fn enclosing_rectangles(hull: &[Vec2]) -> Vec<(EnclosingRectangle, Measures)> {
+    let n = hull.len();
+    if n < 3 {
+        let (Some(&a), Some(&b)) = (hull.first(), hull.last()) else {
+            return vec![];
+        };
+        let flat = EnclosingRectangle {
+            corners: [a, b, b, a],
+        };
+        let measures = Measures {
+            area: 0.0,
+            perimeter: 2.0 * a.as_dvec2().distance(b.as_dvec2()),
+        };
+        return vec![(flat, measures)];
+    }
+    let at = |i: usize| hull[i % n].as_dvec2();
+    // Extreme vertices along the edge (right, left) and away from it (top), found incrementally
+    let (mut right, mut top, mut left) = (1, 1, 1);
+    (0..n)
+        .map(|i| {
+            let origin = at(i);
+            let u = (at(i + 1) - origin).normalize();
+            let v = u.perp();
+            right = right.max(i + 1);
+            while (at(right + 1) - at(right)).dot(u) > 0.0 {
+                right += 1;
+            }
+            top = top.max(right);
+            while (at(top + 1) - at(top)).dot(v) > 0.0 {
+                top += 1;
+            }
+            left = left.max(top);
+            while (at(left + 1) - at(left)).dot(u) < 0.0 {
+                left += 1;
+            }
+            let (low, high) = ((at(left) - origin).dot(u), (at(right) - origin).dot(u));
+            let height = (at(top) - origin).dot(v);
+            let corners = [
+                origin + u * low,
+                origin + u * high,
+                origin + u * high + v * height,
+                origin + u * low + v * height,
+            ];
+            let rectangle = EnclosingRectangle {
+                corners: corners.map(|c| c.as_vec2()),
+            };
+            let measures = Measures {
+                area: (high - low) * height,
+                perimeter: 2.0 * ((high - low) + height),
+            };
+            (rectangle, measures)
+        })
+        .collect()
+}
+
+/// Sign of the turn from edge `i` to edge `j`, positive while vertex `j + 1` is farther from the
+/// line through edge `i` than vertex `j`.
+fn edge_turn(hull: &[Vec2], i: usize, j: usize) -> f64 {
+    let n = hull.len();
+    let edge = |k: usize| hull[(k + 1) % n].as_dvec2() - hull[k].as_dvec2();
+    orient2d(DVec2::ZERO, edge(i), edge(j))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convex::hull::{ConvexHull, MonotoneChain};
+    use crate::geometry::polygon::PolygonWithHoles;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Random point sets with their hulls, some of them with parallel edges from a grid.
+    fn hulls() -> impl Iterator<Item = (Vec<Vec2>, Vec<Vec2>)> {
+        let mut rng = StdRng::seed_from_u64(7);
+        (0..300).map(move |k| {
+            let count = rng.gen_range(3..40);
+            let points: Vec<Vec2> = (0..count)
+                .map(|_| match k % 2 {
+                    0 => Vec2::new(rng.gen_range(-5.0..5.0), rng.gen_range(-2.0..2.0)),
+                    _ => Vec2::new(rng.gen_range(0..6) as f32, rng.gen_range(0..4) as f32),
+                })
+                .collect();
+            let hull = MonotoneChain.hull(&points).unwrap();
+            (points, hull)
+        })
+    }
+
+    fn near(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-4 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    #[test]
+    fn diameter_and_width_by_brute_force() {
+        for (points, hull) in hulls() {
+            let farthest = points
+                .iter()
+                .flat_map(|p| {
+                    points
+                        .iter()
+                        .map(move |q| p.as_dvec2().distance(q.as_dvec2()))
+                })
+                .fold(0.0, f64::max);
+            let (a, b) = diameter(&hull).unwrap();
+            assert!(near(a.as_dvec2().distance(b.as_dvec2()), farthest));
+            let pair = (
+                hull.iter().position(|&p| p == a).unwrap(),
+                hull.iter().position(|&p| p == b).unwrap(),
+            );
+            assert!(antipodal_pairs(&hull).contains(&(pair.0.min(pair.1), pair.0.max(pair.1))));
+
+            // The narrowest strip is flush with a hull edge, at the distance of the farthest point
+            let n = hull.len();
+            let narrowest = (0..n)
+                .map(|i| {
+                    let (a, b) = (hull[i].as_dvec2(), hull[(i + 1) % n].as_dvec2());
+                    points
+                        .iter()
+                        .map(|p| (b - a).normalize().perp_dot(p.as_dvec2() - a))
+                        .fold(0.0, f64::max)
+                })
+                .fold(f64::INFINITY, f64::min);
+            let strip = min_width(&hull).unwrap();
+            assert!(near(strip.width as f64, narrowest), "{strip:?} {narrowest}");
+        }
+    }
+
+    /// Whether the point is on the inner side of every side of the rectangle, up to rounding.
+    fn encloses(rectangle: &EnclosingRectangle, p: Vec2) -> bool {
+        let corners = rectangle.corners.map(|c| c.as_dvec2());
+        (0..4).all(|k| {
+            let (a, b) = (corners[k], corners[(k + 1) % 4]);
+            let side = (b - a).try_normalize().unwrap_or(DVec2::ZERO);
+            side.perp_dot(p.as_dvec2() - a) >= -1e-4
+        })
+    }
+
+    #[test]
+    fn rectangles_by_brute_force() {
+        for (points, hull) in hulls() {
+            // Bounding box of the points along each hull edge
+            let n = hull.len();
+            let boxes: Vec<(f64, f64)> = (0..n)
+                .map(|i| {
+                    let (a, b) = (hull[i].as_dvec2(), hull[(i + 1) % n].as_dvec2());
+                    let u = (b - a).normalize();
+                    let (along, across): (Vec<f64>, Vec<f64>) = points
+                        .iter()
+                        .map(|p| (u.dot(p.as_dvec2() - a), u.perp_dot(p.as_dvec2() - a)))
+                        .unzip();
+                    let extent = |values: Vec<f64>| {
+                        values.iter().fold(f64::NEG_INFINITY, |m, &v| m.max(v))
+                            - values.iter().fold(f64::INFINITY, |m, &v| m.min(v))
+                    };
+                    (extent(along), extent(across))
+                })
+                .collect();
+            let least = |measure: fn(&(f64, f64)) -> f64| {
+                boxes.iter().map(measure).fold(f64::INFINITY, f64::min)
+            };
+
+            let area = PolygonWithHoles::from(hull.clone()).area();
+            let smallest = min_area_rectangle(&hull).unwrap();
+            let shortest = min_perimeter_rectangle(&hull).unwrap();
+            assert!(near(smallest.area() as f64, least(|(w, h)| w * h)));
+            assert!(near(
+                shortest.perimeter() as f64,
+                least(|(w, h)| 2.0 * (w + h))
+            ));
+            for rectangle in [smallest, shortest] {
+                assert!(rectangle.area() >= area * (1.0 - 1e-5));
+                assert!(
+                    points.iter().all(|&p| encloses(&rectangle, p)),
+                    "{rectangle:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn antipodal_pairs_of_a_square() {
+        let square = [Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y];
+        // Adjacent corners too, through the lines along the other sides
+        let all = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
+        assert_eq!(antipodal_pairs(&square), all);
+        let (a, b) = diameter(&square).unwrap();
+        assert_eq!(a.distance_squared(b), 2.0);
+        assert_eq!(min_width(&square).unwrap().width, 1.0);
+        assert_eq!(min_area_rectangle(&square).unwrap().area(), 1.0);
+    }
+
+    #[test]
+    fn tiny_hulls() {
+        assert!(antipodal_pairs(&[]).is_empty());
+        assert_eq!(diameter(&[]), None);
+        assert_eq!(min_width(&[]), None);
+        assert_eq!(min_area_rectangle(&[]), None);
+        assert_eq!(min_perimeter_rectangle(&[]), None);
+
+        let point = [Vec2::ONE];
+        assert!(antipodal_pairs(&point).is_empty());
+        assert_eq!(diameter(&point), Some((Vec2::ONE, Vec2::ONE)));
+        assert_eq!(min_width(&point).unwrap().width, 0.0);
+        let rectangle = min_area_rectangle(&point).unwrap();
+        assert_eq!((rectangle.area(), rectangle.perimeter()), (0.0, 0.0));
+
+        let (a, b) = (Vec2::ZERO, Vec2::new(3.0, 4.0));
+        assert_eq!(antipodal_pairs(&[a, b]), [(0, 1)]);
+        assert_eq!(diameter(&[a, b]), Some((a, b)));
+        let strip = min_width(&[a, b]).unwrap();
+        assert_eq!((strip.width, strip.foot()), (0.0, a));
+        for rectangle in [
+            min_area_rectangle(&[a, b]),
+            min_perimeter_rectangle(&[a, b]),
+        ] {
+            let rectangle = rectangle.unwrap();
+            assert_eq!((rectangle.area(), rectangle.perimeter()), (0.0, 10.0));
+            assert!(encloses(&rectangle, a) && encloses(&rectangle, b));
+        }
+    }
+}
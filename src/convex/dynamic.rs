@@ -0,0 +1,252 @@
+use super::hull::HullError;
+use crate::geometry::predicates::orient2d;
+use bevy::prelude::*;
+use ordered_float::OrderedFloat;
+use std::collections::BTreeMap;
+use std::ops::Bound::{Excluded, Unbounded};
+
+/// Convex hull maintained under point insertion and removal.
+///
+/// The hull is kept as a lower and an upper chain, each a map from x to y, so inserting a point
+/// costs O(log n) amortized. Removing a point that is not a hull vertex costs O(log n). Removing a
+/// hull vertex fills the gap in its chain with the points between its neighbours in x, which
+/// costs O(k log n) for k such points. That is O(n log n) in the worst case, e.g. when a vertex of
+/// a nearly flat chain goes or when the points are removed from the outside in.
+/// [`DynamicHull::hull`] follows the same policy as [`super::hull::ConvexHull`].
+#[derive(Default)]
+pub struct DynamicHull {
+    points: BTreeMap<(OrderedFloat<f32>, OrderedFloat<f32>), usize>,
+    /// Number of points counting duplicates
+    len: usize,
+    lower: Chain,
+    upper: Chain,
+}
+
+impl DynamicHull {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn insert(&mut self, point: Vec2) -> Result<(), HullError> {
+        if !point.is_finite() {
+            return Err(HullError::NonFinite);
+        }
+        *self.points.entry(key(point)).or_default() += 1;
+        self.len += 1;
+        self.lower.insert(point);
+        self.upper.insert(flip(point));
+        Ok(())
+    }
+
+    /// Removes one copy of the point, returns whether it was present.
+    pub fn remove(&mut self, point: Vec2) -> bool {
+        let Some(count) = self.points.get_mut(&key(point)) else {
+            return false;
+        };
+        *count -= 1;
+        self.len -= 1;
+        if *count > 0 {
+            return true;
+        }
+        self.points.remove(&key(point));
+        if self.lower.has_vertex(point) {
+            self.lower.remove(point, &self.points, |p| p);
+        }
+        if self.upper.has_vertex(flip(point)) {
+            self.upper.remove(flip(point), &self.points, flip);
+        }
+        true
+    }
+
+    /// Hull vertices in counter clockwise order starting from the left most (then lowest) point.
+    pub fn hull(&self) -> Vec<Vec2> {
+        let mut hull: Vec<Vec2> = self
+            .lower
+            .vertices()
+            .chain(self.upper.vertices().rev().map(flip))
+            .collect();
+        hull.dedup();
+        if hull.len() > 1 && hull.first() == hull.last() {
+            hull.pop();
+        }
+        hull
+    }
+}
+
+fn key(point: Vec2) -> (OrderedFloat<f32>, OrderedFloat<f32>) {
+    (OrderedFloat(point.x), OrderedFloat(point.y))
+}
+
+/// Mirrors about the x axis, which turns the upper chain into a lower chain.
+fn flip(point: Vec2) -> Vec2 {
+    Vec2::new(point.x, -point.y)
+}
+
+fn orient(o: Vec2, a: Vec2, b: Vec2) -> f64 {
+    orient2d(o.as_dvec2(), a.as_dvec2(), b.as_dvec2())
+}
+
+/// Lower convex chain, strictly convex and sorted by x.
+#[derive(Default)]
+struct Chain(BTreeMap<OrderedFloat<f32>, f32>);
+
+impl Chain {
+    fn before(&self, x: f32) -> Option<Vec2> {
+        let (x, y) = self.0.range(..OrderedFloat(x)).next_back()?;
+        Some(Vec2::new(x.0, *y))
+    }
+
+    fn after(&self, x: f32) -> Option<Vec2> {
+        let (x, y) = self
+            .0
+            .range((Excluded(OrderedFloat(x)), Unbounded))
+            .next()?;
+        Some(Vec2::new(x.0, *y))
+    }
+
+    fn has_vertex(&self, point: Vec2) -> bool {
+        self.0.get(&OrderedFloat(point.x)) == Some(&point.y)
+    }
+
+    fn vertices(&self) -> impl DoubleEndedIterator<Item = Vec2> + '_ {
+        self.0.iter().map(|(x, y)| Vec2::new(x.0, *y))
+    }
+
+    /// Takes out the vertex and puts in the points between its neighbours in x, which are the
+    /// only ones that can come up in its place. `map` takes them to the side of the chain.
+    fn remove(
+        &mut self,
+        p: Vec2,
+        points: &BTreeMap<(OrderedFloat<f32>, OrderedFloat<f32>), usize>,
+        map: fn(Vec2) -> Vec2,
+    ) {
+        let (before, after) = (self.before(p.x), self.after(p.x));
+        self.0.remove(&OrderedFloat(p.x));
+        let low = before.map_or(Unbounded, |a| Excluded(key(Vec2::new(a.x, f32::INFINITY))));
+        let high = after.map_or(Unbounded, |b| {
+            Excluded(key(Vec2::new(b.x, f32::NEG_INFINITY)))
+        });
+        for &(x, y) in points.range((low, high)).map(|(k, _)| k) {
+            self.insert(map(Vec2::new(x.0, y.0)));
+        }
+    }
+
+    fn insert(&mut self, p: Vec2) {
+        if matches!(self.0.get(&OrderedFloat(p.x)), Some(&y) if y <= p.y) {
+            return;
+        }
+        if let (Some(a), Some(b)) = (self.before(p.x), self.after(p.x)) {
+            if orient(a, b, p) >= 0.0 {
+                return;
+            }
+        }
+        self.0.insert(OrderedFloat(p.x), p.y);
+        // Drop the neighbours that no longer make a strict left turn
+        while let Some(a) = self.before(p.x) {
+            match self.before(a.x) {
+                Some(before_a) if orient(before_a, a, p) <= 0.0 => {
+                    self.0.remove(&OrderedFloat(a.x));
+                }
+                _ => break,
+            }
+        }
+        while let Some(b) = self.after(p.x) {
+            match self.after(b.x) {
+                Some(after_b) if orient(p, b, after_b) <= 0.0 => {
+                    self.0.remove(&OrderedFloat(b.x));
+                }
+                _ => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convex::hull::{ConvexHull, MonotoneChain};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Inserts and removes random points, checking the hull against `MonotoneChain` on the
+    /// points left after every step.
+    fn check(rng: &mut StdRng, point: impl Fn(&mut StdRng) -> Vec2) {
+        let mut dynamic = DynamicHull::new();
+        let mut points: Vec<Vec2> = vec![];
+        for _ in 0..300 {
+            // Removals of present points and of ones that are not there
+            if !points.is_empty() && rng.gen_bool(0.45) {
+                let i = rng.gen_range(0..points.len());
+                assert!(dynamic.remove(points.swap_remove(i)));
+            } else if rng.gen_bool(0.1) {
+                let p = point(rng);
+                assert_eq!(dynamic.remove(p), points.contains(&p));
+                if let Some(i) = points.iter().position(|&q| q == p) {
+                    points.swap_remove(i);
+                }
+            } else {
+                let p = point(rng);
+                dynamic.insert(p).unwrap();
+                points.push(p);
+            }
+            assert_eq!(dynamic.len(), points.len());
+            assert_eq!(dynamic.is_empty(), points.is_empty());
+            assert_eq!(
+                dynamic.hull(),
+                MonotoneChain.hull(&points).unwrap_or_default(),
+                "{points:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn agrees_with_monotone_chain() {
+        let mut rng = StdRng::seed_from_u64(4);
+        for _ in 0..50 {
+            // On a small grid, with duplicates, collinear points and vertical runs
+            check(&mut rng, |rng| {
+                Vec2::new(rng.gen_range(0..6) as f32, rng.gen_range(0..6) as f32)
+            });
+            check(&mut rng, |rng| {
+                Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0))
+            });
+        }
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        let mut dynamic = DynamicHull::new();
+        assert!(dynamic.hull().is_empty());
+        assert_eq!(
+            dynamic.insert(Vec2::new(f32::NAN, 0.0)),
+            Err(HullError::NonFinite)
+        );
+        assert!(dynamic.is_empty());
+        assert!(!dynamic.remove(Vec2::ZERO));
+        for _ in 0..3 {
+            dynamic.insert(Vec2::ONE).unwrap();
+        }
+        assert_eq!(dynamic.hull(), [Vec2::ONE]);
+        assert!(dynamic.remove(Vec2::ONE));
+        assert_eq!(dynamic.len(), 2);
+        assert_eq!(dynamic.hull(), [Vec2::ONE]);
+        // The same point with a negative zero
+        dynamic.insert(Vec2::new(-0.0, 1.0)).unwrap();
+        assert!(dynamic.remove(Vec2::new(0.0, 1.0)));
+        assert_eq!(dynamic.hull(), [Vec2::ONE]);
+        dynamic.clear();
+        assert!(dynamic.is_empty());
+        assert!(dynamic.hull().is_empty());
+    }
+}
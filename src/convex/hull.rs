@@ -1,50 +1,423 @@
+use crate::geometry::point::Point;
 use bevy::prelude::*;
+use std::cmp::Ordering;
+use std::fmt;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HullError {
+    Empty,
+    NonFinite,
+    /// A 3d hull needs at least four points that do not lie on a common plane.
+    Coplanar,
+    /// Vertices given as a hull that are not strictly convex in counter clockwise order.
+    NotConvex,
+}
+
+impl fmt::Display for HullError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HullError::Empty => write!(f, "convex hull of an empty point set"),
+            HullError::NonFinite => write!(f, "convex hull of a point set with NaN or infinity"),
+            HullError::Coplanar => write!(f, "3d convex hull of coplanar points"),
+            HullError::NotConvex => write!(f, "vertices of a hull that are not strictly convex"),
+        }
+    }
+}
+
+impl std::error::Error for HullError {}
+
+/// Every implementation follows the same policy so that outputs can be compared directly:
+/// - duplicate points are merged,
+/// - points lying on the boundary between two hull vertices (collinear) are dropped,
+/// - vertices are in counter clockwise order starting from the left most (then lowest) point,
+/// - the polygon is not closed, i.e. the first vertex is not repeated at the end,
+/// - a set of identical points gives one vertex and a set of collinear points gives the two end points.
+///
+/// The points can be of any [`Point`] type, all decisions go through its exact predicates.
+pub trait ConvexHull<P: Point = Vec2> {
+    fn hull(&self, points: &[P]) -> Result<Vec<P>, HullError>;
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct GiftWrapping;
+
+#[derive(Default, Clone, Copy)]
+pub struct GrahamScan;
+
+#[derive(Default, Clone, Copy)]
+pub struct MonotoneChain;
+
+#[derive(Default, Clone, Copy)]
+pub struct QuickHull;
+
+#[derive(Default, Clone, Copy)]
+pub struct Chan;
+
+/// Closed polyline around the points, ready to be drawn as a line strip.
 pub fn algo(points: Vec<Vec2>) -> Vec<Vec2> {
-    // Find the left most point
-    let start = points
-        .iter()
-        .reduce(|left_most, v| if v.x < left_most.x { v } else { left_most })
-        .map(|e| *e)
-        .unwrap_or_default();
-    let finish = points
+    closed(MonotoneChain.hull(&points).unwrap_or_default())
+}
+
+/// Repeats the first vertex at the end.
+pub fn closed<P: Copy>(mut polygon: Vec<P>) -> Vec<P> {
+    if let Some(&first) = polygon.first() {
+        polygon.push(first);
+    }
+    polygon
+}
+
+/// Validated, sorted (by x then y) and deduplicated copy of the input.
+fn prepare<P: Point>(points: &[P]) -> Result<Vec<P>, HullError> {
+    if points.is_empty() {
+        return Err(HullError::Empty);
+    }
+    if points.iter().any(|p| !p.is_finite()) {
+        return Err(HullError::NonFinite);
+    }
+    let mut points = points.to_vec();
+    points.sort_by(P::lexicographic);
+    points.dedup();
+    Ok(points)
+}
+
+/// Whether `q` should replace `best` as the next vertex after `p` while wrapping counter clockwise.
+fn wraps_tighter<P: Point>(p: P, best: P, q: P) -> bool {
+    match P::orient(p, best, q) {
+        Ordering::Less => true,
+        Ordering::Equal => P::compare_distance(p, q, best).is_gt(),
+        Ordering::Greater => false,
+    }
+}
+
+fn monotone_chain<P: Point>(points: &[P]) -> Vec<P> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut hull: Vec<P> = Vec::with_capacity(2 * points.len());
+    for &p in points {
+        while hull.len() >= 2 && P::orient(hull[hull.len() - 2], hull[hull.len() - 1], p).is_le() {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    let lower_len = hull.len() + 1;
+    for &p in points.iter().rev().skip(1) {
+        while hull.len() >= lower_len
+            && P::orient(hull[hull.len() - 2], hull[hull.len() - 1], p).is_le()
+        {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    hull.pop();
+    hull
+}
+
+impl<P: Point> ConvexHull<P> for MonotoneChain {
+    fn hull(&self, points: &[P]) -> Result<Vec<P>, HullError> {
+        Ok(monotone_chain(&prepare(points)?))
+    }
+}
+
+impl<P: Point> ConvexHull<P> for GiftWrapping {
+    fn hull(&self, points: &[P]) -> Result<Vec<P>, HullError> {
+        let points = prepare(points)?;
+        let start = points[0];
+        let mut hull = vec![start];
+        loop {
+            let last = *hull.last().unwrap();
+            let next = points
+                .iter()
+                .copied()
+                .filter(|&p| p != last)
+                .reduce(|best, q| {
+                    if wraps_tighter(last, best, q) {
+                        q
+                    } else {
+                        best
+                    }
+                });
+            match next {
+                Some(next) if next != start => hull.push(next),
+                _ => break,
+            }
+        }
+        Ok(hull)
+    }
+}
+
+impl<P: Point> ConvexHull<P> for GrahamScan {
+    fn hull(&self, points: &[P]) -> Result<Vec<P>, HullError> {
+        let mut points = prepare(points)?;
+        // The pivot is the left most point so every other point is within a half plane around it
+        let pivot = points[0];
+        points[1..].sort_by(|&a, &b| {
+            P::orient(pivot, a, b)
+                .reverse()
+                .then_with(|| P::compare_distance(pivot, a, b))
+        });
+        let mut hull: Vec<P> = Vec::with_capacity(points.len());
+        for p in points {
+            while hull.len() >= 2
+                && P::orient(hull[hull.len() - 2], hull[hull.len() - 1], p).is_le()
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        Ok(hull)
+    }
+}
+
+impl<P: Point> ConvexHull<P> for QuickHull {
+    fn hull(&self, points: &[P]) -> Result<Vec<P>, HullError> {
+        let points = prepare(points)?;
+        let (a, b) = (points[0], points[points.len() - 1]);
+        if a == b {
+            return Ok(vec![a]);
+        }
+        let (below, above): (Vec<P>, Vec<P>) = points
+            .iter()
+            .filter(|&&p| P::orient(a, b, p).is_ne())
+            .partition(|&&p| P::orient(a, b, p).is_lt());
+        let mut hull = vec![a];
+        quick_hull(a, b, below, &mut hull);
+        hull.push(b);
+        quick_hull(b, a, above, &mut hull);
+        Ok(hull)
+    }
+}
+
+/// Pushes the hull vertices strictly between `a` and `b`, given the points strictly right of `a -> b`.
+fn quick_hull<P: Point>(a: P, b: P, right_of_ab: Vec<P>, hull: &mut Vec<P>) {
+    let Some(farthest) = right_of_ab.iter().copied().reduce(|far, p| {
+        // Farther right of a -> b is right of the parallel through far
+        if P::cross(a, b, far, p).is_lt() {
+            p
+        } else {
+            far
+        }
+    }) else {
+        return;
+    };
+    let right_of_a_farthest = right_of_ab
         .iter()
-        .filter(|v| **v != start)
-        .reduce(|all_on_left, v| {
-            let cross = (*all_on_left - start)
-                .extend(0.0)
-                .cross((*v - start).extend(0.0));
-            if cross.z < 0. {
-                v
-            } else {
-                all_on_left
+        .copied()
+        .filter(|&p| P::orient(a, farthest, p).is_lt())
+        .collect();
+    let right_of_farthest_b = right_of_ab
+        .into_iter()
+        .filter(|&p| P::orient(farthest, b, p).is_lt())
+        .collect();
+    quick_hull(a, farthest, right_of_a_farthest, hull);
+    hull.push(farthest);
+    quick_hull(farthest, b, right_of_farthest_b, hull);
+}
+
+impl<P: Point> ConvexHull<P> for Chan {
+    fn hull(&self, points: &[P]) -> Result<Vec<P>, HullError> {
+        let points = prepare(points)?;
+        if points.len() < 3 {
+            return Ok(points);
+        }
+        // Guess the hull size as m = 2^(2^t), squaring the guess until wrapping finishes within m steps
+        let mut m = 4usize;
+        loop {
+            if let Some(hull) = chan_wrap(&points, m) {
+                return Ok(hull);
             }
-        })
-        .map(|e| *e)
-        .unwrap_or(Vec2::ZERO);
-    let mut hull: Vec<_> = vec![start];
-    let mut rem: Vec<_> = points.clone();
-    loop {
-        let last = *hull.last().unwrap();
-        rem = rem.into_iter().filter(|v| *v != last).collect();
-        let next = rem
-            .clone()
-            .into_iter()
-            .reduce(|all_on_right, v| {
-                let cross = ((all_on_right - last).extend(0.0)).cross((v - last).extend(0.0));
-                if cross.z > 0. {
-                    v
-                } else {
-                    all_on_right
+            m = m.saturating_mul(m);
+        }
+    }
+}
+
+fn chan_wrap<P: Point>(points: &[P], m: usize) -> Option<Vec<P>> {
+    // The first group holds the left most point, which is the first vertex of the hull
+    let groups: Vec<Vec<P>> = points.chunks(m).map(monotone_chain).collect();
+    let start = (0, 0);
+    let mut curr = start;
+    let mut hull = vec![groups[0][0]];
+    for _ in 0..m {
+        let p = groups[curr.0][curr.1];
+        let mut best: Option<(usize, usize)> = None;
+        for (g, group) in groups.iter().enumerate() {
+            let candidate = if g == curr.0 {
+                if group.len() == 1 {
+                    continue;
+                }
+                (g, (curr.1 + 1) % group.len())
+            } else {
+                (g, right_tangent(group, p))
+            };
+            best = match best {
+                Some((bg, bi))
+                    if !wraps_tighter(p, groups[bg][bi], groups[candidate.0][candidate.1]) =>
+                {
+                    Some((bg, bi))
                 }
+                _ => Some(candidate),
+            };
+        }
+        let next = best?;
+        if next == start {
+            return Some(hull);
+        }
+        hull.push(groups[next.0][next.1]);
+        curr = next;
+    }
+    None
+}
+
+/// Index of the vertex of the counter clockwise, strictly convex polygon `poly` that is the right
+/// tangent point from `p`, i.e. all of `poly` is on or left of `p -> poly[index]`. Of two collinear
+/// tangent points the farther one is returned. `p` must be strictly outside `poly`.
+pub(crate) fn right_tangent<P: Point>(poly: &[P], p: P) -> usize {
+    tangent(poly.len(), |i| poly[i], p, 1)
+}
+
+/// Like [`right_tangent`] but all of `poly` is on or right of `p -> poly[index]`.
+pub(crate) fn left_tangent<P: Point>(poly: &[P], p: P) -> usize {
+    let n = poly.len();
+    // Walking backwards with the orientation flipped turns the left tangent into a right tangent
+    (n - tangent(n, |i| poly[(n - i) % n], p, -1)) % n
+}
+
+fn tangent<P: Point>(n: usize, vertex: impl Fn(usize) -> P, p: P, orientation: i8) -> usize {
+    let turn = |a: P, b: P| -> i8 { P::orient(p, a, b) as i8 * orientation };
+    if n < 3 {
+        return (0..n)
+            .reduce(|best, i| match turn(vertex(best), vertex(i)) {
+                -1 => i,
+                0 if P::compare_distance(p, vertex(i), vertex(best)).is_gt() => i,
+                _ => best,
             })
-            .map(|e| e)
-            .unwrap_or(Vec2::ZERO);
-        hull.push(next);
-        if next == finish {
-            hull.push(start);
-            break;
+            .unwrap_or(0);
+    }
+    // Sign of edge i as seen from p, the edges facing p (-1) form one contiguous run ending at the tangent
+    let edge = |i: usize| turn(vertex(i % n), vertex((i + 1) % n));
+    let farther_along = |i: usize| -> usize {
+        // Prefer the farther of two collinear tangent points so that boundary points are dropped
+        let j = (i + 1) % n;
+        if edge(i) == 0 && P::compare_distance(p, vertex(j), vertex(i)).is_gt() {
+            j
+        } else {
+            i
+        }
+    };
+    let start = match edge(0) {
+        0 if edge(n - 1) == -1 => return farther_along(0),
+        0 => 1,
+        _ => 0,
+    };
+    let at = |k: usize| (start + k) % n;
+    let reference = vertex(start);
+    let facing_first = edge(start) == -1;
+    // Monotone over k: true strictly before the tangent and false from the tangent onwards
+    let before_tangent = |k: usize| -> bool {
+        if k == 0 {
+            return true;
+        }
+        let (e, side) = (edge(at(k)), turn(reference, vertex(at(k))));
+        if facing_first {
+            e == -1 && side == -1
+        } else {
+            e == -1 || side == 1
+        }
+    };
+    let (mut lo, mut hi) = (0, n);
+    while hi - lo > 1 {
+        let mid = (lo + hi) / 2;
+        if before_tangent(mid) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    farther_along(at(hi))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const ALGORITHMS: [&dyn ConvexHull; 5] = [
+        &GiftWrapping,
+        &GrahamScan,
+        &MonotoneChain,
+        &QuickHull,
+        &Chan,
+    ];
+
+    fn agreed_hull(points: &[Vec2]) -> Result<Vec<Vec2>, HullError> {
+        let hull = MonotoneChain.hull(points);
+        for algorithm in ALGORITHMS {
+            assert_eq!(algorithm.hull(points), hull, "{points:?}");
+        }
+        hull
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        assert_eq!(agreed_hull(&[]), Err(HullError::Empty));
+        assert_eq!(agreed_hull(&[Vec2::ONE]), Ok(vec![Vec2::ONE]));
+        assert_eq!(agreed_hull(&[Vec2::ONE; 7]), Ok(vec![Vec2::ONE]));
+        let collinear: Vec<Vec2> = (0..10).rev().map(|i| Vec2::splat(i as f32)).collect();
+        assert_eq!(
+            agreed_hull(&collinear),
+            Ok(vec![Vec2::ZERO, Vec2::splat(9.0)])
+        );
+        let doubled: Vec<Vec2> = collinear.iter().chain(&collinear).copied().collect();
+        assert_eq!(
+            agreed_hull(&doubled),
+            Ok(vec![Vec2::ZERO, Vec2::splat(9.0)])
+        );
+        // Sorting must not separate the zeros, which are the same point
+        let zeros = [Vec2::new(-0.0, 0.0), Vec2::new(-0.0, 1.0), Vec2::ZERO];
+        assert_eq!(agreed_hull(&zeros), Ok(vec![Vec2::ZERO, Vec2::Y]));
+        for bad in [f32::NAN, f32::INFINITY] {
+            let points = [Vec2::ZERO, Vec2::X, Vec2::new(bad, 1.0)];
+            assert_eq!(agreed_hull(&points), Err(HullError::NonFinite));
+        }
+    }
+
+    #[test]
+    fn collinear_runs_and_duplicates() {
+        // Square with every side subdivided and every point repeated
+        let side: Vec<f32> = (0..=4).map(|i| i as f32).collect();
+        let mut points = vec![];
+        for &t in &side {
+            points.extend([
+                Vec2::new(t, 0.0),
+                Vec2::new(4.0, t),
+                Vec2::new(t, 4.0),
+                Vec2::new(0.0, t),
+                Vec2::new(2.0, 2.0),
+            ]);
+        }
+        points.extend(points.clone());
+        let square = [
+            Vec2::ZERO,
+            Vec2::new(4.0, 0.0),
+            Vec2::splat(4.0),
+            Vec2::new(0.0, 4.0),
+        ];
+        assert_eq!(agreed_hull(&points), Ok(square.to_vec()));
+    }
+
+    #[test]
+    fn random_grids_agree() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..500 {
+            let count = rng.gen_range(1..100);
+            let points: Vec<Vec2> = (0..count)
+                .map(|_| Vec2::new(rng.gen_range(0..6) as f32, rng.gen_range(0..6) as f32))
+                .collect();
+            let hull = agreed_hull(&points).unwrap();
+            for i in 0..hull.len() {
+                let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+                assert!(points.iter().all(|&p| !Vec2::orient(a, b, p).is_lt()));
+            }
         }
     }
-    return hull;
 }
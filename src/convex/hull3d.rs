@@ -0,0 +1,300 @@
+use super::hull::HullError;
+use crate::geometry::predicates::{orient2d, orient3d};
+use bevy::math::DVec3;
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Convex hull of a 3d point set as a closed triangle mesh.
+/// Faces are counter clockwise when seen from outside, so their normals point outwards.
+/// Duplicate points are merged. Faces lying in a common plane are not merged, so a point on the
+/// boundary of the hull may be kept as a vertex when it was needed to triangulate a flat side.
+pub struct Hull3d {
+    pub vertices: Vec<Vec3>,
+    pub faces: Vec<[usize; 3]>,
+}
+
+struct Face {
+    corners: [usize; 3],
+    outside: Vec<usize>,
+    alive: bool,
+}
+
+impl Hull3d {
+    /// Quickhull: repeatedly lift the farthest outside point of a face into the hull.
+    pub fn new(points: &[Vec3]) -> Result<Self, HullError> {
+        if points.is_empty() {
+            return Err(HullError::Empty);
+        }
+        if points.iter().any(|p| !p.is_finite()) {
+            return Err(HullError::NonFinite);
+        }
+        // Adding zero turns -0.0 into 0.0, so that the sort keeps equal points together
+        let mut points: Vec<DVec3> = points.iter().map(|p| p.as_dvec3() + 0.0).collect();
+        points.sort_by(|a, b| {
+            a.x.total_cmp(&b.x)
+                .then(a.y.total_cmp(&b.y))
+                .then(a.z.total_cmp(&b.z))
+        });
+        points.dedup();
+        let [a, b, c, d] = initial_tetrahedron(&points).ok_or(HullError::Coplanar)?;
+
+        let mut faces: Vec<Face> = vec![];
+        let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+        let outer = |[a, b, c]: [usize; 3], inner: usize| {
+            if orient3d(points[a], points[b], points[c], points[inner]) > 0.0 {
+                [a, b, c]
+            } else {
+                [a, c, b]
+            }
+        };
+        for (corners, inner) in [
+            ([a, b, c], d),
+            ([a, b, d], c),
+            ([a, c, d], b),
+            ([b, c, d], a),
+        ] {
+            add_face(&mut faces, &mut edges, outer(corners, inner));
+        }
+        let unassigned = (0..points.len()).filter(|&i| ![a, b, c, d].contains(&i));
+        assign(&points, &mut faces, 0..4, unassigned);
+
+        let mut pending: Vec<usize> = (0..4).collect();
+        while let Some(f) = pending.pop() {
+            if !faces[f].alive || faces[f].outside.is_empty() {
+                continue;
+            }
+            let [a, b, c] = faces[f].corners.map(|i| points[i]);
+            let apex = *faces[f]
+                .outside
+                .iter()
+                .max_by(|&&p, &&q| {
+                    (-orient3d(a, b, c, points[p])).total_cmp(&-orient3d(a, b, c, points[q]))
+                })
+                .unwrap();
+
+            // The faces visible from the apex form a connected region, its boundary is the horizon
+            let sees = |face: &Face| {
+                let [a, b, c] = face.corners.map(|i| points[i]);
+                orient3d(a, b, c, points[apex]) < 0.0
+            };
+            let mut visible = HashSet::from([f]);
+            let mut stack = vec![f];
+            let mut horizon = vec![];
+            while let Some(g) = stack.pop() {
+                let [a, b, c] = faces[g].corners;
+                for (u, v) in [(a, b), (b, c), (c, a)] {
+                    let h = edges[&(v, u)];
+                    if visible.contains(&h) {
+                        continue;
+                    }
+                    if sees(&faces[h]) {
+                        visible.insert(h);
+                        stack.push(h);
+                    } else {
+                        horizon.push((u, v));
+                    }
+                }
+            }
+
+            let mut orphans = vec![];
+            for &g in &visible {
+                let [a, b, c] = faces[g].corners;
+                for edge in [(a, b), (b, c), (c, a)] {
+                    edges.remove(&edge);
+                }
+                faces[g].alive = false;
+                orphans.extend(faces[g].outside.drain(..).filter(|&p| p != apex));
+            }
+            let first_new = faces.len();
+            for (u, v) in horizon {
+                add_face(&mut faces, &mut edges, [u, v, apex]);
+            }
+            let new_faces = first_new..faces.len();
+            assign(&points, &mut faces, new_faces.clone(), orphans);
+            pending.extend(new_faces);
+        }
+
+        let faces: Vec<[usize; 3]> = faces
+            .into_iter()
+            .filter(|face| face.alive)
+            .map(|face| face.corners)
+            .collect();
+        let mut remap = HashMap::new();
+        let mut vertices = vec![];
+        let faces = faces
+            .into_iter()
+            .map(|corners| {
+                corners.map(|i| {
+                    *remap.entry(i).or_insert_with(|| {
+                        vertices.push(points[i].as_vec3());
+                        vertices.len() - 1
+                    })
+                })
+            })
+            .collect();
+        Ok(Self { vertices, faces })
+    }
+
+    pub fn volume(&self) -> f32 {
+        let origin = self.vertices[0].as_dvec3();
+        let six_times_volume: f64 = self
+            .corners()
+            .map(|[a, b, c]| (a - origin).dot((b - origin).cross(c - origin)))
+            .sum();
+        (six_times_volume / 6.0) as f32
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let twice_area: f64 = self
+            .corners()
+            .map(|[a, b, c]| (b - a).cross(c - a).length())
+            .sum();
+        (twice_area / 2.0) as f32
+    }
+
+    /// Points on the boundary are contained.
+    pub fn contains(&self, point: Vec3) -> bool {
+        let point = point.as_dvec3();
+        self.corners()
+            .all(|[a, b, c]| orient3d(a, b, c, point) >= 0.0)
+    }
+
+    fn corners(&self) -> impl Iterator<Item = [DVec3; 3]> + '_ {
+        self.faces
+            .iter()
+            .map(|face| face.map(|i| self.vertices[i].as_dvec3()))
+    }
+}
+
+fn add_face(
+    faces: &mut Vec<Face>,
+    edges: &mut HashMap<(usize, usize), usize>,
+    corners: [usize; 3],
+) {
+    let [a, b, c] = corners;
+    for edge in [(a, b), (b, c), (c, a)] {
+        edges.insert(edge, faces.len());
+    }
+    faces.push(Face {
+        corners,
+        outside: vec![],
+        alive: true,
+    });
+}
+
+/// Moves every point into the outside set of the first face it is strictly above, points that are
+/// above none of the faces are inside the hull and are dropped.
+fn assign(
+    points: &[DVec3],
+    faces: &mut [Face],
+    candidates: std::ops::Range<usize>,
+    unassigned: impl IntoIterator<Item = usize>,
+) {
+    for p in unassigned {
+        let above = candidates.clone().find(|&f| {
+            let [a, b, c] = faces[f].corners.map(|i| points[i]);
+            orient3d(a, b, c, points[p]) < 0.0
+        });
+        if let Some(f) = above {
+            faces[f].outside.push(p);
+        }
+    }
+}
+
+fn initial_tetrahedron(points: &[DVec3]) -> Option<[usize; 4]> {
+    let (a, b) = (0, 1);
+    if points.len() < 4 {
+        return None;
+    }
+    let collinear = |p: DVec3, q: DVec3, r: DVec3| {
+        orient2d(p.xy(), q.xy(), r.xy()) == 0.0
+            && orient2d(p.yz(), q.yz(), r.yz()) == 0.0
+            && orient2d(p.zx(), q.zx(), r.zx()) == 0.0
+    };
+    let c = (2..points.len()).find(|&c| !collinear(points[a], points[b], points[c]))?;
+    let d =
+        (2..points.len()).find(|&d| orient3d(points[a], points[b], points[c], points[d]) != 0.0)?;
+    Some([a, b, c, d])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn cube() -> Vec<Vec3> {
+        (0..8)
+            .map(|i| Vec3::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2) as f32))
+            .collect()
+    }
+
+    #[test]
+    fn unit_cube() {
+        // Centre and a face centre are not vertices, corners given again with y = -0.0 sort apart
+        // from their twins under `total_cmp` and are duplicates all the same
+        let mut points = cube();
+        points.extend([
+            Vec3::splat(0.5),
+            Vec3::new(0.5, 0.5, 0.0),
+            Vec3::new(0.0, -0.0, 0.0),
+            Vec3::new(0.0, -0.0, 1.0),
+        ]);
+        let hull = Hull3d::new(&points).unwrap();
+        assert!((hull.volume() - 1.0).abs() < 1e-6);
+        assert!((hull.surface_area() - 6.0).abs() < 1e-6);
+        for corner in [Vec3::ZERO, Vec3::Z] {
+            assert_eq!(hull.vertices.iter().filter(|&&v| v == corner).count(), 1);
+        }
+        assert!(!hull.contains(Vec3::new(1.0, 1.0, 1.0 + 1e-6)));
+        assert!(!hull.contains(Vec3::new(-1e-6, 0.5, 0.5)));
+    }
+
+    #[test]
+    fn random_grids_contained() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..300 {
+            let count = rng.gen_range(4..60);
+            let points: Vec<Vec3> = (0..count)
+                .map(|_| {
+                    Vec3::new(
+                        rng.gen_range(0..5) as f32,
+                        rng.gen_range(0..5) as f32,
+                        rng.gen_range(0..5) as f32,
+                    )
+                })
+                .collect();
+            let hull = match Hull3d::new(&points) {
+                Ok(hull) => hull,
+                Err(error) => {
+                    assert_eq!(error, HullError::Coplanar);
+                    continue;
+                }
+            };
+            assert!(points.iter().all(|&p| hull.contains(p)), "{points:?}");
+            assert!(hull.volume() > 0.0);
+            // Closed: every directed edge has its reverse in another face
+            let edges: HashSet<(usize, usize)> = hull
+                .faces
+                .iter()
+                .flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)])
+                .collect();
+            assert_eq!(edges.len(), 3 * hull.faces.len());
+            assert!(edges.iter().all(|&(a, b)| edges.contains(&(b, a))));
+        }
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        assert!(matches!(Hull3d::new(&[]), Err(HullError::Empty)));
+        let nan = [Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::new(0.0, 0.0, f32::NAN)];
+        assert!(matches!(Hull3d::new(&nan), Err(HullError::NonFinite)));
+        let square: Vec<Vec3> = cube().into_iter().map(|p| p.with_z(2.0)).collect();
+        assert!(matches!(Hull3d::new(&square), Err(HullError::Coplanar)));
+        let line: Vec<Vec3> = (0..5).map(|i| Vec3::splat(i as f32)).collect();
+        assert!(matches!(Hull3d::new(&line), Err(HullError::Coplanar)));
+        assert!(matches!(
+            Hull3d::new(&cube()[..3]),
+            Err(HullError::Coplanar)
+        ));
+    }
+}
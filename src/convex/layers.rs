@@ -0,0 +1,265 @@
+use super::hull::HullError;
+use crate::geometry::point::Point;
+use bevy::prelude::*;
+
+/// Convex layers (onion peeling): layer 0 is the boundary of the hull of all points, layer 1 the
+/// boundary of the hull of what remains, and so on.
+///
+/// Unlike [`super::hull::ConvexHull`], every point on the boundary of a hull belongs to its layer,
+/// including the collinear ones, so every input point is on exactly one layer. Duplicates are
+/// merged. Each layer is in counter clockwise order starting from its left most (then lowest)
+/// point. The innermost layer may be flat, i.e. a single point or collinear points sorted by x then y.
+pub struct ConvexLayers<P = Vec2> {
+    pub layers: Vec<Vec<P>>,
+}
+
+impl<P: Point> ConvexLayers<P> {
+    /// Peels one layer at a time in O(n) over the presorted points, O(n^2) in the worst case.
+    pub fn new(points: &[P]) -> Result<Self, HullError> {
+        if points.is_empty() {
+            return Err(HullError::Empty);
+        }
+        if points.iter().any(|p| !p.is_finite()) {
+            return Err(HullError::NonFinite);
+        }
+        let mut remaining = points.to_vec();
+        remaining.sort_by(P::lexicographic);
+        remaining.dedup();
+        let mut layers = vec![];
+        while !remaining.is_empty() {
+            let (layer, rest) = peel(&remaining);
+            layers.push(layer);
+            remaining = rest;
+        }
+        Ok(Self { layers })
+    }
+
+    /// Index of the innermost layer whose hull contains the point, boundary included. An input
+    /// point gets the index of its own layer, so small depths rank outliers first.
+    /// `None` if the point is outside all layers.
+    pub fn depth(&self, point: P) -> Option<usize> {
+        // The layers are nested, so containment holds for a prefix of them
+        let contained = self.layers.partition_point(|layer| contains(layer, point));
+        contained.checked_sub(1)
+    }
+}
+
+/// Whether all points of the layer are collinear.
+pub(crate) fn is_flat<P: Point>(layer: &[P]) -> bool {
+    match layer {
+        [first, .., last] => layer.iter().all(|&p| P::orient(*first, *last, p).is_eq()),
+        _ => true,
+    }
+}
+
+fn contains<P: Point>(layer: &[P], point: P) -> bool {
+    if is_flat(layer) {
+        // Flat layers are sorted, so the point is on them if it is on the line and in between
+        let (first, last) = (layer[0], layer[layer.len() - 1]);
+        return P::orient(first, last, point).is_eq()
+            && point.lexicographic(&first).is_ge()
+            && point.lexicographic(&last).is_le();
+    }
+    (0..layer.len()).all(|i| P::orient(layer[i], layer[(i + 1) % layer.len()], point).is_ge())
+}
+
+/// Splits sorted points into the boundary of their hull and the rest, both keeping their order.
+fn peel<P: Point>(points: &[P]) -> (Vec<P>, Vec<P>) {
+    if is_flat(points) {
+        return (points.to_vec(), vec![]);
+    }
+    // Monotone chain that only pops on strict right turns, which keeps collinear boundary points
+    let chain = |order: &mut dyn Iterator<Item = usize>| {
+        let mut chain: Vec<usize> = vec![];
+        for i in order {
+            while chain.len() >= 2
+                && P::orient(
+                    points[chain[chain.len() - 2]],
+                    points[chain[chain.len() - 1]],
+                    points[i],
+                )
+                .is_lt()
+            {
+                chain.pop();
+            }
+            chain.push(i);
+        }
+        chain.pop();
+        chain
+    };
+    let lower = chain(&mut (0..points.len()));
+    let upper = chain(&mut (0..points.len()).rev());
+    let boundary: Vec<usize> = lower.into_iter().chain(upper).collect();
+    let mut on_boundary = vec![false; points.len()];
+    for &i in &boundary {
+        on_boundary[i] = true;
+    }
+    let rest = points
+        .iter()
+        .zip(on_boundary)
+        .filter(|(_, on_boundary)| !on_boundary)
+        .map(|(&p, _)| p)
+        .collect();
+    (boundary.into_iter().map(|i| points[i]).collect(), rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::cmp::Ordering;
+
+    /// Grid points, many of them collinear or repeated.
+    fn grid(rng: &mut StdRng, count: usize, side: i32) -> Vec<Vec2> {
+        (0..count)
+            .map(|_| Vec2::new(rng.gen_range(0..side) as f32, rng.gen_range(0..side) as f32))
+            .collect()
+    }
+
+    /// Peels the points that some line through them and another point has on one side.
+    fn brute_force_layers(points: &[Vec2]) -> Vec<Vec<Vec2>> {
+        let mut remaining = points.to_vec();
+        remaining.sort_by(Point::lexicographic);
+        remaining.dedup();
+        let mut layers = vec![];
+        while !remaining.is_empty() {
+            let supported = |p: Vec2, q: Vec2| {
+                let sides: Vec<Ordering> =
+                    remaining.iter().map(|&r| Vec2::orient(p, q, r)).collect();
+                sides.iter().all(|s| s.is_ge()) || sides.iter().all(|s| s.is_le())
+            };
+            let (layer, rest) = remaining.iter().partition(|&&p| {
+                remaining.len() == 1 || remaining.iter().any(|&q| q != p && supported(p, q))
+            });
+            layers.push(layer);
+            remaining = rest;
+        }
+        layers
+    }
+
+    /// Whether the point is in a triangle, on a segment or at a point of the layer.
+    fn brute_force_contains(layer: &[Vec2], point: Vec2) -> bool {
+        let on_segment = |a: Vec2, b: Vec2| {
+            Vec2::orient(a, b, point).is_eq() && (a - point).dot(b - point) <= 0.0
+        };
+        let in_triangle = |a: Vec2, b: Vec2, c: Vec2| {
+            let sides = [
+                Vec2::orient(a, b, point),
+                Vec2::orient(b, c, point),
+                Vec2::orient(c, a, point),
+            ];
+            sides.iter().all(|s| s.is_ge()) || sides.iter().all(|s| s.is_le())
+        };
+        let pairs = || {
+            layer
+                .iter()
+                .flat_map(|&a| layer.iter().map(move |&b| (a, b)))
+        };
+        pairs().any(|(a, b)| on_segment(a, b))
+            || pairs().any(|(a, b)| {
+                layer
+                    .iter()
+                    .any(|&c| Vec2::orient(a, b, c).is_ne() && in_triangle(a, b, c))
+            })
+    }
+
+    fn check(points: &[Vec2]) -> ConvexLayers {
+        let layers = ConvexLayers::new(points).unwrap();
+        let expected = brute_force_layers(points);
+        assert_eq!(layers.layers.len(), expected.len(), "{points:?}");
+        for (k, layer) in layers.layers.iter().enumerate() {
+            let mut sorted = layer.clone();
+            sorted.sort_by(Point::lexicographic);
+            assert_eq!(sorted, expected[k], "{points:?}");
+            assert_eq!(layer[0], sorted[0]);
+            if is_flat(layer) {
+                assert_eq!(*layer, sorted);
+                assert_eq!(k, layers.layers.len() - 1);
+                continue;
+            }
+            // Counter clockwise and convex, with the collinear points in order along the edges
+            let n = layer.len();
+            for i in 0..n {
+                let (a, b, c) = (layer[i], layer[(i + 1) % n], layer[(i + 2) % n]);
+                match Vec2::orient(a, b, c) {
+                    Ordering::Less => panic!("{layer:?}"),
+                    Ordering::Equal => assert!((b - a).dot(c - b) > 0.0, "{layer:?}"),
+                    Ordering::Greater => {}
+                }
+                assert!(layer.iter().all(|&p| Vec2::orient(a, b, p).is_ge()));
+                // Strictly inside
+                for inner in &layers.layers[k + 1..] {
+                    assert!(inner.iter().all(|&p| Vec2::orient(a, b, p).is_gt()));
+                }
+            }
+        }
+        for &p in points {
+            let layer = expected.iter().position(|layer| layer.contains(&p));
+            assert_eq!(layers.depth(p), layer);
+        }
+        layers
+    }
+
+    #[test]
+    fn agrees_with_brute_force() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for count in [1, 2, 3, 5, 10, 30] {
+            for side in [2, 4, 10] {
+                for _ in 0..30 {
+                    let points = grid(&mut rng, count, side);
+                    let layers = check(&points);
+                    for _ in 0..20 {
+                        let p = IVec2::new(rng.gen_range(-1..=side), rng.gen_range(-1..=side))
+                            .as_vec2();
+                        let depth = layers
+                            .layers
+                            .iter()
+                            .rposition(|layer| brute_force_contains(layer, p));
+                        assert_eq!(layers.depth(p), depth, "{points:?} {p}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn collinear_and_degenerate() {
+        assert!(matches!(
+            ConvexLayers::<Vec2>::new(&[]),
+            Err(HullError::Empty)
+        ));
+        assert!(matches!(
+            ConvexLayers::<Vec2>::new(&[Vec2::ZERO, Vec2::NAN]),
+            Err(HullError::NonFinite)
+        ));
+        // The points along the edges of the square stay on the outer layer
+        let square: Vec<Vec2> = [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (2, 1),
+            (2, 2),
+            (1, 2),
+            (0, 2),
+            (0, 1),
+        ]
+        .map(|(x, y)| Vec2::new(x as f32, y as f32))
+        .to_vec();
+        let layers = check(&[square.clone(), vec![Vec2::ONE], square.clone()].concat());
+        assert_eq!(layers.layers, [square, vec![Vec2::ONE]]);
+        // Collinear points make a single flat layer, sorted
+        let line: Vec<Vec2> = (0..5)
+            .rev()
+            .map(|i| Vec2::new(i as f32, 2.0 * i as f32))
+            .collect();
+        let layers = check(&line);
+        assert_eq!(layers.layers.len(), 1);
+        assert_eq!(layers.layers[0].first(), Some(&Vec2::ZERO));
+        assert_eq!(layers.depth(Vec2::new(0.5, 1.0)), Some(0));
+        assert_eq!(layers.depth(Vec2::new(5.0, 10.0)), None);
+        // A flat innermost layer inside a triangle
+        let nested = [(0, 0), (8, 0), (4, 8), (3, 2), (4, 3), (5, 4)];
+        let layers = check(&nested.map(|(x, y)| Vec2::new(x as f32, y as f32)));
+        assert_eq!(layers.layers[1].len(), 3);
+    }
+}
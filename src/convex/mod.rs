@@ -1,2 +1,7 @@
+pub mod calipers;
+pub mod dynamic;
 pub mod hull;
+pub mod hull3d;
+pub mod layers;
+pub mod polygon;
 pub mod spiral;
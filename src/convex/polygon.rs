@@ -0,0 +1,449 @@
+use super::hull::{left_tangent, right_tangent, ConvexHull, HullError, MonotoneChain};
+use crate::geometry::predicates::orient2d;
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+/// Strictly convex polygon in counter clockwise order starting from its left most (then lowest)
+/// vertex, as produced by [`ConvexHull`]. It may be degenerate, i.e. a single point or a segment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConvexPolygon {
+    vertices: Vec<Vec2>,
+}
+
+impl ConvexPolygon {
+    /// Polygon around the convex hull of the points.
+    pub fn new(points: &[Vec2]) -> Result<Self, HullError> {
+        Ok(Self {
+            vertices: MonotoneChain.hull(points)?,
+        })
+    }
+
+    /// Takes the output of a [`ConvexHull`] without recomputing it. A closed polyline such as the
+    /// one returned by [`super::hull::algo`] is accepted as well, and so is any other first vertex.
+    /// The vertices are checked in O(n) to be strictly convex in counter clockwise order.
+    pub fn from_hull(mut hull: Vec<Vec2>) -> Result<Self, HullError> {
+        if hull.is_empty() {
+            return Err(HullError::Empty);
+        }
+        if hull.iter().any(|v| !v.is_finite()) {
+            return Err(HullError::NonFinite);
+        }
+        if hull.len() > 1 && hull.first() == hull.last() {
+            hull.pop();
+        }
+        let first = (0..hull.len())
+            .min_by(|&i, &j| {
+                hull[i]
+                    .x
+                    .total_cmp(&hull[j].x)
+                    .then(hull[i].y.total_cmp(&hull[j].y))
+            })
+            .unwrap();
+        hull.rotate_left(first);
+        let n = hull.len();
+        let convex = match n {
+            1 => true,
+            2 => hull[0] != hull[1],
+            // Turning left at every vertex and around once, i.e. the vertices after the first
+            // are in order of angle around it
+            _ => {
+                (0..n).all(|i| orient(hull[i], hull[(i + 1) % n], hull[(i + 2) % n]) > 0.0)
+                    && (1..n - 1).all(|i| orient(hull[0], hull[i], hull[i + 1]) > 0.0)
+            }
+        };
+        if !convex {
+            return Err(HullError::NotConvex);
+        }
+        Ok(Self { vertices: hull })
+    }
+
+    pub fn vertices(&self) -> &[Vec2] {
+        &self.vertices
+    }
+
+    pub fn area(&self) -> f32 {
+        (self.twice_signed_area() / 2.0) as f32
+    }
+
+    pub fn perimeter(&self) -> f32 {
+        self.edges()
+            .map(|(a, b)| a.as_dvec2().distance(b.as_dvec2()))
+            .sum::<f64>() as f32
+    }
+
+    /// Centre of mass of the enclosed area, or of the vertices for a degenerate polygon.
+    pub fn centroid(&self) -> Vec2 {
+        let twice_area = self.twice_signed_area();
+        if twice_area == 0.0 {
+            let sum: DVec2 = self.vertices.iter().map(|v| v.as_dvec2()).sum();
+            return (sum / self.vertices.len() as f64).as_vec2();
+        }
+        let origin = self.vertices[0].as_dvec2();
+        let weighted: DVec2 = self
+            .edges()
+            .map(|(a, b)| {
+                let (a, b) = (a.as_dvec2() - origin, b.as_dvec2() - origin);
+                (a + b) * a.perp_dot(b)
+            })
+            .sum();
+        (origin + weighted / (3.0 * twice_area)).as_vec2()
+    }
+
+    /// Whether the point is inside or on the boundary, in O(log n) by locating the point among the
+    /// wedges around the first vertex.
+    pub fn contains(&self, point: Vec2) -> bool {
+        let v = &self.vertices;
+        let n = v.len();
+        if n < 3 {
+            return on_segment(v[0], v[n - 1], point);
+        }
+        if orient(v[0], v[1], point) < 0.0 || orient(v[0], v[n - 1], point) > 0.0 {
+            return false;
+        }
+        let wedge = 1 + v[1..].partition_point(|&vi| orient(v[0], vi, point) >= 0.0);
+        if wedge == n {
+            return on_segment(v[0], v[n - 1], point);
+        }
+        orient(v[wedge - 1], v[wedge], point) >= 0.0
+    }
+
+    /// Indices of the vertices touched by the two tangent lines from an outside point, as
+    /// `(left, right)` where the polygon lies right of `point -> left` and left of `point -> right`.
+    /// The lookup is O(log n). `None` if the point is inside or on the boundary.
+    pub fn tangents(&self, point: Vec2) -> Option<(usize, usize)> {
+        if self.contains(point) {
+            return None;
+        }
+        Some((
+            left_tangent(&self.vertices, point),
+            right_tangent(&self.vertices, point),
+        ))
+    }
+
+    /// Intersection by clipping one polygon against each edge of the other, O(n m).
+    /// `None` if the polygons are disjoint.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let (subject, clip) = if other.vertices.len() >= 3 {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let points: Vec<Vec2> = if clip.vertices.len() >= 3 {
+            clip.edges()
+                .fold(subject.vertices.clone(), |subject, (a, b)| {
+                    clip_by_half_plane(&subject, a, b)
+                })
+        } else {
+            // Both are points or segments
+            let mut points: Vec<Vec2> = subject
+                .vertices
+                .iter()
+                .copied()
+                .filter(|&p| clip.contains(p))
+                .chain(
+                    clip.vertices
+                        .iter()
+                        .copied()
+                        .filter(|&p| subject.contains(p)),
+                )
+                .collect();
+            if let ([a, b], [c, d]) = (&subject.vertices[..], &clip.vertices[..]) {
+                if orient(*a, *b, *c) * orient(*a, *b, *d) < 0.0
+                    && orient(*c, *d, *a) * orient(*c, *d, *b) < 0.0
+                {
+                    points.push(crossing(*a, *b, *c, *d));
+                }
+            }
+            points
+        };
+        Self::new(&points).ok()
+    }
+
+    /// Minkowski sum by merging the edges of both polygons in angular order in O(n + m). The
+    /// merged vertices then go through [`MonotoneChain`] in O((n + m) log(n + m)), which drops
+    /// those between parallel edges and those that rounding the sums to f32 made concave. Fails
+    /// with [`HullError::NonFinite`] when coordinates of the sum overflow.
+    pub fn minkowski_sum(&self, other: &Self) -> Result<Self, HullError> {
+        let (p, q) = (&self.vertices, &other.vertices);
+        if p.len() < 3 || q.len() < 3 {
+            let sums: Vec<Vec2> = p
+                .iter()
+                .flat_map(|&a| q.iter().map(move |&b| a + b))
+                .collect();
+            return Self::new(&sums);
+        }
+        let (i0, j0) = (lowest(p), lowest(q));
+        let p_at = |i: usize| p[(i0 + i) % p.len()];
+        let q_at = |j: usize| q[(j0 + j) % q.len()];
+        let (mut i, mut j) = (0, 0);
+        let mut sum = Vec::with_capacity(p.len() + q.len());
+        while i < p.len() || j < q.len() {
+            sum.push(p_at(i) + q_at(j));
+            let turn = orient2d(
+                DVec2::ZERO,
+                (p_at(i + 1) - p_at(i)).as_dvec2(),
+                (q_at(j + 1) - q_at(j)).as_dvec2(),
+            );
+            if turn >= 0.0 && i < p.len() {
+                i += 1;
+            }
+            if turn <= 0.0 && j < q.len() {
+                j += 1;
+            }
+        }
+        Self::new(&sum)
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        let n = self.vertices.len();
+        (0..n).map(move |i| (self.vertices[i], self.vertices[(i + 1) % n]))
+    }
+
+    fn twice_signed_area(&self) -> f64 {
+        let origin = self.vertices[0].as_dvec2();
+        self.edges()
+            .map(|(a, b)| (a.as_dvec2() - origin).perp_dot(b.as_dvec2() - origin))
+            .sum()
+    }
+}
+
+fn orient(o: Vec2, a: Vec2, b: Vec2) -> f64 {
+    orient2d(o.as_dvec2(), a.as_dvec2(), b.as_dvec2())
+}
+
+fn on_segment(a: Vec2, b: Vec2, p: Vec2) -> bool {
+    orient(a, b, p) == 0.0 && p.cmpge(a.min(b)).all() && p.cmple(a.max(b)).all()
+}
+
+/// Index of the lowest (then left most) vertex.
+fn lowest(vertices: &[Vec2]) -> usize {
+    (0..vertices.len())
+        .min_by(|&i, &j| {
+            let (a, b) = (vertices[i], vertices[j]);
+            a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+        })
+        .unwrap()
+}
+
+/// Point where the lines through `a, b` and `c, d` cross, the lines must not be parallel.
+fn crossing(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> Vec2 {
+    let (sa, sb) = (orient(c, d, a), orient(c, d, b));
+    a.lerp(b, (sa / (sa - sb)) as f32)
+}
+
+/// Sutherland-Hodgman step, keeps the part of the closed polyline on or left of `a -> b`.
+fn clip_by_half_plane(polygon: &[Vec2], a: Vec2, b: Vec2) -> Vec<Vec2> {
+    let mut clipped = vec![];
+    for (i, &curr) in polygon.iter().enumerate() {
+        let next = polygon[(i + 1) % polygon.len()];
+        let (curr_side, next_side) = (orient(a, b, curr), orient(a, b, next));
+        if curr_side >= 0.0 {
+            clipped.push(curr);
+        }
+        if curr_side * next_side < 0.0 {
+            clipped.push(crossing(curr, next, a, b));
+        }
+    }
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::boolean;
+    use crate::geometry::polygon::PolygonWithHoles;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Hulls of grid points, degenerate ones included.
+    fn random_polygon(rng: &mut StdRng, side: i32) -> ConvexPolygon {
+        let count = rng.gen_range(1..20);
+        let points: Vec<Vec2> = (0..count)
+            .map(|_| IVec2::new(rng.gen_range(0..side), rng.gen_range(0..side)).as_vec2())
+            .collect();
+        ConvexPolygon::new(&points).unwrap()
+    }
+
+    fn brute_force_contains(polygon: &ConvexPolygon, point: Vec2) -> bool {
+        match *polygon.vertices() {
+            [a] => a == point,
+            [a, b] => on_segment(a, b, point),
+            _ => polygon.edges().all(|(a, b)| orient(a, b, point) >= 0.0),
+        }
+    }
+
+    #[test]
+    fn contains_and_tangents() {
+        let mut rng = StdRng::seed_from_u64(6);
+        for _ in 0..500 {
+            let polygon = random_polygon(&mut rng, 8);
+            let v = polygon.vertices();
+            // Every grid point around, so many are on the boundary
+            for x in -1..=8 {
+                for y in -1..=8 {
+                    let p = Vec2::new(x as f32, y as f32);
+                    let inside = brute_force_contains(&polygon, p);
+                    assert_eq!(polygon.contains(p), inside, "{v:?} {p}");
+                    let tangents = polygon.tangents(p);
+                    assert_eq!(tangents.is_none(), inside, "{v:?} {p}");
+                    if let Some((left, right)) = tangents {
+                        assert!(v.iter().all(|&q| orient(p, v[left], q) <= 0.0), "{v:?} {p}");
+                        assert!(
+                            v.iter().all(|&q| orient(p, v[right], q) >= 0.0),
+                            "{v:?} {p}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn area_perimeter_and_centroid() {
+        let mut rng = StdRng::seed_from_u64(6);
+        for _ in 0..500 {
+            let polygon = random_polygon(&mut rng, 100);
+            let v = polygon.vertices();
+            let area = PolygonWithHoles::from(v.to_vec()).area();
+            assert_eq!(polygon.area(), area);
+            let perimeter: f32 = polygon.edges().map(|(a, b)| a.distance(b)).sum();
+            assert!((polygon.perimeter() - perimeter).abs() <= 1e-4 * perimeter);
+            // Fan of triangles from the first vertex, weighted by area
+            let (mut weighted, mut total) = (DVec2::ZERO, 0.0);
+            for i in 1..v.len().saturating_sub(1) {
+                let [a, b, c] = [v[0], v[i], v[i + 1]].map(|p| p.as_dvec2());
+                let area = (b - a).perp_dot(c - a) / 2.0;
+                weighted += (a + b + c) / 3.0 * area;
+                total += area;
+            }
+            let centroid = if total > 0.0 {
+                weighted / total
+            } else {
+                v.iter().map(|p| p.as_dvec2()).sum::<DVec2>() / v.len() as f64
+            };
+            assert!(
+                polygon.centroid().as_dvec2().distance(centroid) < 1e-3,
+                "{v:?}"
+            );
+            if v.len() >= 3 {
+                assert!(polygon.contains(polygon.centroid()));
+            }
+        }
+    }
+
+    #[test]
+    fn intersection_agrees_with_boolean() {
+        let mut rng = StdRng::seed_from_u64(6);
+        for _ in 0..1000 {
+            let (a, b) = (random_polygon(&mut rng, 10), random_polygon(&mut rng, 10));
+            let expected: f32 = boolean::intersection(
+                &[PolygonWithHoles::from(a.vertices().to_vec())],
+                &[PolygonWithHoles::from(b.vertices().to_vec())],
+            )
+            .iter()
+            .map(|p| p.area())
+            .sum();
+            let intersection = a.intersection(&b);
+            let area = intersection.as_ref().map_or(0.0, |p| p.area());
+            assert!((area - expected).abs() < 1e-3, "{a:?} {b:?}");
+            // Any point in both is in the intersection, and only those. Crossings are rounded, so
+            // points on the boundaries may go either way
+            let on_boundary = |polygon: &ConvexPolygon, p: Vec2| {
+                polygon.edges().any(|(a, b)| on_segment(a, b, p))
+            };
+            for x in 0..10 {
+                for y in 0..10 {
+                    let p = Vec2::new(x as f32, y as f32);
+                    if on_boundary(&a, p) || on_boundary(&b, p) {
+                        continue;
+                    }
+                    let both = a.contains(p) && b.contains(p);
+                    if let Some(intersection) = &intersection {
+                        assert_eq!(intersection.contains(p), both, "{a:?} {b:?} {p}");
+                    } else {
+                        assert!(!both);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn disjoint_and_touching_intersections() {
+        let square = |x: f32, y: f32| {
+            ConvexPolygon::new(
+                &[Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y].map(|p| p + Vec2::new(x, y)),
+            )
+            .unwrap()
+        };
+        assert_eq!(square(0.0, 0.0).intersection(&square(2.0, 0.0)), None);
+        assert_eq!(square(0.0, 0.0).intersection(&square(1.0, 1.5)), None);
+        // Along an edge, at a corner, and a segment across
+        let edge = square(0.0, 0.0).intersection(&square(1.0, 0.0)).unwrap();
+        assert_eq!(edge.vertices(), [Vec2::X, Vec2::ONE]);
+        let corner = square(0.0, 0.0).intersection(&square(1.0, 1.0)).unwrap();
+        assert_eq!(corner.vertices(), [Vec2::ONE]);
+        let segment = ConvexPolygon::new(&[Vec2::new(-1.0, 0.5), Vec2::new(2.0, 0.5)]).unwrap();
+        let across = square(0.0, 0.0).intersection(&segment).unwrap();
+        assert_eq!(across, segment.intersection(&square(0.0, 0.0)).unwrap());
+        let ends = [Vec2::new(0.0, 0.5), Vec2::new(1.0, 0.5)];
+        assert_eq!(across.vertices().len(), 2);
+        for (&end, expected) in across.vertices().iter().zip(ends) {
+            assert!(end.distance(expected) < 1e-6, "{end}");
+        }
+    }
+
+    #[test]
+    fn from_hull_checks_convexity() {
+        let square = vec![Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y];
+        let polygon = ConvexPolygon::from_hull(square.clone()).unwrap();
+        assert_eq!(polygon.vertices(), square);
+        let closed_from_elsewhere = vec![Vec2::ONE, Vec2::Y, Vec2::ZERO, Vec2::X, Vec2::ONE];
+        assert_eq!(ConvexPolygon::from_hull(closed_from_elsewhere), Ok(polygon));
+        for bad in [
+            vec![Vec2::ZERO, Vec2::Y, Vec2::ONE, Vec2::X],
+            vec![Vec2::ZERO, Vec2::X, Vec2::new(2.0, 0.0), Vec2::ONE],
+            vec![Vec2::ZERO, Vec2::ONE, Vec2::X, Vec2::Y],
+            vec![
+                Vec2::ZERO,
+                Vec2::X,
+                Vec2::ONE,
+                Vec2::Y,
+                Vec2::ZERO,
+                Vec2::X,
+                Vec2::ONE,
+                Vec2::Y,
+            ],
+            vec![Vec2::ONE, Vec2::ONE, Vec2::ONE],
+        ] {
+            assert_eq!(ConvexPolygon::from_hull(bad), Err(HullError::NotConvex));
+        }
+        let nan = vec![Vec2::ZERO, Vec2::new(f32::NAN, 0.0), Vec2::Y];
+        assert_eq!(ConvexPolygon::from_hull(nan), Err(HullError::NonFinite));
+    }
+
+    #[test]
+    fn minkowski_sum_of_degenerate_polygons() {
+        let segment = ConvexPolygon::new(&[Vec2::ZERO, Vec2::ONE]).unwrap();
+        let collinear = ConvexPolygon::new(&[Vec2::splat(2.0), Vec2::splat(5.0)]).unwrap();
+        let sum = segment.minkowski_sum(&collinear).unwrap();
+        assert_eq!(sum.vertices(), [Vec2::splat(2.0), Vec2::splat(6.0)]);
+        let point = ConvexPolygon::new(&[Vec2::X]).unwrap();
+        assert_eq!(
+            point.minkowski_sum(&point).unwrap().vertices(),
+            [Vec2::new(2.0, 0.0)]
+        );
+        let huge = ConvexPolygon::new(&[Vec2::splat(f32::MAX), Vec2::ZERO, Vec2::X]).unwrap();
+        assert_eq!(huge.minkowski_sum(&huge), Err(HullError::NonFinite));
+        let square = ConvexPolygon::new(&[Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y]).unwrap();
+        let sum = square.minkowski_sum(&square).unwrap();
+        assert_eq!(
+            sum.vertices(),
+            [
+                Vec2::ZERO,
+                Vec2::new(2.0, 0.0),
+                Vec2::splat(2.0),
+                Vec2::new(0.0, 2.0)
+            ]
+        );
+    }
+}
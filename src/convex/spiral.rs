@@ -1,34 +1,85 @@
+use super::layers::{is_flat, ConvexLayers};
+use crate::geometry::point::Point;
 use bevy::prelude::*;
+use std::cmp::Ordering;
 
+/// Polyline through all the points that never crosses itself, spiralling inwards from the left
+/// most point. Empty for empty or non finite input. Other point types go through
+/// [`from_layers`].
 pub fn algo(points: Vec<Vec2>) -> Vec<Vec2> {
-    // Find the left most point
-    let start = points
-        .iter()
-        .reduce(|left_most, v| if v.x < left_most.x { v } else { left_most })
-        .map(|e| *e)
-        .unwrap_or(Vec2::ZERO);
-    let mut spiral: Vec<_> = vec![start];
-    let mut rem: Vec<_> = points.clone();
-    loop {
-        let last = *spiral.last().unwrap();
-        rem = rem.into_iter().filter(|v| *v != last).collect();
-        if rem.len() == 0 {
-            break;
+    match ConvexLayers::new(&points) {
+        Ok(layers) => from_layers(&layers),
+        Err(_) => vec![],
+    }
+}
+
+/// Walks every layer counter clockwise except for its last edge, then steps to the next layer.
+///
+/// The walk along a layer stays on the boundary of its hull and every following layer is strictly
+/// inside that hull. The step from the end of a walk enters the next layer at its right tangent
+/// point, so it touches the next layer only there and the walk along it never comes back to the
+/// step. Hence the polyline is simple.
+pub fn from_layers<P: Point>(layers: &ConvexLayers<P>) -> Vec<P> {
+    let mut spiral: Vec<P> = vec![];
+    for layer in &layers.layers {
+        let start = match spiral.last() {
+            None => 0,
+            Some(&from) => entry(layer, from),
+        };
+        if is_flat(layer) && start != 0 {
+            spiral.extend(layer.iter().rev());
+        } else {
+            spiral.extend(layer[start..].iter().chain(&layer[..start]));
         }
-        let next = rem
-            .clone()
-            .into_iter()
-            .reduce(|all_on_right, v| {
-                let cross = ((all_on_right - last).extend(0.0)).cross((v - last).extend(0.0));
-                if cross.z > 0. {
-                    v
-                } else {
-                    all_on_right
+    }
+    spiral
+}
+
+/// Index of the right tangent point of the layer seen from outside, the nearer one on ties.
+fn entry<P: Point>(layer: &[P], from: P) -> usize {
+    (0..layer.len())
+        .reduce(|best, i| match P::orient(from, layer[best], layer[i]) {
+            Ordering::Less => i,
+            Ordering::Equal if P::compare_distance(from, layer[i], layer[best]).is_lt() => i,
+            _ => best,
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::sweep::is_self_intersecting;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn check(points: &[Vec2]) {
+        let spiral = algo(points.to_vec());
+        let mut sorted = spiral.clone();
+        sorted.sort_by(Point::lexicographic);
+        let mut expected = points.to_vec();
+        expected.sort_by(Point::lexicographic);
+        expected.dedup();
+        // Every point once, starting from the left most
+        assert_eq!(sorted, expected, "{points:?}");
+        assert_eq!(spiral.first(), expected.first());
+        assert!(!is_self_intersecting(&spiral), "{points:?} {spiral:?}");
+    }
+
+    #[test]
+    fn simple_through_all_points() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for count in [1, 2, 3, 5, 10, 30, 100] {
+            for side in [2, 4, 10, 1000] {
+                for _ in 0..30 {
+                    let points: Vec<Vec2> = (0..count)
+                        .map(|_| IVec2::new(rng.gen_range(0..side), rng.gen_range(0..side)))
+                        .map(|p| p.as_vec2())
+                        .collect();
+                    check(&points);
                 }
-            })
-            .map(|e| e)
-            .unwrap_or(Vec2::ZERO);
-        spiral.push(next);
+            }
+        }
+        assert!(algo(vec![]).is_empty());
+        assert!(algo(vec![Vec2::ZERO, Vec2::INFINITY]).is_empty());
     }
-    return spiral;
 }
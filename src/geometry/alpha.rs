@@ -0,0 +1,196 @@
+//! Alpha shapes: the region left after carving away every empty disk of radius alpha, with its
+//! boundary straightened between the points. A large alpha gives the convex hull, a small one
+//! splits the points into clusters and opens holes where the points are sparse.
+use super::polygon::{nest, PolygonWithHoles};
+use crate::convex::hull::HullError;
+use bevy::math::{DVec2, I64Vec2};
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// Boundary of the alpha shape with disks of radius `alpha`, as one polygon per connected
+/// component. Duplicates are merged. Parts of the shape without area, i.e. isolated points and
+/// strands of edges, are left out. A non positive or infinite `alpha` gives no polygons at all.
+///
+/// An edge is on the boundary when exactly one of the two disks of radius `alpha` through its end
+/// points is empty, which is tested against the points within `2 alpha` found with a grid. This is
+/// O(n k^2) for k points within reach of each point, so O(n^3) when `alpha` spans all points.
+pub fn alpha_shape(points: &[Vec2], alpha: f32) -> Result<Vec<PolygonWithHoles>, HullError> {
+    if points.is_empty() {
+        return Err(HullError::Empty);
+    }
+    if points.iter().any(|p| !p.is_finite()) {
+        return Err(HullError::NonFinite);
+    }
+    // Adding zero turns -0.0 into 0.0, so that the sort keeps equal points together
+    let mut points: Vec<Vec2> = points.iter().map(|&p| p + 0.0).collect();
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if !alpha.is_finite() || alpha <= 0.0 {
+        return Ok(vec![]);
+    }
+    let edges = boundary_edges(&points, alpha as f64);
+    let rings = chain(&points, &edges);
+    Ok(nest(
+        rings
+            .iter()
+            .map(|ring| ring.iter().map(|&i| points[i]).collect())
+            .collect(),
+    ))
+}
+
+/// Directed boundary edges with the shape on their left.
+fn boundary_edges(points: &[Vec2], alpha: f64) -> Vec<(usize, usize)> {
+    let cell = |p: Vec2| (p.as_dvec2() / (2.0 * alpha)).floor().as_i64vec2();
+    let mut grid: HashMap<I64Vec2, Vec<usize>> = HashMap::new();
+    for (i, &p) in points.iter().enumerate() {
+        grid.entry(cell(p)).or_default().push(i);
+    }
+    let reach = 4.0 * alpha * alpha;
+    // Points on the boundary of a disk do not make it non empty
+    let inside = alpha * alpha * (1.0 - 1e-9);
+    let mut edges = vec![];
+    for (i, &p) in points.iter().enumerate() {
+        let origin = cell(p);
+        let near: Vec<usize> = (-1..=1)
+            .flat_map(|x| (-1..=1).map(move |y| origin + I64Vec2::new(x, y)))
+            .filter_map(|c| grid.get(&c))
+            .flatten()
+            .copied()
+            .filter(|&k| p.as_dvec2().distance_squared(points[k].as_dvec2()) <= reach)
+            .collect();
+        for &j in near.iter().filter(|&&j| j > i) {
+            let (a, b) = (p.as_dvec2(), points[j].as_dvec2());
+            let half = (b - a) / 2.0;
+            let normal = half.perp().normalize() * (alpha * alpha - half.length_squared()).sqrt();
+            let is_empty = |center: DVec2| {
+                near.iter().all(|&k| {
+                    k == i || k == j || center.distance_squared(points[k].as_dvec2()) >= inside
+                })
+            };
+            match (is_empty(a + half + normal), is_empty(a + half - normal)) {
+                (true, false) => edges.push((j, i)),
+                (false, true) => edges.push((i, j)),
+                _ => {}
+            }
+        }
+    }
+    edges
+}
+
+/// Links the edges into closed rings of point indices. Where several rings touch at a point, each
+/// takes the sharpest left turn so that the rings do not cross.
+fn chain(points: &[Vec2], edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (e, &(a, _)) in edges.iter().enumerate() {
+        outgoing.entry(a).or_default().push(e);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut rings = vec![];
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let mut ring = vec![edges[start].0];
+        let mut edge = start;
+        loop {
+            let (a, b) = edges[edge];
+            let incoming = points[b].as_dvec2() - points[a].as_dvec2();
+            let turn = |e: &usize| {
+                let outgoing = points[edges[*e].1].as_dvec2() - points[b].as_dvec2();
+                incoming.perp_dot(outgoing).atan2(incoming.dot(outgoing))
+            };
+            let next = outgoing
+                .get(&b)
+                .into_iter()
+                .flatten()
+                .filter(|&&e| !used[e] || e == start)
+                .max_by(|e, f| turn(e).total_cmp(&turn(f)));
+            match next {
+                Some(&e) if e == start => {
+                    rings.push(ring);
+                    break;
+                }
+                Some(&e) => {
+                    used[e] = true;
+                    ring.push(b);
+                    edge = e;
+                }
+                // Only reachable through rounding, the open chain is dropped
+                None => break,
+            }
+        }
+    }
+    rings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convex::hull::{ConvexHull, MonotoneChain};
+    use crate::geometry::point::Point;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn grid(keep: impl Fn(Vec2) -> bool, step: f32, size: i32) -> Vec<Vec2> {
+        (-size..=size)
+            .flat_map(|x| (-size..=size).map(move |y| Vec2::new(x as f32, y as f32) * step))
+            .filter(|&p| keep(p))
+            .collect()
+    }
+
+    #[test]
+    fn concave_l() {
+        let points = grid(|p| p.min_element() >= 0.0 && p.min_element() <= 2.0, 1.0, 6);
+        let shapes = alpha_shape(&points, 1.0).unwrap();
+        assert_eq!(shapes.len(), 1);
+        let shape = &shapes[0];
+        assert!(shape.holes.is_empty());
+        // Arms of 12 each sharing 4, and the diagonal across the inner corner fills half a cell
+        assert!((shape.area() - 20.5).abs() < 1e-4, "{}", shape.area());
+        let n = shape.outer.len();
+        assert!((0..n).any(|i| {
+            let [a, b, c] = [i, i + 1, i + 2].map(|j| shape.outer[j % n]);
+            Vec2::orient(a, b, c).is_lt()
+        }));
+    }
+
+    #[test]
+    fn ring_with_hole() {
+        let points = grid(|p| (3.0..=5.0).contains(&p.length()), 0.5, 10);
+        let shapes = alpha_shape(&points, 1.0).unwrap();
+        assert_eq!(shapes.len(), 1);
+        assert_eq!(shapes[0].holes.len(), 1);
+        assert!(!shapes[0].contains(Vec2::ZERO));
+        assert!(shapes[0].contains(Vec2::new(4.0, 0.1)));
+    }
+
+    #[test]
+    fn large_alpha_gives_convex_hull() {
+        let mut rng = StdRng::seed_from_u64(9);
+        for _ in 0..20 {
+            let points: Vec<Vec2> = (0..40)
+                .map(|_| Vec2::new(rng.gen_range(0.0..10.0), rng.gen_range(0.0..10.0)))
+                .collect();
+            let shapes = alpha_shape(&points, 1000.0).unwrap();
+            assert_eq!(shapes.len(), 1);
+            assert!(shapes[0].holes.is_empty());
+            let mut outer = shapes[0].outer.clone();
+            let hull = MonotoneChain.hull(&points).unwrap();
+            let first = outer.iter().position(|&p| p == hull[0]).unwrap();
+            outer.rotate_left(first);
+            assert_eq!(outer, hull);
+        }
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        assert_eq!(alpha_shape(&[], 1.0), Err(HullError::Empty));
+        assert_eq!(alpha_shape(&[Vec2::NAN], 1.0), Err(HullError::NonFinite));
+        let square = [Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y];
+        assert_eq!(alpha_shape(&square, 0.0), Ok(vec![]));
+        assert_eq!(alpha_shape(&square, f32::INFINITY), Ok(vec![]));
+        // A strand of points has no area
+        let line: Vec<Vec2> = (0..5).map(|i| Vec2::X * i as f32).collect();
+        assert_eq!(alpha_shape(&line, 1.0), Ok(vec![]));
+    }
+}
@@ -0,0 +1,436 @@
+//! Union, intersection, difference and XOR of sets of polygons with holes. The edges of both sets
+//! are split where they cross into a planar arrangement, whose faces each lie inside or outside
+//! of either set as a whole. Crossing an edge changes the winding numbers of the sets by the
+//! edges of each running along it, so they follow face by face from the outside of every
+//! connected part. The result is bounded by the edges between faces that are kept and faces
+//! that are not.
+use super::polygon::{nest, winding_number, PolygonWithHoles};
+use super::predicates::orient2d;
+use super::sweep::crossings;
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BooleanOp {
+    Union,
+    Intersection,
+    /// The first set without the second.
+    Difference,
+    Xor,
+}
+
+impl BooleanOp {
+    fn keeps(self, in_first: bool, in_second: bool) -> bool {
+        match self {
+            BooleanOp::Union => in_first || in_second,
+            BooleanOp::Intersection => in_first && in_second,
+            BooleanOp::Difference => in_first && !in_second,
+            BooleanOp::Xor => in_first != in_second,
+        }
+    }
+}
+
+pub fn union(a: &[PolygonWithHoles], b: &[PolygonWithHoles]) -> Vec<PolygonWithHoles> {
+    boolean(a, b, BooleanOp::Union)
+}
+
+pub fn intersection(a: &[PolygonWithHoles], b: &[PolygonWithHoles]) -> Vec<PolygonWithHoles> {
+    boolean(a, b, BooleanOp::Intersection)
+}
+
+pub fn difference(a: &[PolygonWithHoles], b: &[PolygonWithHoles]) -> Vec<PolygonWithHoles> {
+    boolean(a, b, BooleanOp::Difference)
+}
+
+pub fn xor(a: &[PolygonWithHoles], b: &[PolygonWithHoles]) -> Vec<PolygonWithHoles> {
+    boolean(a, b, BooleanOp::Xor)
+}
+
+/// Polygons within a set may overlap, a point is in the set when any of them winds around it.
+/// Hull polylines convert with `PolygonWithHoles::from`. Result polygons can touch each other
+/// and their holes at single points, and have no vertices in the middle of straight edges.
+pub fn boolean(
+    a: &[PolygonWithHoles],
+    b: &[PolygonWithHoles],
+    op: BooleanOp,
+) -> Vec<PolygonWithHoles> {
+    let rings: Vec<(usize, &[Vec2])> = [a, b]
+        .iter()
+        .enumerate()
+        .flat_map(|(set, polygons)| {
+            polygons.iter().flat_map(move |polygon| {
+                std::iter::once(&polygon.outer)
+                    .chain(&polygon.holes)
+                    .map(move |ring| (set, ring.as_slice()))
+            })
+        })
+        .collect();
+    let mut segments = vec![];
+    let mut owners = vec![];
+    for (r, &(_, ring)) in rings.iter().enumerate() {
+        for i in 0..ring.len() {
+            let (p, q) = (ring[i], ring[(i + 1) % ring.len()]);
+            if p != q {
+                segments.push((p, q));
+                owners.push(r);
+            }
+        }
+    }
+    let mut splits: Vec<Vec<Vec2>> = segments.iter().map(|&(p, q)| vec![p, q]).collect();
+    for crossing in crossings(&segments) {
+        for &s in &crossing.segments {
+            splits[s].push(crossing.point);
+        }
+    }
+
+    // Pieces between splits, with how many edges of each set run along them from the lower
+    // numbered vertex to the higher one
+    let mut arrangement = Arrangement::default();
+    let mut counts: HashMap<(usize, usize), [i32; 2]> = HashMap::new();
+    let mut ring_vertex = vec![None; rings.len()];
+    for (s, points) in splits.iter_mut().enumerate() {
+        let (p, q) = segments[s];
+        let along = |x: &Vec2| (*x - p).as_dvec2().dot((q - p).as_dvec2());
+        points.sort_by(|x, y| along(x).total_cmp(&along(y)));
+        points.dedup();
+        let (set, _) = rings[owners[s]];
+        for pair in points.windows(2) {
+            let (u, v) = (arrangement.vertex(pair[0]), arrangement.vertex(pair[1]));
+            if u != v {
+                ring_vertex[owners[s]] = Some(u);
+                counts.entry((u.min(v), u.max(v))).or_default()[set] += if u < v { 1 } else { -1 };
+            }
+        }
+    }
+    let mut edges: Vec<((usize, usize), [i32; 2])> = counts.into_iter().collect();
+    edges.sort_unstable();
+    arrangement.build(edges.iter().map(|&(edge, _)| edge).collect());
+    let count = |h: usize| {
+        let [first, second] = edges[h / 2].1;
+        if h & 1 == 0 {
+            [first, second]
+        } else {
+            [-first, -second]
+        }
+    };
+
+    // Winding numbers of the faces, from the outer face of each connected part, which is only
+    // inside rings of other parts
+    let faces = arrangement.faces();
+    let mut windings: Vec<Option<[i32; 2]>> = vec![None; faces.len()];
+    for (part, vertex, outer) in arrangement.outer_faces() {
+        let point = arrangement.vertices[vertex];
+        let mut winding = [0, 0];
+        for (r, &(set, ring)) in rings.iter().enumerate() {
+            if ring_vertex[r].is_some_and(|v| arrangement.part[v] != part) {
+                winding[set] += winding_number(ring, point.as_dvec2());
+            }
+        }
+        windings[arrangement.face[outer]] = Some(winding);
+        let mut queue = VecDeque::from([arrangement.face[outer]]);
+        while let Some(f) = queue.pop_front() {
+            let winding = windings[f].unwrap();
+            for &h in &faces[f] {
+                let g = arrangement.face[h ^ 1];
+                if windings[g].is_none() {
+                    let [first, second] = count(h);
+                    windings[g] = Some([winding[0] - first, winding[1] - second]);
+                    queue.push_back(g);
+                }
+            }
+        }
+    }
+    let kept: Vec<bool> = windings
+        .iter()
+        .map(|w| w.is_some_and(|[first, second]| op.keeps(first != 0, second != 0)))
+        .collect();
+
+    // Boundary with the kept faces on the left, taking the sharpest left turn where it touches
+    // itself
+    let boundary: Vec<bool> = (0..arrangement.face.len())
+        .map(|h| kept[arrangement.face[h]] && !kept[arrangement.face[h ^ 1]])
+        .collect();
+    let mut used = vec![false; boundary.len()];
+    let mut result = vec![];
+    for start in 0..boundary.len() {
+        if !boundary[start] || used[start] {
+            continue;
+        }
+        let mut ring = vec![];
+        let mut h = start;
+        while !used[h] {
+            used[h] = true;
+            ring.push(arrangement.vertices[arrangement.origin(h)]);
+            h = arrangement.next(h, |g| boundary[g]);
+        }
+        result.push(straighten(ring));
+    }
+    nest(result)
+}
+
+/// Drops the vertices in the middle of straight edges.
+fn straighten(ring: Vec<Vec2>) -> Vec<Vec2> {
+    let n = ring.len();
+    (0..n)
+        .filter(|&i| {
+            let (u, v, w) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+            orient2d(u.as_dvec2(), v.as_dvec2(), w.as_dvec2()) != 0.0
+        })
+        .map(|i| ring[i])
+        .collect()
+}
+
+/// Half edge based planar graph. Edge `e` gives half edges `2 e` from its lower numbered vertex
+/// and `2 e + 1` back, each with the face on its left.
+#[derive(Default)]
+struct Arrangement {
+    vertices: Vec<Vec2>,
+    ids: HashMap<(u32, u32), usize>,
+    edges: Vec<(usize, usize)>,
+    /// Half edges leaving each vertex, counter clockwise
+    outgoing: Vec<Vec<usize>>,
+    /// Index of each half edge among those leaving its origin
+    position: Vec<usize>,
+    face: Vec<usize>,
+    /// Connected part of each vertex
+    part: Vec<usize>,
+}
+
+impl Arrangement {
+    fn vertex(&mut self, point: Vec2) -> usize {
+        // Adding zero turns -0 into 0, so that both get the same vertex
+        let point = point + 0.0;
+        *self
+            .ids
+            .entry((point.x.to_bits(), point.y.to_bits()))
+            .or_insert_with(|| {
+                self.vertices.push(point);
+                self.vertices.len() - 1
+            })
+    }
+
+    fn origin(&self, h: usize) -> usize {
+        let (u, v) = self.edges[h / 2];
+        if h & 1 == 0 {
+            u
+        } else {
+            v
+        }
+    }
+
+    fn direction(&self, h: usize) -> DVec2 {
+        self.vertices[self.origin(h ^ 1)].as_dvec2() - self.vertices[self.origin(h)].as_dvec2()
+    }
+
+    fn build(&mut self, edges: Vec<(usize, usize)>) {
+        self.edges = edges;
+        let mut outgoing = vec![vec![]; self.vertices.len()];
+        for h in 0..2 * self.edges.len() {
+            outgoing[self.origin(h)].push(h);
+        }
+        for list in &mut outgoing {
+            list.sort_by(|&g, &h| counter_clockwise(self.direction(g), self.direction(h)));
+        }
+        self.position = vec![0; 2 * self.edges.len()];
+        for list in &outgoing {
+            for (i, &h) in list.iter().enumerate() {
+                self.position[h] = i;
+            }
+        }
+        self.outgoing = outgoing;
+        self.part = vec![usize::MAX; self.vertices.len()];
+        let mut parts = 0;
+        for start in 0..self.vertices.len() {
+            if self.part[start] != usize::MAX {
+                continue;
+            }
+            self.part[start] = parts;
+            let mut stack = vec![start];
+            while let Some(v) = stack.pop() {
+                for &h in &self.outgoing[v] {
+                    let w = self.origin(h ^ 1);
+                    if self.part[w] == usize::MAX {
+                        self.part[w] = parts;
+                        stack.push(w);
+                    }
+                }
+            }
+            parts += 1;
+        }
+    }
+
+    /// The half edge after `h` around the face on its left among those that pass the filter,
+    /// i.e. the first one clockwise from the way back.
+    fn next(&self, h: usize, filter: impl Fn(usize) -> bool) -> usize {
+        let back = h ^ 1;
+        let list = &self.outgoing[self.origin(back)];
+        (1..=list.len())
+            .map(|k| list[(self.position[back] + list.len() - k) % list.len()])
+            .find(|&g| filter(g))
+            .unwrap_or(back)
+    }
+
+    /// Half edges around each face, and sets the face of every half edge.
+    fn faces(&mut self) -> Vec<Vec<usize>> {
+        self.face = vec![usize::MAX; 2 * self.edges.len()];
+        let mut faces = vec![];
+        for start in 0..self.face.len() {
+            if self.face[start] != usize::MAX {
+                continue;
+            }
+            let mut cycle = vec![];
+            let mut h = start;
+            while self.face[h] == usize::MAX {
+                self.face[h] = faces.len();
+                cycle.push(h);
+                h = self.next(h, |_| true);
+            }
+            faces.push(cycle);
+        }
+        faces
+    }
+
+    /// Each connected part with its leftmost vertex and a half edge leaving it with the outer
+    /// face of the part on its left, which is the one pointing most upwards.
+    fn outer_faces(&self) -> Vec<(usize, usize, usize)> {
+        let mut leftmost: Vec<Option<usize>> = vec![];
+        for (v, &part) in self.part.iter().enumerate() {
+            if part >= leftmost.len() {
+                leftmost.resize(part + 1, None);
+            }
+            let (p, q) = (self.vertices[v], leftmost[part].map(|u| self.vertices[u]));
+            if q.is_none_or(|q| (p.x, p.y) < (q.x, q.y)) {
+                leftmost[part] = Some(v);
+            }
+        }
+        leftmost
+            .into_iter()
+            .enumerate()
+            .filter_map(|(part, v)| Some((part, v?)))
+            .filter(|&(_, v)| !self.outgoing[v].is_empty())
+            .map(|(part, v)| {
+                let upwards = self.outgoing[v]
+                    .iter()
+                    .copied()
+                    .max_by(|&g, &h| {
+                        0.0_f64.total_cmp(&orient2d(
+                            DVec2::ZERO,
+                            self.direction(g),
+                            self.direction(h),
+                        ))
+                    })
+                    .unwrap();
+                (part, v, upwards)
+            })
+            .collect()
+    }
+}
+
+/// Order of directions by angle counter clockwise from the positive x axis.
+fn counter_clockwise(d: DVec2, e: DVec2) -> Ordering {
+    let lower = |d: DVec2| d.y < 0.0 || (d.y == 0.0 && d.x < 0.0);
+    lower(d)
+        .cmp(&lower(e))
+        .then_with(|| 0.0_f64.total_cmp(&orient2d(DVec2::ZERO, d, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convex::hull::algo;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn area(polygons: &[PolygonWithHoles]) -> f32 {
+        polygons.iter().map(PolygonWithHoles::area).sum()
+    }
+
+    fn square(min: Vec2, size: f32) -> PolygonWithHoles {
+        PolygonWithHoles::from(vec![
+            min,
+            min + Vec2::X * size,
+            min + Vec2::splat(size),
+            min + Vec2::Y * size,
+        ])
+    }
+
+    fn assert_near(a: f32, b: f32) {
+        assert!(
+            (a - b).abs() <= 1e-4 * a.abs().max(b.abs()).max(1.0),
+            "{a} != {b}"
+        );
+    }
+
+    #[test]
+    fn area_identities_on_hulls() {
+        let mut rng = StdRng::seed_from_u64(15);
+        let hull = |rng: &mut StdRng| {
+            let center = Vec2::new(rng.gen_range(0.0..4.0), rng.gen_range(0.0..4.0));
+            let points = (0..rng.gen_range(3..12))
+                .map(|_| center + Vec2::new(rng.gen_range(-3.0..3.0), rng.gen_range(-3.0..3.0)))
+                .collect();
+            PolygonWithHoles::from(algo(points))
+        };
+        for _ in 0..200 {
+            let a: Vec<PolygonWithHoles> =
+                (0..rng.gen_range(1..3)).map(|_| hull(&mut rng)).collect();
+            let b: Vec<PolygonWithHoles> =
+                (0..rng.gen_range(1..3)).map(|_| hull(&mut rng)).collect();
+            let [both, common, only_a, either] =
+                [union, intersection, difference, xor].map(|op| area(&op(&a, &b)));
+            let [all_a, all_b] = [&a, &b].map(|set| area(&union(set, &[])));
+            assert_near(both + common, all_a + all_b);
+            assert_near(only_a, all_a - common);
+            assert_near(either, both - common);
+            for polygon in [union, intersection, difference, xor]
+                .iter()
+                .flat_map(|op| op(&a, &b))
+            {
+                assert!(polygon.area() > 0.0);
+                assert!(polygon
+                    .holes
+                    .iter()
+                    .all(|hole| PolygonWithHoles::from(hole.clone()).area() > 0.0));
+            }
+        }
+    }
+
+    #[test]
+    fn shared_edges_and_vertices() {
+        let (a, b) = ([square(Vec2::ZERO, 1.0)], [square(Vec2::X, 1.0)]);
+        let both = union(&a, &b);
+        assert_eq!(both.len(), 1);
+        // The shared edge and the vertices along it are gone
+        assert_eq!(both[0].outer.len(), 4);
+        assert_near(both[0].area(), 2.0);
+        assert!(intersection(&a, &b).is_empty());
+        assert_eq!(difference(&a, &b), a);
+
+        let corner = [square(Vec2::ONE, 1.0)];
+        let both = union(&a, &corner);
+        assert_eq!(both.len(), 2);
+        assert_near(area(&both), 2.0);
+        assert!(intersection(&a, &corner).is_empty());
+
+        assert_eq!(union(&a, &a), a);
+        assert_eq!(intersection(&a, &a), a);
+        assert!(difference(&a, &a).is_empty());
+        assert!(xor(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn difference_makes_a_hole() {
+        let outer = [square(Vec2::ZERO, 4.0)];
+        let inner = [square(Vec2::ONE, 2.0)];
+        let ring = difference(&outer, &inner);
+        assert_eq!(ring.len(), 1);
+        assert_eq!(ring[0].holes.len(), 1);
+        assert_near(ring[0].area(), 12.0);
+        assert!(!ring[0].contains(Vec2::splat(2.0)));
+        assert!(ring[0].contains(Vec2::splat(0.5)));
+        // Filling the hole again gives back the square
+        assert_eq!(union(&ring, &inner), outer);
+        assert!(intersection(&ring, &inner).is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+use super::predicates::orient2d;
+use bevy::math::{DMat2, DVec2};
+use bevy::prelude::*;
+
+/// Line through `p` along `v`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointVecForm {
+    pub p: Vec2,
+    pub v: Vec2,
+}
+
+impl PointVecForm {
+    /// Perpendicular bisector of two points.
+    pub fn bisect(p1: Vec2, p2: Vec2) -> Self {
+        PointVecForm {
+            p: (p1 + p2) / 2.0,
+            v: (p1 - p2).perp(),
+        }
+    }
+
+    /// Intersection with another line, `None` if the lines are parallel.
+    pub fn xn_with(&self, other: &Self) -> Option<Vec2> {
+        // L1: self.p + t_1 * self.v
+        // L2: other.p + t_2 * other.v
+        // self.v.x * t_1 + (-1.0 * other.v.x) * t_2 + (self.p.x - other.p.x) = 0
+        // self.v.y * t_1 + (-1.0 * other.v.y) * t_2 + (self.p.y - other.p.y) = 0
+        let (p1, v1) = (self.p.as_dvec2(), self.v.as_dvec2());
+        let (p2, v2) = (other.p.as_dvec2(), other.v.as_dvec2());
+        let existence_matrix = DMat2::from_cols(v1, -v2);
+        let x_matrix = DMat2::from_cols(p1 - p2, -v2);
+        if orient2d(DVec2::ZERO, v1, v2) == 0.0 {
+            return None;
+        }
+        let t_1 = -x_matrix.determinant() / existence_matrix.determinant();
+        Some((p1 + v1 * t_1).as_vec2())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Disk {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+impl Disk {
+    /// Smallest disk with both points on its boundary.
+    pub fn from_diameter(a: Vec2, b: Vec2) -> Self {
+        let center = (a + b) / 2.0;
+        Self::around(center, &[a, b])
+    }
+
+    /// Smallest disk centered at `center` that contains all the points.
+    pub fn around(center: Vec2, points: &[Vec2]) -> Self {
+        let radius = points
+            .iter()
+            .map(|p| center.as_dvec2().distance(p.as_dvec2()))
+            .fold(0.0, f64::max);
+        Self {
+            center,
+            radius: radius as f32,
+        }
+    }
+
+    /// Points on the boundary are contained, with a small slack that absorbs the rounding of the
+    /// center to f32.
+    pub fn contains(&self, point: Vec2) -> bool {
+        let distance = self.center.as_dvec2().distance(point.as_dvec2());
+        let slack = 1e-5 * self.radius as f64 + 1e-6 * self.center.abs().max_element() as f64;
+        distance <= self.radius as f64 + slack
+    }
+}
+
+/// Circle through three points, where the perpendicular bisectors of two sides meet.
+/// `None` if the points are collinear.
+pub fn circumcircle(a: Vec2, b: Vec2, c: Vec2) -> Option<Disk> {
+    if orient2d(a.as_dvec2(), b.as_dvec2(), c.as_dvec2()) == 0.0 {
+        return None;
+    }
+    let ab_perp = PointVecForm::bisect(a, b);
+    let bc_perp = PointVecForm::bisect(b, c);
+    let center = ab_perp.xn_with(&bc_perp)?;
+    Some(Disk::around(center, &[a, b, c]))
+}
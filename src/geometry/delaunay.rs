@@ -0,0 +1,684 @@
+//! Delaunay triangulation by incremental insertion (Bowyer-Watson). Each point removes the
+//! triangles whose circumcircle contains it and connects itself to the boundary of the hole. While
+//! building, the hull is closed off by ghost triangles that share a vertex at infinity, so points
+//! outside the hull go through the same path as the others.
+use super::predicates::{incircle, orient2d};
+use crate::convex::hull::HullError;
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Vertex at infinity of the ghost triangles.
+const GHOST: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintError {
+    /// An end point is out of range or not a vertex of any triangle, or both ends are the same.
+    NotAVertex,
+    /// The segment crosses a constrained edge.
+    Crossing,
+}
+
+impl fmt::Display for ConstraintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstraintError::NotAVertex => write!(f, "constraint end point is not a vertex"),
+            ConstraintError::Crossing => write!(f, "constraint crosses a constrained edge"),
+        }
+    }
+}
+
+impl std::error::Error for ConstraintError {}
+
+/// Triangulation of a point set where no point is inside the circumcircle of a triangle, apart
+/// from what constrained edges hide from each other. Triangles and edges refer to the points by
+/// their index in the input. Duplicates are merged into their first occurrence, and a set of
+/// collinear points has no triangles at all.
+#[derive(Clone, Debug)]
+pub struct Delaunay {
+    points: Vec<Vec2>,
+    triangles: Vec<[usize; 3]>,
+    neighbors: Vec<[Option<usize>; 3]>,
+    constraints: HashSet<(usize, usize)>,
+    /// Index of the first point equal to each point
+    canonical: Vec<usize>,
+    /// A triangle around each vertex
+    incident: Vec<Option<usize>>,
+}
+
+/// Triangles crossed by a constraint on its way from a vertex to the next vertex on the segment,
+/// with the vertices of the crossed edges on either side.
+struct Crossing {
+    end: usize,
+    triangles: Vec<usize>,
+    left: Vec<usize>,
+    right: Vec<usize>,
+}
+
+impl Delaunay {
+    /// Inserts the points along a Hilbert curve, so each one is found by a short walk from the
+    /// triangles of the previous one.
+    pub fn new(points: &[Vec2]) -> Result<Self, HullError> {
+        if points.is_empty() {
+            return Err(HullError::Empty);
+        }
+        if points.iter().any(|p| !p.is_finite()) {
+            return Err(HullError::NonFinite);
+        }
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        // Finite, so partial_cmp is total and -0.0 sorts next to 0.0
+        order.sort_by(|&i, &j| {
+            let (a, b) = (points[i], points[j]);
+            (a.x.partial_cmp(&b.x).unwrap())
+                .then(a.y.partial_cmp(&b.y).unwrap())
+                .then(i.cmp(&j))
+        });
+        let mut canonical: Vec<usize> = (0..points.len()).collect();
+        for pair in order.windows(2) {
+            if points[pair[0]] == points[pair[1]] {
+                canonical[pair[1]] = canonical[pair[0]];
+            }
+        }
+        order.retain(|&i| canonical[i] == i);
+        let (min, max) = points
+            .iter()
+            .fold((points[0], points[0]), |(min, max), &p| {
+                (min.min(p), max.max(p))
+            });
+        order.sort_by_cached_key(|&i| hilbert((points[i] - min) / (max - min).max_element()));
+        let mut delaunay = Self {
+            points: points.to_vec(),
+            triangles: vec![],
+            neighbors: vec![],
+            constraints: HashSet::new(),
+            canonical,
+            incident: vec![None; points.len()],
+        };
+        let first_turn = (2..order.len())
+            .find(|&k| orient(points[order[0]], points[order[1]], points[order[k]]) != 0.0);
+        let Some(k) = first_turn else {
+            return Ok(delaunay);
+        };
+        delaunay.seed(order[0], order[1], order[k]);
+        let mut free = vec![];
+        let mut last = 0;
+        for (_, &p) in order.iter().enumerate().skip(2).filter(|&(i, _)| i != k) {
+            last = delaunay.insert(p, last, &mut free);
+        }
+        delaunay.compact(&free);
+        Ok(delaunay)
+    }
+
+    pub fn points(&self) -> &[Vec2] {
+        &self.points
+    }
+
+    /// Triangles in counter clockwise order.
+    pub fn triangles(&self) -> &[[usize; 3]] {
+        &self.triangles
+    }
+
+    /// For each triangle, the triangle across the edge opposite each of its vertices, `None` on
+    /// the hull.
+    pub fn neighbors(&self) -> &[[Option<usize>; 3]] {
+        &self.neighbors
+    }
+
+    /// Every edge once as `(smaller index, larger index)`, sorted.
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edges: Vec<(usize, usize)> = (0..self.triangles.len())
+            .flat_map(|t| (0..3).map(move |i| (t, i)))
+            .filter(|&(t, i)| self.neighbors[t][i].is_none_or(|n| n > t))
+            .map(|(t, i)| {
+                let (u, v) = self.edge(t, i);
+                (u.min(v), u.max(v))
+            })
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    /// Points connected to each point by an edge, empty for merged duplicates.
+    pub fn adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![vec![]; self.points.len()];
+        for (u, v) in self.edges() {
+            adjacency[u].push(v);
+            adjacency[v].push(u);
+        }
+        adjacency
+    }
+
+    /// Index of the first point equal to the point, which stands for all of its duplicates.
+    pub fn canonical(&self, point: usize) -> usize {
+        self.canonical[point]
+    }
+
+    /// Triangle containing the point, boundary included. `None` outside the hull.
+    pub fn locate(&self, point: Vec2) -> Option<usize> {
+        if self.triangles.is_empty() {
+            return None;
+        }
+        self.walk(point, 0)
+    }
+
+    pub fn is_constrained(&self, a: usize, b: usize) -> bool {
+        let (Some(&a), Some(&b)) = (self.canonical.get(a), self.canonical.get(b)) else {
+            return false;
+        };
+        self.constraints.contains(&(a.min(b), a.max(b)))
+    }
+
+    /// Forces the segment between two points into the triangulation. Points lying on the segment
+    /// split it into several constrained edges. The triangles it crosses are replaced by the
+    /// constrained Delaunay triangulation of the two sides, which later constraints do not flip.
+    /// Nothing changes on error.
+    pub fn add_constraint(&mut self, a: usize, b: usize) -> Result<(), ConstraintError> {
+        let vertex = |i: usize| {
+            let i = *self.canonical.get(i)?;
+            self.incident[i].map(|_| i)
+        };
+        let (Some(a), Some(b)) = (vertex(a), vertex(b)) else {
+            return Err(ConstraintError::NotAVertex);
+        };
+        if a == b {
+            return Err(ConstraintError::NotAVertex);
+        }
+        // Check the whole segment first, the crossed triangles do not overlap between pieces
+        let mut from = a;
+        while from != b {
+            from = self.trace(from, b)?.end;
+        }
+        let mut from = a;
+        while from != b {
+            let crossing = self.trace(from, b)?;
+            if !crossing.triangles.is_empty() {
+                let mut triangles = vec![];
+                let left: Vec<usize> = crossing.left.iter().rev().copied().collect();
+                self.fill(from, crossing.end, &left, &mut triangles);
+                self.fill(crossing.end, from, &crossing.right, &mut triangles);
+                self.replace(&crossing.triangles, triangles, &mut vec![]);
+            }
+            self.constraints
+                .insert((from.min(crossing.end), from.max(crossing.end)));
+            from = crossing.end;
+        }
+        Ok(())
+    }
+
+    /// First triangle in counter clockwise order, surrounded by ghost triangles.
+    fn seed(&mut self, a: usize, b: usize, c: usize) {
+        let (b, c) = if orient(self.points[a], self.points[b], self.points[c]) > 0.0 {
+            (b, c)
+        } else {
+            (c, b)
+        };
+        self.triangles = vec![[a, b, c], [b, a, GHOST], [c, b, GHOST], [a, c, GHOST]];
+        self.neighbors = vec![[None; 3]; 4];
+        self.link(&[0, 1, 2, 3], &HashMap::new());
+    }
+
+    /// Adds a point and returns one of the new triangles.
+    fn insert(&mut self, p: usize, last: usize, free: &mut Vec<usize>) -> usize {
+        let point = self.points[p];
+        let start = match self.ghost_position(last) {
+            Some(g) => self.neighbors[last][g].unwrap(),
+            None => last,
+        };
+        let start = self.walk(point, start).unwrap();
+        let mut cavity = vec![start];
+        let mut in_cavity = HashSet::from([start]);
+        let mut i = 0;
+        while i < cavity.len() {
+            for n in self.neighbors[cavity[i]].into_iter().flatten() {
+                if !in_cavity.contains(&n) && self.conflicts(n, point) {
+                    in_cavity.insert(n);
+                    cavity.push(n);
+                }
+            }
+            i += 1;
+        }
+        let star = cavity
+            .iter()
+            .flat_map(|&t| (0..3).map(move |i| (t, i)))
+            .filter(|&(t, i)| self.neighbors[t][i].is_none_or(|n| !in_cavity.contains(&n)))
+            .map(|(t, i)| {
+                let (u, v) = self.edge(t, i);
+                [u, v, p]
+            })
+            .collect();
+        self.replace(&cavity, star, free)[0]
+    }
+
+    /// Whether the point is inside the circumcircle of the triangle. A ghost triangle stands for
+    /// the half plane beyond its hull edge, plus the edge itself.
+    fn conflicts(&self, t: usize, point: Vec2) -> bool {
+        let [a, b, c] = self.triangles[t].map(|v| self.points.get(v).copied());
+        match (a, b, c) {
+            (Some(a), Some(b), Some(c)) => {
+                incircle(a.as_dvec2(), b.as_dvec2(), c.as_dvec2(), point.as_dvec2()) > 0.0
+            }
+            (None, Some(x), Some(y)) | (Some(y), None, Some(x)) | (Some(x), Some(y), None) => {
+                let side = orient(x, y, point);
+                side > 0.0
+                    || side == 0.0 && point.cmpge(x.min(y)).all() && point.cmple(x.max(y)).all()
+            }
+            _ => unreachable!("ghost triangles have one vertex at infinity"),
+        }
+    }
+
+    /// Walks from triangle `t` towards the point, crossing any edge that has the point strictly on
+    /// its other side. Starting from a varying edge keeps the walk from cycling. Ends in the
+    /// triangle containing the point or a ghost triangle, `None` when it leaves the hull.
+    fn walk(&self, point: Vec2, mut t: usize) -> Option<usize> {
+        let mut offset = 0;
+        while self.ghost_position(t).is_none() {
+            offset = (offset + 1) % 3;
+            let exit = (0..3).map(|i| (i + offset) % 3).find(|&i| {
+                let (u, v) = self.edge(t, i);
+                orient(self.points[u], self.points[v], point) < 0.0
+            });
+            match exit {
+                Some(i) => t = self.neighbors[t][i]?,
+                None => break,
+            }
+        }
+        Some(t)
+    }
+
+    /// Follows the segment from vertex `a` towards `b` up to the first vertex on it.
+    fn trace(&self, a: usize, b: usize) -> Result<Crossing, ConstraintError> {
+        let point = |v: usize| self.points[v];
+        let direction = point(b) - point(a);
+        let first = self.around(a).into_iter().find_map(|t| {
+            let i = self.triangles[t].iter().position(|&v| v == a)?;
+            let (u, v) = self.edge(t, i);
+            for w in [u, v] {
+                let on_segment = orient(point(a), point(b), point(w)) == 0.0
+                    && (point(w) - point(a)).dot(direction) > 0.0;
+                if w == b || on_segment {
+                    return Some((w, None));
+                }
+            }
+            let inside = orient(point(a), point(u), point(b)) > 0.0
+                && orient(point(a), point(v), point(b)) < 0.0;
+            inside.then_some((b, Some((t, u, v))))
+        });
+        // Some triangle around `a` either has an edge along the segment or is crossed by it
+        let (end, first) = first.unwrap();
+        let Some((mut t, mut right, mut left)) = first else {
+            return Ok(Crossing {
+                end,
+                triangles: vec![],
+                left: vec![],
+                right: vec![],
+            });
+        };
+        let mut crossing = Crossing {
+            end,
+            triangles: vec![t],
+            left: vec![left],
+            right: vec![right],
+        };
+        loop {
+            if self.is_constrained(right, left) {
+                return Err(ConstraintError::Crossing);
+            }
+            let i = (0..3).find(|&i| self.edge(t, i) == (right, left)).unwrap();
+            // The segment ends inside the hull, so there is always a triangle beyond
+            t = self.neighbors[t][i].unwrap();
+            crossing.triangles.push(t);
+            let w = self.triangles[t]
+                .into_iter()
+                .find(|&w| w != right && w != left)
+                .unwrap();
+            let side = orient(point(a), point(b), point(w));
+            if w == b || side == 0.0 {
+                crossing.end = w;
+                return Ok(crossing);
+            }
+            if side > 0.0 {
+                left = w;
+                crossing.left.push(w);
+            } else {
+                right = w;
+                crossing.right.push(w);
+            }
+        }
+    }
+
+    /// Triangulates the polygon `x, y, chain..` given in counter clockwise order, by putting on
+    /// `x y` the vertex whose circumcircle holds no other vertex of the chain.
+    fn fill(&self, x: usize, y: usize, chain: &[usize], triangles: &mut Vec<[usize; 3]>) {
+        if chain.is_empty() {
+            return;
+        }
+        let [px, py] = [x, y].map(|v| self.points[v].as_dvec2());
+        let apex = (1..chain.len()).fold(0, |apex, i| {
+            let [pa, pi] = [chain[apex], chain[i]].map(|v| self.points[v].as_dvec2());
+            if incircle(px, py, pa, pi) > 0.0 {
+                i
+            } else {
+                apex
+            }
+        });
+        triangles.push([x, y, chain[apex]]);
+        self.fill(chain[apex], y, &chain[..apex], triangles);
+        self.fill(x, chain[apex], &chain[apex + 1..], triangles);
+    }
+
+    /// Triangles around a vertex.
+    fn around(&self, v: usize) -> Vec<usize> {
+        let mut around = vec![];
+        let mut stack = vec![self.incident[v].unwrap()];
+        while let Some(t) = stack.pop() {
+            if !around.contains(&t) && self.triangles[t].contains(&v) {
+                around.push(t);
+                stack.extend(self.neighbors[t].into_iter().flatten());
+            }
+        }
+        around
+    }
+
+    /// Swaps triangles covering a polygon for another set covering the same polygon. Returns the
+    /// indices of the new triangles, which reuse the free slots first.
+    fn replace(
+        &mut self,
+        removed: &[usize],
+        triangles: Vec<[usize; 3]>,
+        free: &mut Vec<usize>,
+    ) -> Vec<usize> {
+        let mut outside = HashMap::new();
+        for &t in removed {
+            for i in 0..3 {
+                let n = self.neighbors[t][i];
+                if n.is_none_or(|n| !removed.contains(&n)) {
+                    outside.insert(self.edge(t, i), n);
+                }
+            }
+        }
+        free.extend(removed);
+        let added: Vec<usize> = triangles
+            .into_iter()
+            .map(|triangle| match free.pop() {
+                Some(t) => {
+                    self.triangles[t] = triangle;
+                    t
+                }
+                None => {
+                    self.triangles.push(triangle);
+                    self.neighbors.push([None; 3]);
+                    self.triangles.len() - 1
+                }
+            })
+            .collect();
+        self.link(&added, &outside);
+        for &t in &added {
+            for v in self.triangles[t] {
+                if let Some(incident) = self.incident.get_mut(v) {
+                    *incident = Some(t);
+                }
+            }
+        }
+        added
+    }
+
+    /// Sets the neighbors of the new triangles, among themselves or across the outside edges.
+    fn link(&mut self, added: &[usize], outside: &HashMap<(usize, usize), Option<usize>>) {
+        let edges: HashMap<(usize, usize), usize> = added
+            .iter()
+            .flat_map(|&t| (0..3).map(move |i| (t, i)))
+            .map(|(t, i)| (self.edge(t, i), t))
+            .collect();
+        for &t in added {
+            for i in 0..3 {
+                let (u, v) = self.edge(t, i);
+                self.neighbors[t][i] = match edges.get(&(v, u)) {
+                    Some(&n) => Some(n),
+                    None => {
+                        let n = outside[&(u, v)];
+                        if let Some(n) = n {
+                            let j = (0..3).find(|&j| self.edge(n, j) == (v, u)).unwrap();
+                            self.neighbors[n][j] = Some(t);
+                        }
+                        n
+                    }
+                };
+            }
+        }
+    }
+
+    /// Drops the free slots and the ghost triangles.
+    fn compact(&mut self, free: &[usize]) {
+        let free: HashSet<usize> = free.iter().copied().collect();
+        let kept: Vec<usize> = (0..self.triangles.len())
+            .filter(|&t| !free.contains(&t) && self.ghost_position(t).is_none())
+            .collect();
+        let mut index = vec![None; self.triangles.len()];
+        for (new, &old) in kept.iter().enumerate() {
+            index[old] = Some(new);
+        }
+        self.triangles = kept.iter().map(|&t| self.triangles[t]).collect();
+        self.neighbors = kept
+            .iter()
+            .map(|&t| self.neighbors[t].map(|n| n.and_then(|n| index[n])))
+            .collect();
+        for (t, triangle) in self.triangles.iter().enumerate() {
+            for &v in triangle {
+                self.incident[v] = Some(t);
+            }
+        }
+    }
+
+    /// Edge opposite vertex `i`, in the triangle's order.
+    fn edge(&self, t: usize, i: usize) -> (usize, usize) {
+        let triangle = self.triangles[t];
+        (triangle[(i + 1) % 3], triangle[(i + 2) % 3])
+    }
+
+    fn ghost_position(&self, t: usize) -> Option<usize> {
+        self.triangles[t].iter().position(|&v| v == GHOST)
+    }
+}
+
+fn orient(a: Vec2, b: Vec2, c: Vec2) -> f64 {
+    orient2d(a.as_dvec2(), b.as_dvec2(), c.as_dvec2())
+}
+
+/// Position along a Hilbert curve over the unit square, at 16 bits per axis.
+fn hilbert(p: Vec2) -> u64 {
+    const SIDE: u32 = 1 << 16;
+    let [mut x, mut y] = p
+        .to_array()
+        .map(|c| ((c * SIDE as f32) as u32).min(SIDE - 1));
+    let mut d = 0;
+    let mut s = SIDE / 2;
+    while s > 0 {
+        let (rx, ry) = ((x & s > 0) as u32, (y & s > 0) as u32);
+        d += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+        // Rotate the quadrant so the curve inside it starts and ends at the right corners
+        if ry == 0 {
+            if rx == 1 {
+                (x, y) = (SIDE - 1 - x, SIDE - 1 - y);
+            }
+            (x, y) = (y, x);
+        }
+        s /= 2;
+    }
+    d
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convex::hull::{ConvexHull, MonotoneChain};
+    use crate::geometry::polygon::PolygonWithHoles;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn random(rng: &mut StdRng, count: usize) -> Vec<Vec2> {
+        (0..count)
+            .map(|_| Vec2::new(rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0)))
+            .collect()
+    }
+
+    /// Grid with every point twice, full of cocircular and collinear points.
+    fn grid(side: i32) -> Vec<Vec2> {
+        let points: Vec<Vec2> = (0..side * side)
+            .map(|i| Vec2::new((i % side) as f32, (i / side) as f32))
+            .collect();
+        points.iter().chain(&points).copied().collect()
+    }
+
+    fn check(delaunay: &Delaunay) {
+        let points = delaunay.points();
+        let corners = |t: &[usize; 3]| t.map(|v| points[v].as_dvec2());
+        let vertices: Vec<usize> = (0..points.len())
+            .filter(|&v| delaunay.canonical(v) == v)
+            .collect();
+        for triangle in delaunay.triangles() {
+            let [a, b, c] = corners(triangle);
+            assert!(orient2d(a, b, c) > 0.0);
+            if delaunay.constraints.is_empty() {
+                for &v in &vertices {
+                    assert!(incircle(a, b, c, points[v].as_dvec2()) <= 0.0);
+                }
+            }
+        }
+        let area: f64 = delaunay
+            .triangles()
+            .iter()
+            .map(|t| {
+                let [a, b, c] = corners(t);
+                orient2d(a, b, c) / 2.0
+            })
+            .sum();
+        let hull = PolygonWithHoles::from(MonotoneChain.hull(points).unwrap());
+        assert!((area - hull.area() as f64).abs() <= 1e-4 * area.max(1.0));
+
+        for (t, neighbors) in delaunay.neighbors().iter().enumerate() {
+            for (i, &n) in neighbors.iter().enumerate() {
+                let (u, v) = delaunay.edge(t, i);
+                match n {
+                    Some(n) => {
+                        let j = (0..3).find(|&j| delaunay.edge(n, j) == (v, u)).unwrap();
+                        assert_eq!(delaunay.neighbors()[n][j], Some(t));
+                        // Locally Delaunay unless constrained, which is enough for the whole
+                        // triangulation to be constrained Delaunay
+                        if !delaunay.is_constrained(u, v) {
+                            let [a, b, c] = corners(&delaunay.triangles()[t]);
+                            let opposite = points[delaunay.triangles()[n][j]].as_dvec2();
+                            assert!(incircle(a, b, c, opposite) <= 0.0, "{t} {n}");
+                        }
+                    }
+                    // Hull edges have every point on their left or on them
+                    None => assert!(vertices
+                        .iter()
+                        .all(|&w| orient(points[u], points[v], points[w]) >= 0.0)),
+                }
+            }
+        }
+        let adjacency = delaunay.adjacency();
+        for (u, adjacent) in adjacency.iter().enumerate() {
+            assert_eq!(adjacent.is_empty(), delaunay.canonical(u) != u);
+            assert!(adjacent.iter().all(|&v| adjacency[v].contains(&u)));
+        }
+    }
+
+    #[test]
+    fn empty_circumcircles() {
+        let mut rng = StdRng::seed_from_u64(10);
+        for count in [3, 4, 10, 100, 500] {
+            check(&Delaunay::new(&random(&mut rng, count)).unwrap());
+        }
+        for side in [2, 3, 7, 20] {
+            let delaunay = Delaunay::new(&grid(side)).unwrap();
+            assert_eq!(delaunay.triangles().len(), 2 * (side - 1).pow(2) as usize);
+            check(&delaunay);
+        }
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        assert!(matches!(Delaunay::new(&[]), Err(HullError::Empty)));
+        assert!(matches!(
+            Delaunay::new(&[Vec2::INFINITY]),
+            Err(HullError::NonFinite)
+        ));
+        let line: Vec<Vec2> = (0..5).map(|i| Vec2::splat(i as f32)).collect();
+        let delaunay = Delaunay::new(&line).unwrap();
+        assert!(delaunay.triangles().is_empty() && delaunay.edges().is_empty());
+        assert_eq!(delaunay.locate(Vec2::ONE), None);
+        let zeros = [Vec2::new(-0.0, 0.0), Vec2::X, Vec2::Y, Vec2::ZERO];
+        let delaunay = Delaunay::new(&zeros).unwrap();
+        assert_eq!(delaunay.canonical(3), 0);
+        assert_eq!(delaunay.triangles().len(), 1);
+    }
+
+    #[test]
+    fn constraints() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..50 {
+            let points = random(&mut rng, 60);
+            let mut delaunay = Delaunay::new(&points).unwrap();
+            let (a, b) = (rng.gen_range(0..60), rng.gen_range(0..60));
+            if a == b {
+                continue;
+            }
+            delaunay.add_constraint(a, b).unwrap();
+            assert!(delaunay.is_constrained(b, a));
+            assert!(delaunay.edges().contains(&(a.min(b), a.max(b))));
+            check(&delaunay);
+
+            // A segment between the two sides of the constraint crosses it, unless it goes round
+            let crossing = (0..60)
+                .flat_map(|c| (0..60).map(move |d| (c, d)))
+                .find(|&(c, d)| {
+                    let side = |p| orient(points[a], points[b], points[p]);
+                    let across = |p, q| {
+                        orient(points[p], points[q], points[a])
+                            * orient(points[p], points[q], points[b])
+                            < 0.0
+                    };
+                    side(c) * side(d) < 0.0 && across(c, d)
+                });
+            if let Some((c, d)) = crossing {
+                let before = delaunay.clone();
+                assert_eq!(
+                    delaunay.add_constraint(c, d),
+                    Err(ConstraintError::Crossing)
+                );
+                assert_eq!(delaunay.triangles(), before.triangles());
+                assert_eq!(delaunay.neighbors(), before.neighbors());
+            }
+        }
+    }
+
+    #[test]
+    fn constraints_through_vertices() {
+        let mut delaunay = Delaunay::new(&grid(6)).unwrap();
+        // The diagonal from corner to corner runs through a point in each row
+        delaunay.add_constraint(0, 35).unwrap();
+        for k in 0..5 {
+            assert!(delaunay.is_constrained(7 * k, 7 * (k + 1)));
+        }
+        check(&delaunay);
+        assert_eq!(
+            delaunay.add_constraint(5, 30),
+            Err(ConstraintError::Crossing)
+        );
+        assert_eq!(
+            delaunay.add_constraint(36, 36),
+            Err(ConstraintError::NotAVertex)
+        );
+        assert_eq!(
+            delaunay.add_constraint(0, 72),
+            Err(ConstraintError::NotAVertex)
+        );
+        // Duplicates stand for their first occurrence, along the right side through a point in
+        // each row
+        delaunay.add_constraint(36 + 5, 36 + 35).unwrap();
+        for k in 0..5 {
+            assert!(delaunay.is_constrained(5 + 6 * k, 11 + 6 * k));
+        }
+        check(&delaunay);
+    }
+}
@@ -0,0 +1,364 @@
+//! Smallest enclosing circle and sphere with Welzl's algorithm, in the randomized incremental form
+//! of nested loops: a point outside the current disk is on the boundary of the next one, which is
+//! found by going over the earlier points again with that point fixed. The points are shuffled
+//! first, which makes the expected running time linear.
+use super::circle::{circumcircle, Disk};
+use super::predicates::orient3d;
+use crate::convex::hull::HullError;
+use bevy::math::{DMat3, DVec3};
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ball {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl Ball {
+    /// Smallest ball centered at `center` that contains all the points.
+    pub fn around(center: Vec3, points: &[Vec3]) -> Self {
+        let radius = points
+            .iter()
+            .map(|p| center.as_dvec3().distance(p.as_dvec3()))
+            .fold(0.0, f64::max);
+        Self {
+            center,
+            radius: radius as f32,
+        }
+    }
+
+    /// Points on the boundary are contained, with a small slack that absorbs the rounding of the
+    /// center to f32.
+    pub fn contains(&self, point: Vec3) -> bool {
+        let distance = self.center.as_dvec3().distance(point.as_dvec3());
+        let slack = 1e-5 * self.radius as f64 + 1e-6 * self.center.abs().max_element() as f64;
+        distance <= self.radius as f64 + slack
+    }
+}
+
+/// The shuffle draws from `rng`, which only changes the order of the work, the disk is the same
+/// up to rounding.
+pub fn min_enclosing_circle(points: &[Vec2], rng: &mut impl Rng) -> Result<Disk, HullError> {
+    if points.is_empty() {
+        return Err(HullError::Empty);
+    }
+    if points.iter().any(|p| !p.is_finite()) {
+        return Err(HullError::NonFinite);
+    }
+    let mut points = points.to_vec();
+    points.shuffle(rng);
+    let mut disk = Disk::around(points[0], &[]);
+    for i in 1..points.len() {
+        if disk.contains(points[i]) {
+            continue;
+        }
+        // points[i] is on the boundary of the smallest disk around points[..=i]
+        disk = Disk::around(points[i], &[]);
+        for j in 0..i {
+            if disk.contains(points[j]) {
+                continue;
+            }
+            disk = Disk::from_diameter(points[i], points[j]);
+            for k in 0..j {
+                if !disk.contains(points[k]) {
+                    disk = disk_through(points[i], points[j], points[k]);
+                }
+            }
+        }
+    }
+    Ok(disk)
+}
+
+/// The shuffle draws from `rng`, as for [`min_enclosing_circle`].
+pub fn min_enclosing_sphere(points: &[Vec3], rng: &mut impl Rng) -> Result<Ball, HullError> {
+    if points.is_empty() {
+        return Err(HullError::Empty);
+    }
+    if points.iter().any(|p| !p.is_finite()) {
+        return Err(HullError::NonFinite);
+    }
+    let mut points = points.to_vec();
+    points.shuffle(rng);
+    let mut ball = Ball::around(points[0], &[]);
+    for i in 1..points.len() {
+        if ball.contains(points[i]) {
+            continue;
+        }
+        ball = Ball::around(points[i], &[]);
+        for j in 0..i {
+            if ball.contains(points[j]) {
+                continue;
+            }
+            ball = ball_through(&[points[i], points[j]]);
+            for k in 0..j {
+                if ball.contains(points[k]) {
+                    continue;
+                }
+                ball = ball_through(&[points[i], points[j], points[k]]);
+                for l in 0..k {
+                    if !ball.contains(points[l]) {
+                        ball = ball_through(&[points[i], points[j], points[k], points[l]]);
+                    }
+                }
+            }
+        }
+    }
+    Ok(ball)
+}
+
+/// Smallest disk with the three points on its boundary. Collinear points do not fit on a circle,
+/// the disk spanned by the farthest two is used instead.
+fn disk_through(a: Vec2, b: Vec2, c: Vec2) -> Disk {
+    circumcircle(a, b, c).unwrap_or_else(|| {
+        [(a, b), (b, c), (c, a)]
+            .map(|(p, q)| Disk::from_diameter(p, q))
+            .into_iter()
+            .max_by(|d, e| d.radius.total_cmp(&e.radius))
+            .unwrap()
+    })
+}
+
+/// Smallest ball with all the (up to four) points on its boundary. When the points are degenerate,
+/// i.e. collinear or coplanar, the smallest ball through a subset that contains the rest is used.
+fn ball_through(points: &[Vec3]) -> Ball {
+    let p: Vec<DVec3> = points.iter().map(|p| p.as_dvec3()).collect();
+    let center = match p[..] {
+        [a] => Some(a),
+        [a, b] => Some((a + b) / 2.0),
+        [a, b, c] => {
+            // Circumcenter within the plane of the triangle
+            let (ab, ac) = (b - a, c - a);
+            let normal = ab.cross(ac);
+            (normal != DVec3::ZERO).then(|| {
+                a + (normal.cross(ab) * ac.length_squared()
+                    + ac.cross(normal) * ab.length_squared())
+                    / (2.0 * normal.length_squared())
+            })
+        }
+        [a, b, c, d] => (orient3d(a, b, c, d) != 0.0).then(|| {
+            let rows = DMat3::from_cols(b - a, c - a, d - a).transpose();
+            let rhs = DVec3::new(
+                (b - a).length_squared(),
+                (c - a).length_squared(),
+                (d - a).length_squared(),
+            ) / 2.0;
+            a + rows.inverse() * rhs
+        }),
+        _ => unreachable!("a ball is fixed by at most four points"),
+    };
+    match center {
+        Some(center) => Ball::around(center.as_vec3(), points),
+        None => {
+            // Degenerate: the smallest ball through a proper subset that holds the rest
+            let n = points.len();
+            let subsets = (1..(1 << n) - 1).map(|mask: usize| {
+                (0..n)
+                    .filter(|i| mask & (1 << i) != 0)
+                    .map(|i| points[i])
+                    .collect::<Vec<Vec3>>()
+            });
+            subsets
+                .map(|subset| ball_through(&subset))
+                .filter(|ball| points.iter().all(|&p| ball.contains(p)))
+                .min_by(|a, b| a.radius.total_cmp(&b.radius))
+                .unwrap_or_else(|| Ball::around(points[0], points))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Smallest disk through two or three of the points that contains all of them, in O(n^4).
+    fn brute_force_circle(points: &[Vec2]) -> Disk {
+        let n = points.len();
+        let mut candidates = vec![Disk::around(points[0], &[])];
+        for i in 0..n {
+            for j in i + 1..n {
+                candidates.push(Disk::from_diameter(points[i], points[j]));
+                for k in j + 1..n {
+                    candidates.extend(circumcircle(points[i], points[j], points[k]));
+                }
+            }
+        }
+        candidates
+            .into_iter()
+            .filter(|disk| points.iter().all(|&p| disk.contains(p)))
+            .min_by(|a, b| a.radius.total_cmp(&b.radius))
+            .unwrap()
+    }
+
+    /// Smallest ball through two to four of the points that contains all of them, in O(n^5).
+    fn brute_force_sphere(points: &[Vec3]) -> Ball {
+        let n = points.len();
+        let mut candidates = vec![Ball::around(points[0], &[])];
+        for i in 0..n {
+            for j in i + 1..n {
+                candidates.push(ball_through(&[points[i], points[j]]));
+                for k in j + 1..n {
+                    candidates.push(ball_through(&[points[i], points[j], points[k]]));
+                    for l in k + 1..n {
+                        candidates
+                            .push(ball_through(&[points[i], points[j], points[k], points[l]]));
+                    }
+                }
+            }
+        }
+        candidates
+            .into_iter()
+            .filter(|ball| points.iter().all(|&p| ball.contains(p)))
+            .min_by(|a, b| a.radius.total_cmp(&b.radius))
+            .unwrap()
+    }
+
+    fn near(a: f32, b: f32) -> bool {
+        (a - b).abs() <= 1e-5 * a.max(b).max(1.0)
+    }
+
+    /// Contains the points, has the radius of the brute force and, unless it is a single point,
+    /// at least two of them on its boundary.
+    fn check_circle(points: &[Vec2]) {
+        let disk = min_enclosing_circle(points, &mut StdRng::seed_from_u64(8)).unwrap();
+        assert!(
+            points.iter().all(|&p| disk.contains(p)),
+            "{disk:?} {points:?}"
+        );
+        let expected = brute_force_circle(points);
+        assert!(
+            near(disk.radius, expected.radius),
+            "{disk:?} != {expected:?} {points:?}"
+        );
+        let support = points
+            .iter()
+            .filter(|p| near(disk.center.distance(**p), disk.radius))
+            .count();
+        assert!(disk.radius == 0.0 || support >= 2, "{disk:?} {points:?}");
+    }
+
+    fn check_sphere(points: &[Vec3]) {
+        let ball = min_enclosing_sphere(points, &mut StdRng::seed_from_u64(9)).unwrap();
+        assert!(
+            points.iter().all(|&p| ball.contains(p)),
+            "{ball:?} {points:?}"
+        );
+        let expected = brute_force_sphere(points);
+        assert!(
+            near(ball.radius, expected.radius),
+            "{ball:?} != {expected:?} {points:?}"
+        );
+        let support = points
+            .iter()
+            .filter(|p| near(ball.center.distance(**p), ball.radius))
+            .count();
+        assert!(ball.radius == 0.0 || support >= 2, "{ball:?} {points:?}");
+    }
+
+    #[test]
+    fn random_circles() {
+        let mut rng = StdRng::seed_from_u64(8);
+        for _ in 0..300 {
+            let count = rng.gen_range(1..20);
+            // On a small grid half the time, for duplicates and cocircular points
+            let grid = rng.gen_bool(0.5);
+            let points: Vec<Vec2> = (0..count)
+                .map(|_| {
+                    if grid {
+                        Vec2::new(rng.gen_range(0..4) as f32, rng.gen_range(0..4) as f32)
+                    } else {
+                        Vec2::new(rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0))
+                    }
+                })
+                .collect();
+            check_circle(&points);
+        }
+    }
+
+    #[test]
+    fn random_spheres() {
+        let mut rng = StdRng::seed_from_u64(9);
+        for _ in 0..200 {
+            let count = rng.gen_range(1..10);
+            let grid = rng.gen_bool(0.5);
+            let points: Vec<Vec3> = (0..count)
+                .map(|_| {
+                    if grid {
+                        IVec3::new(
+                            rng.gen_range(0..3),
+                            rng.gen_range(0..3),
+                            rng.gen_range(0..3),
+                        )
+                        .as_vec3()
+                    } else {
+                        Vec3::new(
+                            rng.gen_range(-5.0..5.0),
+                            rng.gen_range(-5.0..5.0),
+                            rng.gen_range(-5.0..5.0),
+                        )
+                    }
+                })
+                .collect();
+            check_sphere(&points);
+        }
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        let rng = &mut StdRng::seed_from_u64(10);
+        assert_eq!(min_enclosing_circle(&[], rng), Err(HullError::Empty));
+        assert_eq!(min_enclosing_sphere(&[], rng), Err(HullError::Empty));
+        for bad in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let points = [Vec2::ZERO, Vec2::X, Vec2::new(1.0, bad)];
+            assert_eq!(
+                min_enclosing_circle(&points, rng),
+                Err(HullError::NonFinite)
+            );
+            let points = [Vec3::ZERO, Vec3::new(bad, 0.0, 1.0), Vec3::Y];
+            assert_eq!(
+                min_enclosing_sphere(&points, rng),
+                Err(HullError::NonFinite)
+            );
+        }
+        let disk = min_enclosing_circle(&[Vec2::ONE], rng).unwrap();
+        assert_eq!((disk.center, disk.radius), (Vec2::ONE, 0.0));
+        let disk = min_enclosing_circle(&[Vec2::ONE; 5], rng).unwrap();
+        assert_eq!((disk.center, disk.radius), (Vec2::ONE, 0.0));
+        let disk = min_enclosing_circle(&[Vec2::ZERO, Vec2::X * 2.0], rng).unwrap();
+        assert_eq!((disk.center, disk.radius), (Vec2::X, 1.0));
+        // Collinear points, spanned by the two ends
+        let line: Vec<Vec2> = (0..10)
+            .map(|i| Vec2::new(i as f32, 2.0 * i as f32))
+            .collect();
+        let disk = min_enclosing_circle(&line, rng).unwrap();
+        assert!(disk.center.distance(Vec2::new(4.5, 9.0)) <= 1e-5);
+        check_circle(&line);
+        let doubled: Vec<Vec2> = line.iter().chain(&line).copied().collect();
+        check_circle(&doubled);
+
+        let ball = min_enclosing_sphere(&[Vec3::ONE; 3], rng).unwrap();
+        assert_eq!((ball.center, ball.radius), (Vec3::ONE, 0.0));
+        check_sphere(&[Vec3::ZERO, Vec3::X * 2.0]);
+        let line: Vec<Vec3> = (0..6).map(|i| Vec3::splat(i as f32)).collect();
+        check_sphere(&line);
+        // Coplanar points, whose ball is the circle of the plane
+        let square = [Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0), Vec3::Y];
+        let ball = min_enclosing_sphere(&square, rng).unwrap();
+        assert!(near(ball.radius, 0.5_f32.sqrt()));
+        check_sphere(&square);
+    }
+
+    #[test]
+    fn any_shuffle_gives_the_same_disk() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let points: Vec<Vec2> = (0..50)
+            .map(|_| Vec2::new(rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0)))
+            .collect();
+        let disk =
+            |seed: u64| min_enclosing_circle(&points, &mut StdRng::seed_from_u64(seed)).unwrap();
+        assert_eq!(disk(1), disk(1));
+        assert!(near(disk(1).radius, disk(2).radius));
+    }
+}
@@ -0,0 +1,567 @@
+//! Intersections of lines, rays and segments. Lines and rays are given as a point and a direction,
+//! segments by their end points. All of them are the points `origin + t * direction` for `t` in a
+//! range, i.e. any `t` for a line, `t >= 0` for a ray and `0 <= t <= 1` for a segment, which lets
+//! every pair go through the same code. A zero direction or an empty segment stands for a point.
+use super::predicates::orient2d;
+use bevy::math::{DVec2, DVec3};
+use bevy::prelude::*;
+
+const LINE: (f64, f64) = (f64::NEG_INFINITY, f64::INFINITY);
+const RAY: (f64, f64) = (0.0, f64::INFINITY);
+const SEGMENT: (f64, f64) = (0.0, 1.0);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Intersection {
+    None,
+    Point(Vec2),
+    /// Collinear overlap between two points.
+    Segment(Vec2, Vec2),
+    /// Collinear overlap that is unbounded in one direction.
+    Ray {
+        origin: Vec2,
+        direction: Vec2,
+    },
+    /// The same line.
+    Line {
+        point: Vec2,
+        direction: Vec2,
+    },
+}
+
+/// Pair of closest points, one on each of two lines, rays or segments in 3d.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClosestApproach {
+    pub first: Vec3,
+    pub second: Vec3,
+}
+
+impl ClosestApproach {
+    pub fn distance(&self) -> f32 {
+        self.first.distance(self.second)
+    }
+}
+
+pub fn line_line(p1: Vec2, v1: Vec2, p2: Vec2, v2: Vec2) -> Intersection {
+    intersect(along(p1, v1, LINE), along(p2, v2, LINE))
+}
+
+pub fn ray_ray(o1: Vec2, d1: Vec2, o2: Vec2, d2: Vec2) -> Intersection {
+    intersect(along(o1, d1, RAY), along(o2, d2, RAY))
+}
+
+pub fn ray_segment(origin: Vec2, direction: Vec2, a: Vec2, b: Vec2) -> Intersection {
+    intersect(along(origin, direction, RAY), between(a, b))
+}
+
+pub fn segment_segment(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> Intersection {
+    intersect(between(a, b), between(c, d))
+}
+
+pub fn closest_line_line(p1: Vec3, v1: Vec3, p2: Vec3, v2: Vec3) -> ClosestApproach {
+    closest(along_3d(p1, v1, LINE), along_3d(p2, v2, LINE))
+}
+
+pub fn closest_ray_ray(o1: Vec3, d1: Vec3, o2: Vec3, d2: Vec3) -> ClosestApproach {
+    closest(along_3d(o1, d1, RAY), along_3d(o2, d2, RAY))
+}
+
+pub fn closest_ray_segment(origin: Vec3, direction: Vec3, a: Vec3, b: Vec3) -> ClosestApproach {
+    closest(along_3d(origin, direction, RAY), between_3d(a, b))
+}
+
+pub fn closest_segment_segment(a: Vec3, b: Vec3, c: Vec3, d: Vec3) -> ClosestApproach {
+    closest(between_3d(a, b), between_3d(c, d))
+}
+
+/// Point, direction and parameter range of a line, ray or segment, in f64 so that the direction
+/// of a segment is exact.
+type Linear<V> = (V, V, (f64, f64));
+
+fn along(origin: Vec2, direction: Vec2, range: (f64, f64)) -> Linear<DVec2> {
+    (origin.as_dvec2(), direction.as_dvec2(), range)
+}
+
+fn between(a: Vec2, b: Vec2) -> Linear<DVec2> {
+    (a.as_dvec2(), b.as_dvec2() - a.as_dvec2(), SEGMENT)
+}
+
+fn along_3d(origin: Vec3, direction: Vec3, range: (f64, f64)) -> Linear<DVec3> {
+    (origin.as_dvec3(), direction.as_dvec3(), range)
+}
+
+fn between_3d(a: Vec3, b: Vec3) -> Linear<DVec3> {
+    (a.as_dvec3(), b.as_dvec3() - a.as_dvec3(), SEGMENT)
+}
+
+fn intersect((p, u, first): Linear<DVec2>, (q, v, second): Linear<DVec2>) -> Intersection {
+    let at = |origin: DVec2, direction: DVec2, t: f64| (origin + direction * t).as_vec2();
+    let contains = |(low, high): (f64, f64), t: f64| low <= t && t <= high;
+    match (u == DVec2::ZERO, v == DVec2::ZERO) {
+        (true, true) => return point_if(p == q, p),
+        (true, false) => {
+            let t = (p - q).dot(v) / v.length_squared();
+            return point_if(
+                orient2d(DVec2::ZERO, v, p - q) == 0.0 && contains(second, t),
+                p,
+            );
+        }
+        (false, true) => {
+            let t = (q - p).dot(u) / u.length_squared();
+            return point_if(
+                orient2d(DVec2::ZERO, u, q - p) == 0.0 && contains(first, t),
+                q,
+            );
+        }
+        (false, false) => {}
+    }
+    let w = q - p;
+    if orient2d(DVec2::ZERO, u, v) != 0.0 {
+        // The predicate only gets the sign right, the parameters come from the plain cross
+        // products. Differences of f32 coordinates and their products are exact in f64 unless
+        // the coordinates are more than 2^29 apart in magnitude, so these round only once.
+        let denominator = u.perp_dot(v);
+        let t = w.perp_dot(v) / denominator;
+        let s = w.perp_dot(u) / denominator;
+        if !contains(first, t) || !contains(second, s) {
+            return Intersection::None;
+        }
+        // Snap to the end point of the second when it is the one being touched
+        return Intersection::Point(if s == 0.0 || s == 1.0 {
+            at(q, v, s)
+        } else {
+            at(p, u, t)
+        });
+    }
+    if orient2d(DVec2::ZERO, w, u) != 0.0 {
+        return Intersection::None;
+    }
+    // Collinear, the overlap is where the ranges meet along the first. End points are projected
+    // on their own so that shared ones land exactly on the ends of the first.
+    let (offset, scale) = (w.dot(u) / u.length_squared(), v.dot(u) / u.length_squared());
+    let along_first = |t: f64| (t, at(p, u, t));
+    let along_second = |s: f64| {
+        let t = if s.is_finite() {
+            (q + v * s - p).dot(u) / u.length_squared()
+        } else {
+            offset + scale * s
+        };
+        (t, at(q, v, s))
+    };
+    let (mut low, mut high) = (along_second(second.0), along_second(second.1));
+    if scale < 0.0 {
+        (low, high) = (high, low);
+    }
+    let low = if first.0 >= low.0 {
+        along_first(first.0)
+    } else {
+        low
+    };
+    let high = if first.1 <= high.0 {
+        along_first(first.1)
+    } else {
+        high
+    };
+    match (low.0.is_finite(), high.0.is_finite()) {
+        _ if low.0 > high.0 => Intersection::None,
+        (true, true) if low.0 == high.0 => Intersection::Point(low.1),
+        (true, true) => Intersection::Segment(low.1, high.1),
+        (true, false) => Intersection::Ray {
+            origin: low.1,
+            direction: u.as_vec2(),
+        },
+        (false, true) => Intersection::Ray {
+            origin: high.1,
+            direction: -u.as_vec2(),
+        },
+        (false, false) => Intersection::Line {
+            point: p.as_vec2(),
+            direction: u.as_vec2(),
+        },
+    }
+}
+
+/// Minimizes the distance over both parameters by solving for the closest points of the lines,
+/// then clamping one parameter to its range and the other to the best value that follows.
+fn closest((p, u, first): Linear<DVec3>, (q, v, second): Linear<DVec3>) -> ClosestApproach {
+    let clamp = |t: f64, (low, high): (f64, f64)| t.max(low).min(high);
+    let r = p - q;
+    let (a, e, f) = (u.length_squared(), v.length_squared(), v.dot(r));
+    let (s, t) = if a == 0.0 && e == 0.0 {
+        (0.0, 0.0)
+    } else if a == 0.0 {
+        (0.0, clamp(f / e, second))
+    } else if e == 0.0 {
+        (clamp(-u.dot(r) / a, first), 0.0)
+    } else {
+        let (b, c) = (u.dot(v), u.dot(r));
+        let denominator = a * e - b * b;
+        // Any point of the first will do for parallel lines. Lines that are parallel up to the
+        // rounding of f32 directions count as parallel, their closest points can be far off.
+        let s = if denominator > 1e-12 * a * e {
+            clamp((b * f - c * e) / denominator, first)
+        } else {
+            clamp(0.0, first)
+        };
+        let t = (b * s + f) / e;
+        let clamped = clamp(t, second);
+        if clamped == t {
+            (s, t)
+        } else {
+            (clamp((b * clamped - c) / a, first), clamped)
+        }
+    };
+    ClosestApproach {
+        first: point_on(p, u, s),
+        second: point_on(q, v, t),
+    }
+}
+
+fn point_on(origin: DVec3, direction: DVec3, t: f64) -> Vec3 {
+    (origin + direction * t).as_vec3()
+}
+
+fn point_if(condition: bool, point: DVec2) -> Intersection {
+    if condition {
+        Intersection::Point(point.as_vec2())
+    } else {
+        Intersection::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::I64Vec2;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Kind {
+        Line,
+        Ray,
+        Segment,
+    }
+
+    /// Range of the parameter in multiples of `1 / scale`, unbounded where `None`.
+    fn range(kind: Kind, scale: i64) -> (Option<i64>, Option<i64>) {
+        match kind {
+            Kind::Line => (None, None),
+            Kind::Ray => (Some(0), None),
+            Kind::Segment => (Some(0), Some(scale)),
+        }
+    }
+
+    fn contains((low, high): (Option<i64>, Option<i64>), t: i64) -> bool {
+        low.is_none_or(|low| low <= t) && high.is_none_or(|high| t <= high)
+    }
+
+    /// Intersection of `origin + t * direction` over the ranges of the kinds, with integer
+    /// coordinates and rational parameters compared exactly.
+    fn exact(
+        (p, u, first): (I64Vec2, I64Vec2, Kind),
+        (q, v, second): (I64Vec2, I64Vec2, Kind),
+    ) -> Intersection {
+        let point = |p: I64Vec2| Intersection::Point(p.as_vec2());
+        let on = |p: I64Vec2, q: I64Vec2, v: I64Vec2, kind: Kind| {
+            (p - q).perp_dot(v) == 0 && contains(range(kind, v.length_squared()), (p - q).dot(v))
+        };
+        match (u == I64Vec2::ZERO, v == I64Vec2::ZERO) {
+            (true, true) if p == q => return point(p),
+            (true, true) => return Intersection::None,
+            (true, false) if on(p, q, v, second) => return point(p),
+            (false, true) if on(q, p, u, first) => return point(q),
+            (true, false) | (false, true) => return Intersection::None,
+            (false, false) => {}
+        }
+        let w = q - p;
+        let denominator = u.perp_dot(v);
+        if denominator != 0 {
+            let sign = denominator.signum();
+            let (t, s) = (w.perp_dot(v) * sign, w.perp_dot(u) * sign);
+            let scale = denominator.abs();
+            if !contains(range(first, scale), t) || !contains(range(second, scale), s) {
+                return Intersection::None;
+            }
+            return Intersection::Point(
+                (p.as_dvec2() + u.as_dvec2() * (t as f64 / scale as f64)).as_vec2(),
+            );
+        }
+        if w.perp_dot(u) != 0 {
+            return Intersection::None;
+        }
+        // Collinear, in multiples of `1 / |u|^2` along the first
+        let scale = u.length_squared();
+        let (start, end) = (w.dot(u), (w + v).dot(u));
+        let forward = v.dot(u) > 0;
+        let (low, high) = match second {
+            Kind::Line => (None, None),
+            Kind::Ray if forward => (Some(start), None),
+            Kind::Ray => (None, Some(start)),
+            Kind::Segment => (Some(start.min(end)), Some(start.max(end))),
+        };
+        let (first_low, first_high) = range(first, scale);
+        let low = match (low, first_low) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        let high = match (high, first_high) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let at = |t: i64| (p.as_dvec2() + u.as_dvec2() * (t as f64 / scale as f64)).as_vec2();
+        match (low, high) {
+            (Some(low), Some(high)) if low > high => Intersection::None,
+            (Some(low), Some(high)) if low == high => Intersection::Point(at(low)),
+            (Some(low), Some(high)) => Intersection::Segment(at(low), at(high)),
+            (Some(low), None) => Intersection::Ray {
+                origin: at(low),
+                direction: u.as_vec2(),
+            },
+            (None, Some(high)) => Intersection::Ray {
+                origin: at(high),
+                direction: -u.as_vec2(),
+            },
+            (None, None) => Intersection::Line {
+                point: p.as_vec2(),
+                direction: u.as_vec2(),
+            },
+        }
+    }
+
+    fn same(a: Intersection, b: Intersection) -> bool {
+        let near =
+            |p: Vec2, q: Vec2| p.distance(q) <= 1e-5 * p.abs().max(q.abs()).max_element().max(1.0);
+        match (a, b) {
+            (Intersection::None, Intersection::None) => true,
+            (Intersection::Point(p), Intersection::Point(q)) => near(p, q),
+            (Intersection::Segment(p, q), Intersection::Segment(r, s)) => near(p, r) && near(q, s),
+            (
+                Intersection::Ray {
+                    origin: p,
+                    direction: u,
+                },
+                Intersection::Ray {
+                    origin: q,
+                    direction: v,
+                },
+            )
+            | (
+                Intersection::Line {
+                    point: p,
+                    direction: u,
+                },
+                Intersection::Line {
+                    point: q,
+                    direction: v,
+                },
+            ) => near(p, q) && u == v,
+            _ => false,
+        }
+    }
+
+    fn intersection(
+        (p, u, first): (I64Vec2, I64Vec2, Kind),
+        (q, v, second): (I64Vec2, I64Vec2, Kind),
+    ) -> Intersection {
+        let (p, u, q, v) = (p.as_vec2(), u.as_vec2(), q.as_vec2(), v.as_vec2());
+        match (first, second) {
+            (Kind::Line, Kind::Line) => line_line(p, u, q, v),
+            (Kind::Ray, Kind::Ray) => ray_ray(p, u, q, v),
+            (Kind::Ray, Kind::Segment) => ray_segment(p, u, q, q + v),
+            (Kind::Segment, Kind::Segment) => segment_segment(p, p + u, q, q + v),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn exact_on_a_grid() {
+        // Small coordinates, so that pairs are often parallel, collinear or touch at end points
+        let mut rng = StdRng::seed_from_u64(12);
+        let pairs = [
+            (Kind::Line, Kind::Line),
+            (Kind::Ray, Kind::Ray),
+            (Kind::Ray, Kind::Segment),
+            (Kind::Segment, Kind::Segment),
+        ];
+        for _ in 0..20000 {
+            let vector = |rng: &mut StdRng, size: i64| {
+                I64Vec2::new(rng.gen_range(-size..=size), rng.gen_range(-size..=size))
+            };
+            let (p, u, q) = (
+                vector(&mut rng, 3),
+                vector(&mut rng, 2),
+                vector(&mut rng, 3),
+            );
+            // Parallel a third of the time
+            let v = if rng.gen_bool(0.3) {
+                u * rng.gen_range(-2..=2)
+            } else {
+                vector(&mut rng, 2)
+            };
+            let (first, second) = pairs[rng.gen_range(0..pairs.len())];
+            let (a, b) = ((p, u, first), (q, v, second));
+            let (found, expected) = (intersection(a, b), exact(a, b));
+            assert!(
+                same(found, expected),
+                "{found:?} != {expected:?} for {a:?} {b:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn collinear_and_touching() {
+        let v = |x: f32, y: f32| Vec2::new(x, y);
+        let (o, x, y) = (Vec2::ZERO, Vec2::X, Vec2::Y);
+        // Parallel, overlapping, touching end to end and one inside the other
+        assert_eq!(segment_segment(o, x, y, v(1.0, 1.0)), Intersection::None);
+        assert_eq!(
+            segment_segment(o, v(2.0, 0.0), v(3.0, 0.0), x),
+            Intersection::Segment(x, v(2.0, 0.0))
+        );
+        assert_eq!(
+            segment_segment(o, x, x, v(2.0, 0.0)),
+            Intersection::Point(x)
+        );
+        assert_eq!(
+            segment_segment(o, v(4.0, 0.0), v(3.0, 0.0), x),
+            Intersection::Segment(x, v(3.0, 0.0))
+        );
+        // An end point on the other, and only the end points in common
+        assert_eq!(
+            segment_segment(o, v(2.0, 0.0), x, v(1.0, 3.0)),
+            Intersection::Point(x)
+        );
+        assert_eq!(
+            segment_segment(o, x, x, v(1.0, 1.0)),
+            Intersection::Point(x)
+        );
+        // Rays from a segment, along it both ways and across it
+        assert_eq!(
+            ray_segment(x, x, o, v(2.0, 0.0)),
+            Intersection::Segment(x, v(2.0, 0.0))
+        );
+        assert_eq!(ray_segment(x, -x, o, x), Intersection::Segment(x, o));
+        assert_eq!(
+            ray_segment(v(0.5, -1.0), y, o, x),
+            Intersection::Point(v(0.5, 0.0))
+        );
+        assert_eq!(ray_segment(v(0.5, 1.0), y, o, x), Intersection::None);
+        // Rays facing each other or away along the same line
+        assert_eq!(ray_ray(o, x, x, -x), Intersection::Segment(o, x));
+        assert_eq!(ray_ray(o, -x, x, x), Intersection::None);
+        assert_eq!(ray_ray(o, x, o, -x), Intersection::Point(o));
+        assert_eq!(
+            ray_ray(o, x, x, x),
+            Intersection::Ray {
+                origin: x,
+                direction: x,
+            }
+        );
+    }
+
+    /// Shortest distance by trying the minimum inside and every side of the parameter ranges,
+    /// the other parameter taking its best value there.
+    fn shortest((p, u, first): Linear<DVec3>, (q, v, second): Linear<DVec3>) -> f64 {
+        let clamp = |t: f64, (low, high): (f64, f64)| t.max(low).min(high);
+        let best = |origin: DVec3, direction: DVec3, range: (f64, f64), to: DVec3| {
+            let length = direction.length_squared();
+            if length == 0.0 {
+                0.0
+            } else {
+                clamp((to - origin).dot(direction) / length, range)
+            }
+        };
+        let distance = |s: f64, t: f64| (p + u * s).distance(q + v * t);
+        let mut candidates = vec![(0.0, best(q, v, second, p)), (best(p, u, first, q), 0.0)];
+        for s in [first.0, first.1].into_iter().filter(|s| s.is_finite()) {
+            candidates.push((s, best(q, v, second, p + u * s)));
+        }
+        for t in [second.0, second.1].into_iter().filter(|t| t.is_finite()) {
+            candidates.push((best(p, u, first, q + v * t), t));
+        }
+        let (a, b, c, e, f) = (
+            u.length_squared(),
+            u.dot(v),
+            u.dot(p - q),
+            v.length_squared(),
+            v.dot(p - q),
+        );
+        let denominator = a * e - b * b;
+        if denominator > 0.0 {
+            let (s, t) = ((b * f - c * e) / denominator, (a * f - b * c) / denominator);
+            if clamp(s, first) == s && clamp(t, second) == t {
+                candidates.push((s, t));
+            }
+        }
+        candidates
+            .into_iter()
+            .map(|(s, t)| distance(s, t))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    #[test]
+    fn closest_approach_on_a_grid() {
+        let mut rng = StdRng::seed_from_u64(112);
+        for _ in 0..20000 {
+            let vector = |rng: &mut StdRng, size: i32| {
+                IVec3::new(
+                    rng.gen_range(-size..=size),
+                    rng.gen_range(-size..=size),
+                    rng.gen_range(-size..=size),
+                )
+                .as_vec3()
+            };
+            let (p, u, q) = (
+                vector(&mut rng, 3),
+                vector(&mut rng, 2),
+                vector(&mut rng, 3),
+            );
+            let v = if rng.gen_bool(0.3) {
+                u * rng.gen_range(-2..=2) as f32
+            } else {
+                vector(&mut rng, 2)
+            };
+            let (found, first, second) = match rng.gen_range(0..4) {
+                0 => (closest_line_line(p, u, q, v), LINE, LINE),
+                1 => (closest_ray_ray(p, u, q, v), RAY, RAY),
+                2 => (closest_ray_segment(p, u, q, q + v), RAY, SEGMENT),
+                _ => (
+                    closest_segment_segment(p, p + u, q, q + v),
+                    SEGMENT,
+                    SEGMENT,
+                ),
+            };
+            let (a, b) = (along_3d(p, u, first), along_3d(q, v, second));
+            let expected = shortest(a, b);
+            assert!(
+                (found.distance() as f64 - expected).abs() <= 1e-5 * expected.max(1.0),
+                "{found:?} != {expected} for {a:?} {b:?}"
+            );
+            // Each point is on its own line, ray or segment
+            let point = |p: Vec3| along_3d(p, Vec3::ZERO, LINE);
+            assert!(
+                shortest(a, point(found.first)) <= 1e-5,
+                "{found:?} for {a:?} {b:?}"
+            );
+            assert!(
+                shortest(b, point(found.second)) <= 1e-5,
+                "{found:?} for {a:?} {b:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parallel_lines_in_3d() {
+        let (p, q) = (Vec3::new(0.0, 0.0, 1.0), Vec3::new(5.0, 3.0, 1.0));
+        let approach = closest_line_line(p, Vec3::X, q, Vec3::X * -2.0);
+        assert!((approach.distance() - 3.0).abs() <= 1e-6);
+        // Parallel segments that do not overlap along their direction
+        let approach = closest_segment_segment(p, p + Vec3::X, q, q + Vec3::X);
+        assert_eq!(approach.first, p + Vec3::X);
+        assert_eq!(approach.second, q);
+        // Overlapping ones, where any pair across the overlap is closest
+        let (a, b) = (Vec3::new(3.0, 1.0, 1.0), Vec3::new(1.0, 1.0, 1.0));
+        let approach = closest_segment_segment(Vec3::ZERO, Vec3::X * 4.0, a, b);
+        assert!((approach.distance() - 2.0_f32.sqrt()).abs() <= 1e-6);
+        assert!((1.0..=3.0).contains(&approach.first.x));
+    }
+}
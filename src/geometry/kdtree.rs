@@ -0,0 +1,351 @@
+//! Nearest neighbour queries over a static point set with a k-d tree, and the closest pair by
+//! divide and conquer. Queries return indices into the points and compare `f32` distances as
+//! computed by `Vec2::distance` or `Vec3::distance`, so a radius query finds exactly the points
+//! that a comparison of every distance with the radius would find.
+use bevy::prelude::*;
+use ordered_float::OrderedFloat;
+use std::collections::BinaryHeap;
+
+/// Points the tree can split along their axes.
+pub trait KdPoint: Copy {
+    const DIMENSIONS: usize;
+
+    fn coordinate(&self, axis: usize) -> f32;
+
+    fn distance(&self, other: &Self) -> f32;
+}
+
+impl KdPoint for Vec2 {
+    const DIMENSIONS: usize = 2;
+
+    fn coordinate(&self, axis: usize) -> f32 {
+        self[axis]
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        Vec2::distance(*self, *other)
+    }
+}
+
+impl KdPoint for Vec3 {
+    const DIMENSIONS: usize = 3;
+
+    fn coordinate(&self, axis: usize) -> f32 {
+        self[axis]
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        Vec3::distance(*self, *other)
+    }
+}
+
+/// Balanced tree stored implicitly: every range of `order` is a node whose middle entry splits
+/// the rest along the axis with the widest spread, lower coordinates first.
+pub struct KdTree<P = Vec2> {
+    points: Vec<P>,
+    order: Vec<usize>,
+    /// Splitting axis of the node whose middle entry is at each position of `order`
+    axis: Vec<usize>,
+}
+
+impl<P: KdPoint> KdTree<P> {
+    /// Builds in O(n log n).
+    pub fn new(points: &[P]) -> Self {
+        let mut tree = Self {
+            points: points.to_vec(),
+            order: (0..points.len()).collect(),
+            axis: vec![0; points.len()],
+        };
+        tree.build(0, points.len());
+        tree
+    }
+
+    pub fn points(&self) -> &[P] {
+        &self.points
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Index of the point closest to the query, the lowest one on ties. `None` if empty.
+    pub fn nearest(&self, query: P) -> Option<usize> {
+        self.k_nearest(query, 1).first().copied()
+    }
+
+    /// Indices of the `k` points closest to the query, nearest first and by index on ties.
+    pub fn k_nearest(&self, query: P, k: usize) -> Vec<usize> {
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            self.search_nearest(0, self.len(), query, k, &mut heap);
+        }
+        let mut nearest: Vec<(OrderedFloat<f32>, usize)> = heap.into_vec();
+        nearest.sort_unstable();
+        nearest.into_iter().map(|(_, i)| i).collect()
+    }
+
+    /// Indices of the points at a distance of at most `radius` from the query, in increasing order.
+    pub fn within_radius(&self, query: P, radius: f32) -> Vec<usize> {
+        let mut found = vec![];
+        self.search_radius(0, self.len(), query, radius, &mut found);
+        found.sort_unstable();
+        found
+    }
+
+    fn build(&mut self, low: usize, high: usize) {
+        if high - low < 2 {
+            return;
+        }
+        let points = &self.points;
+        let spread = |axis: usize| {
+            let coordinates = self.order[low..high]
+                .iter()
+                .map(|&i| points[i].coordinate(axis));
+            let (min, max) = coordinates.fold((f32::INFINITY, f32::NEG_INFINITY), |(a, b), c| {
+                (a.min(c), b.max(c))
+            });
+            max - min
+        };
+        let axis = (0..P::DIMENSIONS)
+            .max_by(|&a, &b| spread(a).total_cmp(&spread(b)))
+            .unwrap();
+        let mid = (low + high) / 2;
+        self.order[low..high].select_nth_unstable_by(mid - low, |&i, &j| {
+            points[i]
+                .coordinate(axis)
+                .total_cmp(&points[j].coordinate(axis))
+        });
+        self.axis[mid] = axis;
+        self.build(low, mid);
+        self.build(mid + 1, high);
+    }
+
+    /// Visits the node over `order[low..high]`, nearer side first, keeping the best `k` so far
+    /// in a max heap.
+    fn search_nearest(
+        &self,
+        low: usize,
+        high: usize,
+        query: P,
+        k: usize,
+        heap: &mut BinaryHeap<(OrderedFloat<f32>, usize)>,
+    ) {
+        if low >= high {
+            return;
+        }
+        let mid = (low + high) / 2;
+        let i = self.order[mid];
+        heap.push((OrderedFloat(self.points[i].distance(&query)), i));
+        if heap.len() > k {
+            heap.pop();
+        }
+        let axis = self.axis[mid];
+        let gap = query.coordinate(axis) - self.points[i].coordinate(axis);
+        let (near, far) = if gap < 0.0 {
+            ((low, mid), (mid + 1, high))
+        } else {
+            ((mid + 1, high), (low, mid))
+        };
+        self.search_nearest(near.0, near.1, query, k, heap);
+        let worst = heap.peek().map_or(f32::INFINITY, |&(d, _)| d.0);
+        if heap.len() < k || !beyond(gap.abs(), worst) {
+            self.search_nearest(far.0, far.1, query, k, heap);
+        }
+    }
+
+    fn search_radius(
+        &self,
+        low: usize,
+        high: usize,
+        query: P,
+        radius: f32,
+        found: &mut Vec<usize>,
+    ) {
+        if low >= high {
+            return;
+        }
+        let mid = (low + high) / 2;
+        let i = self.order[mid];
+        if self.points[i].distance(&query) <= radius {
+            found.push(i);
+        }
+        let gap = query.coordinate(self.axis[mid]) - self.points[i].coordinate(self.axis[mid]);
+        if !beyond(gap, radius) {
+            self.search_radius(low, mid, query, radius, found);
+        }
+        if !beyond(-gap, radius) {
+            self.search_radius(mid + 1, high, query, radius, found);
+        }
+    }
+}
+
+/// Indices of two points at the smallest distance, lower index first. `None` for fewer than two
+/// points. The points must be finite.
+///
+/// Splits the points at the median x, and past the closest pair of either half only checks the
+/// strip around the split, in y order. In 2d each point there has a bounded number of candidates,
+/// which makes it O(n log n). In 3d the strip is only bounded in x and y, so it degrades when many
+/// points share a column.
+pub fn closest_pair<P: KdPoint>(points: &[P]) -> Option<(usize, usize)> {
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|&i, &j| points[i].coordinate(0).total_cmp(&points[j].coordinate(0)));
+    let mut best = None;
+    closest_in(points, &mut order, &mut best);
+    best.map(|(_, i, j): (f32, usize, usize)| (i.min(j), i.max(j)))
+}
+
+/// Closest pair among points sorted by x, which are left sorted by y.
+fn closest_in<P: KdPoint>(
+    points: &[P],
+    order: &mut [usize],
+    best: &mut Option<(f32, usize, usize)>,
+) {
+    let by_y = |&i: &usize, &j: &usize| points[i].coordinate(1).total_cmp(&points[j].coordinate(1));
+    let consider = |i: usize, j: usize, best: &mut Option<(f32, usize, usize)>| {
+        let distance = points[i].distance(&points[j]);
+        if best.is_none_or(|(d, _, _)| distance < d) {
+            *best = Some((distance, i, j));
+        }
+    };
+    if order.len() <= 3 {
+        for a in 0..order.len() {
+            for b in a + 1..order.len() {
+                consider(order[a], order[b], best);
+            }
+        }
+        order.sort_by(by_y);
+        return;
+    }
+    let mid = order.len() / 2;
+    let split = points[order[mid]].coordinate(0);
+    closest_in(points, &mut order[..mid], best);
+    closest_in(points, &mut order[mid..], best);
+    // Both halves are sorted runs, which the stable sort merges in linear time
+    order.sort_by(by_y);
+    let within = |gap: f32, best: &Option<(f32, usize, usize)>| {
+        best.is_none_or(|(d, _, _)| !beyond(gap.abs(), d))
+    };
+    let strip: Vec<usize> = order
+        .iter()
+        .copied()
+        .filter(|&i| within(points[i].coordinate(0) - split, best))
+        .collect();
+    for (a, &i) in strip.iter().enumerate() {
+        for &j in &strip[a + 1..] {
+            if !within(points[j].coordinate(1) - points[i].coordinate(1), best) {
+                break;
+            }
+            consider(i, j, best);
+        }
+    }
+}
+
+/// Whether a gap along one axis rules out a distance of at most `distance`. The slack covers the
+/// rounding of the distance, which can come out slightly below the gap, or even zero when the
+/// squares underflow.
+fn beyond(gap: f32, distance: f32) -> bool {
+    gap > (distance * (1.0 + 1e-5)).max(1e-22)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Indices by distance to the query, then by index.
+    fn by_distance<P: KdPoint>(points: &[P], query: P) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        order.sort_by_key(|&i| (OrderedFloat(points[i].distance(&query)), i));
+        order
+    }
+
+    fn check<P: KdPoint + std::fmt::Debug>(points: &[P], queries: &[P]) {
+        let tree = KdTree::new(points);
+        assert_eq!(tree.len(), points.len());
+        for &query in queries {
+            let order = by_distance(points, query);
+            assert_eq!(tree.nearest(query), order.first().copied());
+            for k in [0, 1, 2, 5, points.len(), points.len() + 3] {
+                assert_eq!(tree.k_nearest(query, k), order[..k.min(points.len())]);
+            }
+            // Radii equal to distances of points, which are on the boundary
+            for radius in [0.0, 0.5, 1.0, 1.5, 2.5] {
+                let mut within: Vec<usize> = (0..points.len())
+                    .filter(|&i| points[i].distance(&query) <= radius)
+                    .collect();
+                within.sort_unstable();
+                assert_eq!(
+                    tree.within_radius(query, radius),
+                    within,
+                    "{query:?} {radius}"
+                );
+            }
+        }
+        let closest = (0..points.len())
+            .flat_map(|i| (i + 1..points.len()).map(move |j| points[i].distance(&points[j])))
+            .min_by(f32::total_cmp);
+        let pair = closest_pair(points);
+        assert_eq!(pair.map(|(i, j)| points[i].distance(&points[j])), closest);
+        assert!(pair.is_none_or(|(i, j)| i < j));
+    }
+
+    #[test]
+    fn brute_force_2d() {
+        let mut rng = StdRng::seed_from_u64(17);
+        for count in [0, 1, 2, 3, 10, 100, 400] {
+            // On a grid, so that there are duplicates and ties, or anywhere
+            let grid = |rng: &mut StdRng| {
+                Vec2::new(rng.gen_range(0..8) as f32, rng.gen_range(0..8) as f32) / 2.0
+            };
+            let any =
+                |rng: &mut StdRng| Vec2::new(rng.gen_range(0.0..4.0), rng.gen_range(0.0..4.0));
+            let points_of: [fn(&mut StdRng) -> Vec2; 2] = [grid, any];
+            for point in points_of {
+                let points: Vec<Vec2> = (0..count).map(|_| point(&mut rng)).collect();
+                let queries: Vec<Vec2> = (0..20).map(|_| point(&mut rng)).collect();
+                check(&points, &queries);
+            }
+        }
+    }
+
+    #[test]
+    fn brute_force_3d() {
+        let mut rng = StdRng::seed_from_u64(18);
+        for count in [0, 1, 2, 3, 10, 100, 400] {
+            let grid = |rng: &mut StdRng| {
+                IVec3::new(
+                    rng.gen_range(0..5),
+                    rng.gen_range(0..5),
+                    rng.gen_range(0..5),
+                )
+                .as_vec3()
+                    / 2.0
+            };
+            let any = |rng: &mut StdRng| {
+                Vec3::new(
+                    rng.gen_range(0.0..3.0),
+                    rng.gen_range(0.0..3.0),
+                    rng.gen_range(0.0..3.0),
+                )
+            };
+            let points_of: [fn(&mut StdRng) -> Vec3; 2] = [grid, any];
+            for point in points_of {
+                let points: Vec<Vec3> = (0..count).map(|_| point(&mut rng)).collect();
+                let queries: Vec<Vec3> = (0..20).map(|_| point(&mut rng)).collect();
+                check(&points, &queries);
+            }
+        }
+    }
+
+    #[test]
+    fn all_the_same() {
+        let points = [Vec2::ONE; 9];
+        check(&points, &[Vec2::ONE, Vec2::ZERO]);
+        assert_eq!(closest_pair(&points), Some((0, 1)));
+        assert_eq!(KdTree::new(&points).within_radius(Vec2::ONE, 0.0).len(), 9);
+    }
+}
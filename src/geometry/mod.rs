@@ -0,0 +1,13 @@
+pub mod alpha;
+pub mod boolean;
+pub mod circle;
+pub mod delaunay;
+pub mod enclosing;
+pub mod intersect;
+pub mod kdtree;
+pub mod point;
+pub mod polygon;
+pub mod predicates;
+pub mod sweep;
+pub mod triangulate;
+pub mod voronoi;
@@ -0,0 +1,235 @@
+//! Points for the planar algorithms that never compute new points and only compare the given
+//! ones, so a point type only has to answer a few exact predicates: the order by x then y, the
+//! side of a line and the nearer of two points. Floats go through the adaptive predicates,
+//! integers through `i128`, so no coordinate type changes the outcome.
+//!
+//! Generic over [`Point`] are the hull algorithms of [`ConvexHull`], [`ConvexLayers`] and
+//! [`spiral::from_layers`]. The rest of the geometry takes `Vec2`:
+//! - [`ConvexPolygon`], [`calipers`], alpha shapes, Voronoi and the boolean operations compute
+//!   new points, such as sums, circumcenters and crossings.
+//! - [`Delaunay`] needs the incircle test, which is not among the predicates above and does not
+//!   fit in `i128` for `i64` coordinates. It also orders the points along a Hilbert curve over
+//!   their coordinates.
+//! - [`triangulate`] takes a [`PolygonWithHoles`], whose nesting and area need arithmetic, and
+//!   leaves pinched vertices by the sharpest angle.
+//! - [`DynamicHull`] keys its chains by coordinate.
+//!
+//! [`ConvexHull`]: crate::convex::hull::ConvexHull
+//! [`ConvexLayers`]: crate::convex::layers::ConvexLayers
+//! [`spiral::from_layers`]: crate::convex::spiral::from_layers
+//! [`ConvexPolygon`]: crate::convex::polygon::ConvexPolygon
+//! [`calipers`]: crate::convex::calipers
+//! [`DynamicHull`]: crate::convex::dynamic::DynamicHull
+//! [`Delaunay`]: super::delaunay::Delaunay
+//! [`triangulate`]: super::triangulate
+//! [`PolygonWithHoles`]: super::polygon::PolygonWithHoles
+use super::predicates::{cross2d, farther2d, orient2d};
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use std::cmp::Ordering;
+
+/// Implement it for a point type of your own, either directly or by converting to one of the
+/// types below. Signs have to be exact for the results to be consistent.
+pub trait Point: Copy + PartialEq {
+    /// Whether the predicates can handle the point, false for NaN or infinite coordinates and for
+    /// integers too large for exact arithmetic.
+    fn is_finite(&self) -> bool;
+
+    /// Order by x, then by y. Points that are `==` have to be equal in this order, so that equal
+    /// points end up next to each other when sorted.
+    fn lexicographic(&self, other: &Self) -> Ordering;
+
+    /// Sign of the cross product `(b - a) x (d - c)`, `Greater` when `d - c` turns counter
+    /// clockwise from `b - a`.
+    fn cross(a: Self, b: Self, c: Self, d: Self) -> Ordering;
+
+    /// `Greater` if `a`, `b`, `c` are in counter clockwise order, `Less` if clockwise and `Equal`
+    /// if collinear.
+    fn orient(a: Self, b: Self, c: Self) -> Ordering {
+        Self::cross(a, b, a, c)
+    }
+
+    /// Distance from `p` to `q` compared with the distance from `p` to `r`.
+    fn compare_distance(p: Self, q: Self, r: Self) -> Ordering;
+}
+
+/// Converts to `DVec2`, which holds every `f32` exactly.
+impl Point for Vec2 {
+    fn is_finite(&self) -> bool {
+        Vec2::is_finite(*self)
+    }
+
+    fn lexicographic(&self, other: &Self) -> Ordering {
+        // Adding zero turns -0.0 into 0.0, which `==` does not tell apart
+        (self.x + 0.0)
+            .total_cmp(&(other.x + 0.0))
+            .then((self.y + 0.0).total_cmp(&(other.y + 0.0)))
+    }
+
+    fn cross(a: Self, b: Self, c: Self, d: Self) -> Ordering {
+        <DVec2 as Point>::cross(a.as_dvec2(), b.as_dvec2(), c.as_dvec2(), d.as_dvec2())
+    }
+
+    fn orient(a: Self, b: Self, c: Self) -> Ordering {
+        <DVec2 as Point>::orient(a.as_dvec2(), b.as_dvec2(), c.as_dvec2())
+    }
+
+    fn compare_distance(p: Self, q: Self, r: Self) -> Ordering {
+        <DVec2 as Point>::compare_distance(p.as_dvec2(), q.as_dvec2(), r.as_dvec2())
+    }
+}
+
+/// All signs are exact, the adaptive predicates take differences and squared distances exactly.
+impl Point for DVec2 {
+    fn is_finite(&self) -> bool {
+        DVec2::is_finite(*self)
+    }
+
+    fn lexicographic(&self, other: &Self) -> Ordering {
+        (self.x + 0.0)
+            .total_cmp(&(other.x + 0.0))
+            .then((self.y + 0.0).total_cmp(&(other.y + 0.0)))
+    }
+
+    fn cross(a: Self, b: Self, c: Self, d: Self) -> Ordering {
+        sign(cross2d(a, b, c, d))
+    }
+
+    fn orient(a: Self, b: Self, c: Self) -> Ordering {
+        sign(orient2d(a, b, c))
+    }
+
+    fn compare_distance(p: Self, q: Self, r: Self) -> Ordering {
+        sign(farther2d(p, q, r))
+    }
+}
+
+/// Exact for coordinates below 2^62 in magnitude, larger ones count as not finite.
+impl Point for [i64; 2] {
+    fn is_finite(&self) -> bool {
+        const LIMIT: i64 = 1 << 62;
+        self.iter().all(|c| (-LIMIT..LIMIT).contains(c))
+    }
+
+    fn lexicographic(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+
+    fn cross(a: Self, b: Self, c: Self, d: Self) -> Ordering {
+        let (u, v) = (difference(b, a), difference(d, c));
+        (u[0] * v[1]).cmp(&(u[1] * v[0]))
+    }
+
+    fn compare_distance(p: Self, q: Self, r: Self) -> Ordering {
+        let squared = |[x, y]: [i128; 2]| x * x + y * y;
+        squared(difference(q, p)).cmp(&squared(difference(r, p)))
+    }
+}
+
+fn difference(a: [i64; 2], b: [i64; 2]) -> [i128; 2] {
+    [a[0] as i128 - b[0] as i128, a[1] as i128 - b[1] as i128]
+}
+
+fn sign(value: f64) -> Ordering {
+    if value > 0.0 {
+        Ordering::Greater
+    } else if value < 0.0 {
+        Ordering::Less
+    } else {
+        Ordering::Equal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convex::hull::{ConvexHull, MonotoneChain};
+    use crate::convex::layers::ConvexLayers;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const LIMIT: i64 = 1 << 62;
+
+    #[test]
+    fn integers_agree_with_floats() {
+        let mut rng = StdRng::seed_from_u64(16);
+        let mut random = || [rng.gen_range(-20..20), rng.gen_range(-20..20)];
+        let float = |p: [i64; 2]| Vec2::new(p[0] as f32, p[1] as f32);
+        for _ in 0..10000 {
+            let [a, b, c, d] = [random(), random(), random(), random()];
+            let [fa, fb, fc, fd] = [a, b, c, d].map(float);
+            assert_eq!(a.lexicographic(&b), fa.lexicographic(&fb));
+            assert_eq!(Point::cross(a, b, c, d), Vec2::cross(fa, fb, fc, fd));
+            assert_eq!(Point::orient(a, b, c), Vec2::orient(fa, fb, fc));
+            assert_eq!(
+                Point::compare_distance(a, b, c),
+                Vec2::compare_distance(fa, fb, fc)
+            );
+        }
+    }
+
+    #[test]
+    fn floats_far_apart_in_magnitude() {
+        // 2^100 - 2^-100 rounds to 2^100 even in f64
+        let (big, tiny) = (2f32.powi(100), 2f32.powi(-100));
+        let (a, b) = (Vec2::new(tiny, 0.0), Vec2::new(big, 1.0));
+        assert_eq!(Vec2::cross(a, b, Vec2::ZERO, b), Ordering::Less);
+        assert_eq!(Vec2::cross(Vec2::ZERO, b, a, b), Ordering::Greater);
+        assert_eq!(Vec2::cross(a, b, a, b), Ordering::Equal);
+        let (q, r) = (Vec2::new(big, 0.0), Vec2::new(big, tiny));
+        assert_eq!(Vec2::compare_distance(Vec2::ZERO, q, r), Ordering::Less);
+        assert_eq!(Vec2::compare_distance(Vec2::ZERO, r, q), Ordering::Greater);
+        assert_eq!(Vec2::compare_distance(a, q, q), Ordering::Equal);
+    }
+
+    #[test]
+    fn integers_beyond_floats() {
+        assert!([LIMIT - 1, -LIMIT].is_finite());
+        assert!(![LIMIT, 0].is_finite());
+        assert!(![0, -LIMIT - 1].is_finite());
+
+        // One apart at 2^61, where f64 rounds them to the same point
+        let a = [1 << 61, 1 << 61];
+        let step = |p: [i64; 2], dx: i64, dy: i64| [p[0] + dx, p[1] + dy];
+        assert_eq!(
+            Point::orient(a, step(a, 1, 1), step(a, 2, 2)),
+            Ordering::Equal
+        );
+        assert_eq!(
+            Point::orient(a, step(a, 1, 1), step(a, 2, 3)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            Point::orient(a, step(a, 1, 1), step(a, 3, 2)),
+            Ordering::Less
+        );
+        assert_eq!(
+            Point::compare_distance(a, step(a, 3, 4), step(a, 5, 0)),
+            Ordering::Equal
+        );
+        assert_eq!(
+            Point::compare_distance(a, step(a, 3, 4), step(a, 5, 1)),
+            Ordering::Less
+        );
+
+        // Opposite corners, the largest differences there are
+        let (min, max) = ([-LIMIT, -LIMIT], [LIMIT - 1, LIMIT - 1]);
+        assert_eq!(
+            Point::compare_distance(min, max, step(max, 0, -1)),
+            Ordering::Greater
+        );
+        assert_eq!(Point::cross(min, max, max, min), Ordering::Equal);
+        assert_eq!(Point::orient(min, max, [LIMIT - 1, -LIMIT]), Ordering::Less);
+
+        // Next to the diagonal of the corners, a point is on the hull of the triangle above it
+        // only on the other side
+        let corners = [min, [LIMIT - 1, -LIMIT], max, [-LIMIT, LIMIT - 1]];
+        let triangle = [min, max, corners[3]];
+        for (extra, on_hull) in [([-1, 0], false), ([0, -1], true)] {
+            let layers = ConvexLayers::new(&[triangle.as_slice(), &[extra]].concat()).unwrap();
+            assert_eq!(layers.layers[0].contains(&extra), on_hull, "{extra:?}");
+            let hull = MonotoneChain.hull(&[corners.as_slice(), &[extra]].concat());
+            assert_eq!(hull.unwrap(), corners);
+        }
+        assert!(MonotoneChain.hull(&[[0, 0], [LIMIT, 0]]).is_err());
+    }
+}
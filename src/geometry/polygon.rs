@@ -0,0 +1,205 @@
+use super::predicates::orient2d;
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+/// Simple polygon with an outer boundary in counter clockwise order and holes in clockwise order.
+/// None of the rings are closed, i.e. the first vertex is not repeated at the end.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PolygonWithHoles {
+    pub outer: Vec<Vec2>,
+    pub holes: Vec<Vec<Vec2>>,
+}
+
+impl PolygonWithHoles {
+    /// Rings in any orientation, closed or not, e.g. from `convex::hull::algo`. They are turned
+    /// to follow the convention and a repeated first vertex is dropped.
+    pub fn new(outer: Vec<Vec2>, holes: Vec<Vec<Vec2>>) -> Self {
+        let orient = |mut ring: Vec<Vec2>, counter_clockwise: bool| {
+            if ring.len() > 1 && ring.first() == ring.last() {
+                ring.pop();
+            }
+            if (twice_signed_area(&ring) > 0.0) != counter_clockwise {
+                ring.reverse();
+            }
+            ring
+        };
+        Self {
+            outer: orient(outer, true),
+            holes: holes.into_iter().map(|hole| orient(hole, false)).collect(),
+        }
+    }
+
+    /// The vertices of the outer boundary followed by those of each hole, which is how indices
+    /// into the polygon count.
+    pub fn vertices(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.outer
+            .iter()
+            .chain(self.holes.iter().flatten())
+            .copied()
+    }
+
+    /// Area of the outer boundary minus that of the holes.
+    pub fn area(&self) -> f32 {
+        let area: f64 = std::iter::once(&self.outer)
+            .chain(&self.holes)
+            .map(|ring| twice_signed_area(ring))
+            .sum();
+        (area / 2.0) as f32
+    }
+
+    /// Whether the point is inside the outer boundary and outside all holes. Points on the
+    /// boundary may go either way.
+    pub fn contains(&self, point: Vec2) -> bool {
+        let point = point.as_dvec2();
+        winding_number(&self.outer, point) != 0
+            && self
+                .holes
+                .iter()
+                .all(|hole| winding_number(hole, point) == 0)
+    }
+}
+
+impl From<Vec<Vec2>> for PolygonWithHoles {
+    fn from(ring: Vec<Vec2>) -> Self {
+        Self::new(ring, vec![])
+    }
+}
+
+/// Sorts the rings into outer boundaries and holes by orientation, and puts each hole in the
+/// smallest boundary around it. Holes outside of all boundaries are dropped.
+///
+/// A hole may touch a boundary at any of its vertices, or even at all of them, so its vertices
+/// say nothing about which side it is on. Its edges do, the rings do not cross, so the midpoint of
+/// a hole edge that is not on the boundary is on the same side as the hole. The midpoints are taken
+/// in f64, as in f32 they may round onto the boundary. A hole running along the boundary all the
+/// way round covers the whole polygon and counts as inside.
+pub(crate) fn nest(rings: Vec<Vec<Vec2>>) -> Vec<PolygonWithHoles> {
+    let (mut outers, mut holes) = (vec![], vec![]);
+    for ring in rings {
+        let area = twice_signed_area(&ring);
+        if area > 0.0 {
+            outers.push((ring, area));
+        } else if area < 0.0 {
+            holes.push(ring);
+        }
+    }
+    let mut polygons: Vec<PolygonWithHoles> = outers
+        .iter()
+        .map(|(ring, _)| PolygonWithHoles {
+            outer: ring.clone(),
+            holes: vec![],
+        })
+        .collect();
+    for hole in holes {
+        let around = (0..outers.len())
+            .filter(|&o| {
+                let (outer, _) = &outers[o];
+                (0..hole.len())
+                    .map(|i| {
+                        hole[i]
+                            .as_dvec2()
+                            .midpoint(hole[(i + 1) % hole.len()].as_dvec2())
+                    })
+                    .find(|&p| !on_ring(outer, p))
+                    .is_none_or(|p| winding_number(outer, p) != 0)
+            })
+            .min_by(|&o, &p| outers[o].1.total_cmp(&outers[p].1));
+        if let Some(o) = around {
+            polygons[o].holes.push(hole);
+        }
+    }
+    polygons
+}
+
+/// Twice the area enclosed by the ring, positive if it is counter clockwise.
+pub(crate) fn twice_signed_area(ring: &[Vec2]) -> f64 {
+    let Some(origin) = ring.first().map(|p| p.as_dvec2()) else {
+        return 0.0;
+    };
+    (0..ring.len())
+        .map(|i| {
+            let (a, b) = (ring[i].as_dvec2(), ring[(i + 1) % ring.len()].as_dvec2());
+            (a - origin).perp_dot(b - origin)
+        })
+        .sum()
+}
+
+/// Whether the point is on an edge of the ring.
+fn on_ring(ring: &[Vec2], p: DVec2) -> bool {
+    (0..ring.len()).any(|i| {
+        let (a, b) = (ring[i].as_dvec2(), ring[(i + 1) % ring.len()].as_dvec2());
+        orient2d(a, b, p) == 0.0 && p.cmpge(a.min(b)).all() && p.cmple(a.max(b)).all()
+    })
+}
+
+/// How many times the ring winds counter clockwise around the point.
+pub(crate) fn winding_number(ring: &[Vec2], p: DVec2) -> i32 {
+    let mut winding = 0;
+    for i in 0..ring.len() {
+        let (a, b) = (ring[i].as_dvec2(), ring[(i + 1) % ring.len()].as_dvec2());
+        // Count the edges crossing the horizontal ray to the right of the point, by direction
+        if a.y <= p.y && b.y > p.y && orient2d(a, b, p) > 0.0 {
+            winding += 1;
+        } else if a.y > p.y && b.y <= p.y && orient2d(a, b, p) < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(size: f32) -> Vec<Vec2> {
+        vec![Vec2::ZERO, Vec2::X * size, Vec2::ONE * size, Vec2::Y * size]
+    }
+
+    #[test]
+    fn nests_holes_touching_the_boundary() {
+        // Diamond whose every vertex is on the outer square, inside and outside of it
+        let diamond = vec![
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(1.0, 2.0),
+            Vec2::new(2.0, 1.0),
+        ];
+        let polygons = nest(vec![square(2.0), diamond.clone()]);
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(polygons[0].holes, [diamond]);
+
+        let outside = vec![Vec2::ZERO, Vec2::new(-1.0, 1.0), Vec2::Y * 2.0];
+        assert!(nest(vec![square(2.0), outside])
+            .iter()
+            .all(|p| p.holes.is_empty()));
+
+        // Same ring as the boundary, and a hole sharing an edge with it
+        let mut reversed = square(2.0);
+        reversed.reverse();
+        assert_eq!(nest(vec![square(2.0), reversed]).remove(0).holes.len(), 1);
+        let corner = vec![Vec2::ZERO, Vec2::ONE, Vec2::X];
+        assert_eq!(nest(vec![square(2.0), corner]).remove(0).holes.len(), 1);
+
+        // The smallest boundary around it
+        let big: Vec<Vec2> = square(4.0).into_iter().map(|p| p - Vec2::ONE).collect();
+        let polygons = nest(vec![
+            big,
+            square(2.0),
+            vec![Vec2::ZERO, Vec2::ONE, Vec2::X * 2.0],
+        ]);
+        assert_eq!(
+            polygons.iter().map(|p| p.holes.len()).collect::<Vec<_>>(),
+            [0, 1]
+        );
+
+        // Sliver on top of the outer boundary, outside of it, whose edge midpoints are one above
+        // it, where f32 has no value between it and two above
+        let side = (1 << 24) as f32;
+        let sliver = vec![
+            Vec2::new(0.0, side),
+            Vec2::new(side / 2.0, side + 2.0),
+            Vec2::splat(side),
+        ];
+        assert!(nest(vec![square(side), sliver])[0].holes.is_empty());
+    }
+}
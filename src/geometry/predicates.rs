@@ -0,0 +1,488 @@
+//! Adaptive precision geometric predicates after Shewchuk's "Adaptive Precision Floating-Point
+//! Arithmetic and Fast Robust Geometric Predicates".
+//!
+//! Each predicate first evaluates the determinant in plain `f64` together with a forward error
+//! bound. Only when the result is too close to zero to be trusted is the determinant recomputed
+//! exactly with floating point expansions. The sign of the returned value is always exact, its
+//! magnitude is an approximation of the determinant. A zero determinant is always positive zero,
+//! so the sign can be taken with `total_cmp(&0.0)`.
+use bevy::math::{DVec2, DVec3};
+
+const EPSILON: f64 = f64::EPSILON / 2.0;
+const ORIENT2D_BOUND: f64 = (3.0 + 16.0 * EPSILON) * EPSILON;
+const ORIENT3D_BOUND: f64 = (7.0 + 56.0 * EPSILON) * EPSILON;
+const INCIRCLE_BOUND: f64 = (10.0 + 96.0 * EPSILON) * EPSILON;
+const FARTHER2D_BOUND: f64 = (5.0 + 64.0 * EPSILON) * EPSILON;
+
+/// Positive if `a`, `b`, `c` are in counter clockwise order, negative if clockwise and positive
+/// zero if collinear.
+pub fn orient2d(a: DVec2, b: DVec2, c: DVec2) -> f64 {
+    let det_left = (a.x - c.x) * (b.y - c.y);
+    let det_right = (a.y - c.y) * (b.x - c.x);
+    let det = det_left - det_right;
+    if det != 0.0 && det.abs() >= ORIENT2D_BOUND * (det_left.abs() + det_right.abs()) {
+        return det;
+    }
+    most_significant(&sum_all([
+        product(a.x, b.y),
+        product(-a.x, c.y),
+        product(-c.x, b.y),
+        product(-a.y, b.x),
+        product(a.y, c.x),
+        product(c.y, b.x),
+    ]))
+}
+
+/// Positive if `d - c` turns counter clockwise from `b - a`, negative if clockwise and positive
+/// zero if parallel. The differences are taken exactly, unlike passing them to [`orient2d`].
+pub fn cross2d(a: DVec2, b: DVec2, c: DVec2, d: DVec2) -> f64 {
+    let (u, v) = (b - a, d - c);
+    let (det_left, det_right) = (u.x * v.y, u.y * v.x);
+    let det = det_left - det_right;
+    // Same error bound as `orient2d`, which also multiplies two rounded differences
+    if det != 0.0 && det.abs() >= ORIENT2D_BOUND * (det_left.abs() + det_right.abs()) {
+        return det;
+    }
+    let (u, v) = (exact_diff2(b, a), exact_diff2(d, c));
+    most_significant(&add(&mul(&u[0], &v[1]), &negate(&mul(&u[1], &v[0]))))
+}
+
+/// Positive if `q` is farther from `p` than `r`, negative if nearer and positive zero if both are
+/// as far.
+pub fn farther2d(p: DVec2, q: DVec2, r: DVec2) -> f64 {
+    let (q_lift, r_lift) = (p.distance_squared(q), p.distance_squared(r));
+    let det = q_lift - r_lift;
+    if det != 0.0 && det.abs() >= FARTHER2D_BOUND * (q_lift + r_lift) {
+        return det;
+    }
+    let (qp, rp) = (exact_diff2(q, p), exact_diff2(r, p));
+    let lift = |d: &[Vec<f64>; 2]| add(&mul(&d[0], &d[0]), &mul(&d[1], &d[1]));
+    most_significant(&add(&lift(&qp), &negate(&lift(&rp))))
+}
+
+/// Positive if `d` is below the plane through `a`, `b`, `c`, where below is the side from which
+/// `a`, `b`, `c` appear clockwise. Negative if above and positive zero if the four points are
+/// coplanar.
+pub fn orient3d(a: DVec3, b: DVec3, c: DVec3, d: DVec3) -> f64 {
+    let (ad, bd, cd) = (a - d, b - d, c - d);
+    let (bc, cb) = (bd.x * cd.y, cd.x * bd.y);
+    let (ca, ac) = (cd.x * ad.y, ad.x * cd.y);
+    let (ab, ba) = (ad.x * bd.y, bd.x * ad.y);
+    let det = ad.z * (bc - cb) + bd.z * (ca - ac) + cd.z * (ab - ba);
+    let permanent = (bc.abs() + cb.abs()) * ad.z.abs()
+        + (ca.abs() + ac.abs()) * bd.z.abs()
+        + (ab.abs() + ba.abs()) * cd.z.abs();
+    if det != 0.0 && det.abs() >= ORIENT3D_BOUND * permanent {
+        return det;
+    }
+    let (ad, bd, cd) = (exact_diff3(a, d), exact_diff3(b, d), exact_diff3(c, d));
+    let minor =
+        |p: &[Vec<f64>; 3], q: &[Vec<f64>; 3]| add(&mul(&p[0], &q[1]), &negate(&mul(&q[0], &p[1])));
+    most_significant(&add(
+        &add(
+            &mul(&ad[2], &minor(&bd, &cd)),
+            &mul(&bd[2], &minor(&cd, &ad)),
+        ),
+        &mul(&cd[2], &minor(&ad, &bd)),
+    ))
+}
+
+/// Positive if `d` is inside the circle through `a`, `b`, `c`, negative if outside and positive
+/// zero if cocircular. `a`, `b`, `c` must be in counter clockwise order, otherwise the sign is flipped.
+pub fn incircle(a: DVec2, b: DVec2, c: DVec2, d: DVec2) -> f64 {
+    let (ad, bd, cd) = (a - d, b - d, c - d);
+    let (bc, cb) = (bd.x * cd.y, cd.x * bd.y);
+    let (ca, ac) = (cd.x * ad.y, ad.x * cd.y);
+    let (ab, ba) = (ad.x * bd.y, bd.x * ad.y);
+    let (a_lift, b_lift, c_lift) = (
+        ad.length_squared(),
+        bd.length_squared(),
+        cd.length_squared(),
+    );
+    let det = a_lift * (bc - cb) + b_lift * (ca - ac) + c_lift * (ab - ba);
+    let permanent = (bc.abs() + cb.abs()) * a_lift
+        + (ca.abs() + ac.abs()) * b_lift
+        + (ab.abs() + ba.abs()) * c_lift;
+    if det != 0.0 && det.abs() >= INCIRCLE_BOUND * permanent {
+        return det;
+    }
+    let (ad, bd, cd) = (exact_diff2(a, d), exact_diff2(b, d), exact_diff2(c, d));
+    let lift = |p: &[Vec<f64>; 2]| add(&mul(&p[0], &p[0]), &mul(&p[1], &p[1]));
+    let minor =
+        |p: &[Vec<f64>; 2], q: &[Vec<f64>; 2]| add(&mul(&p[0], &q[1]), &negate(&mul(&q[0], &p[1])));
+    most_significant(&add(
+        &add(
+            &mul(&lift(&ad), &minor(&bd, &cd)),
+            &mul(&lift(&bd), &minor(&cd, &ad)),
+        ),
+        &mul(&lift(&cd), &minor(&ad, &bd)),
+    ))
+}
+
+// Expansions are sums of non overlapping f64 components stored in increasing order of magnitude,
+// with zero components eliminated. The largest component carries the sign of the whole sum.
+
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let b_virtual = x - a;
+    let a_virtual = x - b_virtual;
+    (x, (a - a_virtual) + (b - b_virtual))
+}
+
+fn product(a: f64, b: f64) -> Vec<f64> {
+    let x = a * b;
+    let y = a.mul_add(b, -x);
+    [y, x].into_iter().filter(|&e| e != 0.0).collect()
+}
+
+fn exact_diff2(a: DVec2, b: DVec2) -> [Vec<f64>; 2] {
+    [difference(a.x, b.x), difference(a.y, b.y)]
+}
+
+fn exact_diff3(a: DVec3, b: DVec3) -> [Vec<f64>; 3] {
+    [
+        difference(a.x, b.x),
+        difference(a.y, b.y),
+        difference(a.z, b.z),
+    ]
+}
+
+fn difference(a: f64, b: f64) -> Vec<f64> {
+    let (x, y) = two_sum(a, -b);
+    [y, x].into_iter().filter(|&e| e != 0.0).collect()
+}
+
+fn grow(e: &[f64], b: f64) -> Vec<f64> {
+    let mut h = Vec::with_capacity(e.len() + 1);
+    let mut q = b;
+    for &component in e {
+        let (sum, err) = two_sum(q, component);
+        if err != 0.0 {
+            h.push(err);
+        }
+        q = sum;
+    }
+    if q != 0.0 {
+        h.push(q);
+    }
+    h
+}
+
+fn add(e: &[f64], f: &[f64]) -> Vec<f64> {
+    f.iter()
+        .fold(e.to_vec(), |sum, &component| grow(&sum, component))
+}
+
+fn sum_all<const N: usize>(expansions: [Vec<f64>; N]) -> Vec<f64> {
+    expansions.iter().fold(vec![], |sum, e| add(&sum, e))
+}
+
+fn negate(e: &[f64]) -> Vec<f64> {
+    e.iter().map(|component| -component).collect()
+}
+
+fn scale(e: &[f64], b: f64) -> Vec<f64> {
+    let mut h = Vec::with_capacity(2 * e.len());
+    let Some((&first, rest)) = e.split_first() else {
+        return h;
+    };
+    let (mut q, err) = (first * b, first.mul_add(b, -(first * b)));
+    if err != 0.0 {
+        h.push(err);
+    }
+    for &component in rest {
+        let (product_hi, product_lo) = (component * b, component.mul_add(b, -(component * b)));
+        let (sum, err) = two_sum(q, product_lo);
+        if err != 0.0 {
+            h.push(err);
+        }
+        let (sum, err) = two_sum(product_hi, sum);
+        if err != 0.0 {
+            h.push(err);
+        }
+        q = sum;
+    }
+    if q != 0.0 {
+        h.push(q);
+    }
+    h
+}
+
+fn mul(e: &[f64], f: &[f64]) -> Vec<f64> {
+    f.iter()
+        .fold(vec![], |sum, &component| add(&sum, &scale(e, component)))
+}
+
+fn most_significant(e: &[f64]) -> f64 {
+    e.last().copied().unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convex::hull::{ConvexHull, MonotoneChain, QuickHull};
+    use crate::convex::{layers::ConvexLayers, spiral};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // Coordinates are integers times 2^-shift, exact in f64 below 2^53, so the sign of each
+    // determinant can be checked against i128 arithmetic on the differences of the integers.
+
+    fn to_f64(n: i64, shift: i32) -> f64 {
+        n as f64 * 2f64.powi(-shift)
+    }
+
+    fn point2(p: [i64; 2], shift: i32) -> DVec2 {
+        DVec2::new(to_f64(p[0], shift), to_f64(p[1], shift))
+    }
+
+    fn point3(p: [i64; 3], shift: i32) -> DVec3 {
+        DVec3::new(
+            to_f64(p[0], shift),
+            to_f64(p[1], shift),
+            to_f64(p[2], shift),
+        )
+    }
+
+    fn diff<const D: usize>(a: [i64; D], b: [i64; D]) -> [i128; D] {
+        std::array::from_fn(|i| a[i] as i128 - b[i] as i128)
+    }
+
+    fn exact_orient2d(a: [i64; 2], b: [i64; 2], c: [i64; 2]) -> i128 {
+        let (ac, bc) = (diff(a, c), diff(b, c));
+        ac[0] * bc[1] - ac[1] * bc[0]
+    }
+
+    fn exact_orient3d(a: [i64; 3], b: [i64; 3], c: [i64; 3], d: [i64; 3]) -> i128 {
+        let (ad, bd, cd) = (diff(a, d), diff(b, d), diff(c, d));
+        ad[2] * (bd[0] * cd[1] - cd[0] * bd[1])
+            + bd[2] * (cd[0] * ad[1] - ad[0] * cd[1])
+            + cd[2] * (ad[0] * bd[1] - bd[0] * ad[1])
+    }
+
+    fn exact_incircle(a: [i64; 2], b: [i64; 2], c: [i64; 2], d: [i64; 2]) -> i128 {
+        let (ad, bd, cd) = (diff(a, d), diff(b, d), diff(c, d));
+        let lift = |p: [i128; 2]| p[0] * p[0] + p[1] * p[1];
+        lift(ad) * (bd[0] * cd[1] - cd[0] * bd[1])
+            + lift(bd) * (cd[0] * ad[1] - ad[0] * cd[1])
+            + lift(cd) * (ad[0] * bd[1] - bd[0] * ad[1])
+    }
+
+    /// Also fails on negative zero, which `total_cmp` would order below zero.
+    fn same_sign(approximate: f64, exact: i128) -> bool {
+        approximate.total_cmp(&0.0) == exact.cmp(&0)
+    }
+
+    /// Large offset shared by all points, so that the differences lose all their leading bits.
+    fn offset(rng: &mut StdRng) -> i64 {
+        rng.gen_range(-(1i64 << 50)..1 << 50)
+    }
+
+    /// Points a few units off the line through two of them, all exactly on it for no jitter.
+    fn near_collinear(rng: &mut StdRng, count: usize, jitter: i64) -> Vec<[i64; 2]> {
+        let base = [offset(rng), offset(rng)];
+        let direction = [
+            rng.gen_range(-1 << 20..1 << 20),
+            rng.gen_range(-1 << 20..1 << 20),
+        ];
+        (0..count)
+            .map(|_| {
+                let t = rng.gen_range(-64..64);
+                std::array::from_fn(|i| {
+                    base[i] + t * direction[i] + rng.gen_range(-jitter..=jitter)
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn orient2d_near_collinear() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..2000 {
+            let shift = rng.gen_range(0..60);
+            let jitter = rng.gen_range(0..3);
+            let [a, b, c] = near_collinear(&mut rng, 3, jitter)[..] else {
+                unreachable!()
+            };
+            let approximate = orient2d(point2(a, shift), point2(b, shift), point2(c, shift));
+            assert!(
+                same_sign(approximate, exact_orient2d(a, b, c)),
+                "{a:?} {b:?} {c:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn orient3d_near_coplanar() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut small = || rng.gen_range(-1i64 << 12..1 << 12);
+        for _ in 0..2000 {
+            let base = [small() << 38, small() << 38, small() << 38];
+            let [a, b, c]: [[i64; 3]; 3] =
+                std::array::from_fn(|_| std::array::from_fn(|i| base[i] + small()));
+            let (u, v) = (small() % 8, small() % 8);
+            let d =
+                std::array::from_fn(|i| a[i] + u * (b[i] - a[i]) + v * (c[i] - a[i]) + small() % 2);
+            let shift = small().rem_euclid(60) as i32;
+            let approximate = orient3d(
+                point3(a, shift),
+                point3(b, shift),
+                point3(c, shift),
+                point3(d, shift),
+            );
+            assert!(
+                same_sign(approximate, exact_orient3d(a, b, c, d)),
+                "{a:?} {b:?} {c:?} {d:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn degenerate_is_positive_zero() {
+        // Products like -1 * 0 make the plain determinant negative zero
+        let (a, b, c) = (DVec2::ZERO, DVec2::X * 2.0, DVec2::X);
+        assert!(orient2d(a, b, c).is_sign_positive());
+        assert!(orient2d(c, a, b).is_sign_positive());
+        let up = |p: DVec2| p.extend(0.0);
+        assert!(orient3d(up(a), up(b), DVec3::Y, up(c)).is_sign_positive());
+        assert!(orient3d(up(c), DVec3::Y, up(a), DVec3::X * -3.0).is_sign_positive());
+        let square = [DVec2::ZERO, DVec2::X, DVec2::ONE, DVec2::Y];
+        assert!(incircle(square[0], square[1], square[2], square[3]).is_sign_positive());
+        assert!(incircle(square[3], square[1], square[2], square[0]).is_sign_positive());
+    }
+
+    #[test]
+    fn incircle_near_cocircular() {
+        // Lattice points on the circle of radius 5 * 13 * 17 around the origin
+        const RADIUS: i64 = 5 * 13 * 17;
+        let circle: Vec<[i64; 2]> = (-RADIUS..=RADIUS)
+            .flat_map(|x| {
+                let y = ((RADIUS * RADIUS - x * x) as f64).sqrt() as i64;
+                [[x, y], [x, -y]]
+            })
+            .filter(|&[x, y]| x * x + y * y == RADIUS * RADIUS)
+            .collect();
+        assert!(circle.len() > 16);
+        let mut rng = StdRng::seed_from_u64(4);
+        for _ in 0..2000 {
+            let center = [offset(&mut rng) >> 20, offset(&mut rng) >> 20];
+            let scale = rng.gen_range(1..1 << 10);
+            let [a, b, c, d]: [[i64; 2]; 4] = std::array::from_fn(|_| {
+                let p = circle[rng.gen_range(0..circle.len())];
+                std::array::from_fn(|i| center[i] + scale * p[i])
+            });
+            let d = [d[0] + rng.gen_range(-1..=1), d[1] + rng.gen_range(-1..=1)];
+            let shift = rng.gen_range(0..60);
+            let approximate = incircle(
+                point2(a, shift),
+                point2(b, shift),
+                point2(c, shift),
+                point2(d, shift),
+            );
+            assert!(
+                same_sign(approximate, exact_incircle(a, b, c, d)),
+                "{a:?} {b:?} {c:?} {d:?}"
+            );
+        }
+    }
+
+    /// Sum of two terms far apart in magnitude, so that differences need more bits than f64 has.
+    fn sparse(rng: &mut StdRng) -> i64 {
+        (0..2)
+            .map(|_| rng.gen_range(-128..128) << rng.gen_range(0..54))
+            .sum()
+    }
+
+    #[test]
+    fn cross2d_and_farther2d_near_degenerate() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let representable = |p: &[i64; 2]| p.iter().all(|&n| n as f64 as i64 == n);
+        let lift = |p: [i128; 2]| p[0] * p[0] + p[1] * p[1];
+        let mut checked = 0;
+        for _ in 0..20000 {
+            let [a, b, c] = [(); 3].map(|_| [sparse(&mut rng), sparse(&mut rng)]);
+            let jitter = [rng.gen_range(-1..=1), rng.gen_range(-1..=1)];
+            // Up to the jitter, d - c is b - a and e is as far from a as b
+            let d = [0, 1].map(|i| c[i] + b[i] - a[i] + jitter[i]);
+            let e = [
+                a[0] + a[1] - b[1] + jitter[0],
+                a[1] + b[0] - a[0] + jitter[1],
+            ];
+            if ![a, b, c, d, e].iter().all(representable) {
+                continue;
+            }
+            checked += 1;
+            let shift = rng.gen_range(0..60);
+            let [fa, fb, fc, fd, fe] = [a, b, c, d, e].map(|p| point2(p, shift));
+            let (u, v) = (diff(b, a), diff(d, c));
+            assert!(
+                same_sign(cross2d(fa, fb, fc, fd), u[0] * v[1] - u[1] * v[0]),
+                "{a:?} {b:?} {c:?} {d:?}"
+            );
+            assert!(
+                same_sign(farther2d(fa, fb, fe), lift(u) - lift(diff(e, a))),
+                "{a:?} {b:?} {e:?}"
+            );
+        }
+        assert!(checked > 1000, "{checked}");
+    }
+
+    fn segments_cross(p: [[i64; 2]; 2], q: [[i64; 2]; 2]) -> bool {
+        let side = |a, b, c| exact_orient2d(a, b, c).signum();
+        let within = |a: [i64; 2], b: [i64; 2], c: [i64; 2]| {
+            (0..2).all(|i| a[i].min(b[i]) <= c[i] && c[i] <= a[i].max(b[i]))
+        };
+        let (d1, d2) = (side(q[0], q[1], p[0]), side(q[0], q[1], p[1]));
+        let (d3, d4) = (side(p[0], p[1], q[0]), side(p[0], p[1], q[1]));
+        (d1 * d2 < 0 && d3 * d4 < 0)
+            || (d1 == 0 && within(q[0], q[1], p[0]))
+            || (d2 == 0 && within(q[0], q[1], p[1]))
+            || (d3 == 0 && within(p[0], p[1], q[0]))
+            || (d4 == 0 && within(p[0], p[1], q[1]))
+    }
+
+    #[test]
+    fn hull_and_spiral_near_collinear() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..300 {
+            let shift = rng.gen_range(0..60);
+            let count = rng.gen_range(3..40);
+            let jitter = rng.gen_range(0..3);
+            let integers = near_collinear(&mut rng, count, jitter);
+            let points: Vec<DVec2> = integers.iter().map(|&p| point2(p, shift)).collect();
+            let back = |p: DVec2| integers[points.iter().position(|&q| q == p).unwrap()];
+
+            for hull in [MonotoneChain.hull(&points), QuickHull.hull(&points)] {
+                let hull: Vec<[i64; 2]> = hull.unwrap().into_iter().map(back).collect();
+                if hull.len() >= 3 {
+                    for i in 0..hull.len() {
+                        let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+                        assert!(exact_orient2d(a, b, hull[(i + 2) % hull.len()]) > 0);
+                        assert!(integers.iter().all(|&p| exact_orient2d(a, b, p) >= 0));
+                    }
+                } else {
+                    assert!(integers.iter().all(|&p| match hull[..] {
+                        [a, b] => exact_orient2d(a, b, p) == 0,
+                        _ => true,
+                    }));
+                }
+            }
+
+            let polyline: Vec<[i64; 2]> = spiral::from_layers(&ConvexLayers::new(&points).unwrap())
+                .into_iter()
+                .map(back)
+                .collect();
+            let segments: Vec<[[i64; 2]; 2]> = polyline.windows(2).map(|w| [w[0], w[1]]).collect();
+            for i in 0..segments.len() {
+                if let Some(&next) = segments.get(i + 1) {
+                    let [a, b, c] = [segments[i][0], segments[i][1], next[1]];
+                    let backwards =
+                        diff(b, a)[0] * diff(c, b)[0] + diff(b, a)[1] * diff(c, b)[1] < 0;
+                    assert!(exact_orient2d(a, b, c) != 0 || !backwards, "{polyline:?}");
+                }
+                for j in i + 2..segments.len() {
+                    assert!(!segments_cross(segments[i], segments[j]), "{polyline:?}");
+                }
+            }
+        }
+    }
+}
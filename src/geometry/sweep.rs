@@ -0,0 +1,522 @@
+//! All crossings among a set of segments with the Bentley-Ottmann sweep, in O((n + k) log n) for
+//! n segments meeting at k points. A vertical line sweeps from left to right, stopping at end
+//! points and at crossings, and keeps the segments it cuts ordered from bottom to top. Two
+//! segments can only cross after being next to each other in that order, so only neighbors are
+//! ever tested.
+use super::predicates::orient2d;
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use ordered_float::OrderedFloat;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Point where two or more segments meet, with the indices of all segments through it in
+/// increasing order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Crossing {
+    pub point: Vec2,
+    pub segments: Vec<usize>,
+}
+
+/// Every point where segments cross or touch. Segments that overlap meet at both ends of the
+/// overlap. Segments with non finite end points are left out.
+pub fn crossings(segments: &[(Vec2, Vec2)]) -> Vec<Crossing> {
+    let segments: Vec<(DVec2, DVec2)> = segments
+        .iter()
+        .map(|&(a, b)| {
+            let (a, b) = (a.as_dvec2(), b.as_dvec2());
+            if (a.x, a.y) <= (b.x, b.y) {
+                (a, b)
+            } else {
+                (b, a)
+            }
+        })
+        .collect();
+    let scale = segments
+        .iter()
+        .filter(|(a, b)| a.is_finite() && b.is_finite())
+        .map(|(a, b)| a.abs().max(b.abs()).max_element())
+        .fold(0.0, f64::max);
+    let sweep = Sweep {
+        segments,
+        tolerance: scale * 1e-10,
+    };
+    sweep.run()
+}
+
+/// Points where the polyline crosses or touches itself, where segment `i` runs from
+/// `polyline[i]` to `polyline[i + 1]`. Consecutive segments only count when they fold back onto
+/// each other. A polyline whose last vertex is its first is closed, and its last segment follows
+/// on the first.
+pub fn self_crossings(polyline: &[Vec2]) -> Vec<Crossing> {
+    // Repeated vertices make empty segments, which would join the segments around them
+    let kept: Vec<usize> = (0..polyline.len().saturating_sub(1))
+        .filter(|&i| polyline[i] != polyline[i + 1])
+        .collect();
+    let closed = kept.len() > 2 && polyline.first() == polyline.last();
+    let segments: Vec<(Vec2, Vec2)> = kept
+        .iter()
+        .map(|&i| (polyline[i], polyline[i + 1]))
+        .collect();
+    // Vertex shared with the next segment, if consecutive
+    let joint = |k: usize, l: usize| {
+        let (k, l) = (k.min(l), k.max(l));
+        if l == k + 1 {
+            Some(segments[k].1)
+        } else if closed && k == 0 && l == kept.len() - 1 {
+            Some(segments[k].0)
+        } else {
+            None
+        }
+    };
+    crossings(&segments)
+        .into_iter()
+        .filter(|crossing| {
+            let s = &crossing.segments;
+            (0..s.len())
+                .any(|i| (i + 1..s.len()).any(|j| joint(s[i], s[j]) != Some(crossing.point)))
+        })
+        .map(|crossing| Crossing {
+            point: crossing.point,
+            segments: crossing.segments.iter().map(|&k| kept[k]).collect(),
+        })
+        .collect()
+}
+
+pub fn is_self_intersecting(polyline: &[Vec2]) -> bool {
+    !self_crossings(polyline).is_empty()
+}
+
+/// Segments from their lower left to their upper right end point.
+struct Sweep {
+    segments: Vec<(DVec2, DVec2)>,
+    /// Heights closer than this are taken to be the same, to cover the rounding of crossings
+    tolerance: f64,
+}
+
+#[derive(Clone, Copy)]
+enum Key {
+    Segment(usize),
+    /// Below or above every segment through the point of the entry
+    Below,
+    Above,
+    /// The same as every segment through the point of the entry
+    Through,
+}
+
+/// Key with the event it was made at. Segments in the status keep their order from the event
+/// that put them in until they are taken out again, so two entries compare where the sweep was
+/// at the later of their events, and the order does not depend on where the sweep is now.
+#[derive(Clone, Copy)]
+struct Entry<'a> {
+    sweep: &'a Sweep,
+    at: DVec2,
+    key: Key,
+}
+
+impl PartialEq for Entry<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry<'_> {}
+
+impl PartialOrd for Entry<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let at = if key(self.at) >= key(other.at) {
+            self.at
+        } else {
+            other.at
+        };
+        self.sweep.compare(at, self.key, other.key)
+    }
+}
+
+impl Sweep {
+    fn run(&self) -> Vec<Crossing> {
+        let mut events: BTreeMap<(OrderedFloat<f64>, OrderedFloat<f64>), Vec<usize>> =
+            BTreeMap::new();
+        for (i, &(a, b)) in self.segments.iter().enumerate() {
+            if a.is_finite() && b.is_finite() {
+                events.entry(key(a)).or_default().push(i);
+                events.entry(key(b)).or_default();
+            }
+        }
+        let mut status: BTreeSet<Entry> = BTreeSet::new();
+        let mut crossings = vec![];
+        // Crossings that each segment is in
+        let mut met = vec![vec![]; self.segments.len()];
+        while let Some(((x, y), starting)) = events.pop_first() {
+            let p = DVec2::new(x.0, y.0);
+            let entry = |key: Key| Entry {
+                sweep: self,
+                at: p,
+                key,
+            };
+            // Everything through the point is taken out and put back in its order after the
+            // point, which swaps the segments crossing there
+            let mut through = vec![];
+            while let Some(e) = status.take(&entry(Key::Through)) {
+                through.push(self.segment(&e));
+            }
+            let mut meeting: Vec<usize> = through.iter().chain(&starting).copied().collect();
+            if meeting.len() > 1 {
+                meeting.sort_unstable();
+                for &s in &meeting {
+                    met[s].push(crossings.len());
+                }
+                crossings.push(Crossing {
+                    point: p.as_vec2(),
+                    segments: meeting,
+                });
+            }
+            for &s in through.iter().chain(&starting) {
+                let (_, b) = self.segments[s];
+                if b != p {
+                    status.insert(entry(Key::Segment(s)));
+                }
+            }
+            let below = status.range(..entry(Key::Below)).next_back();
+            let above = status.range(entry(Key::Above)..).next();
+            let mut around = status.range(entry(Key::Below)..=entry(Key::Above));
+            let pairs = match (around.next(), around.next_back()) {
+                (Some(lowest), highest) => {
+                    [(below, Some(lowest)), (highest.or(Some(lowest)), above)]
+                }
+                (None, _) => [(below, above), (None, None)],
+            };
+            for (lower, upper) in pairs {
+                let (Some(lower), Some(upper)) = (lower, upper) else {
+                    continue;
+                };
+                let (s, t) = (self.segment(lower), self.segment(upper));
+                let Some(q) = self.crossing(s, t) else {
+                    continue;
+                };
+                // An event within the tolerance of the crossing is the crossing, if both segments
+                // go through it. Steep segments can be close to an event and still pass it
+                let meet = |e: DVec2| {
+                    (e - q).abs().max_element() <= self.tolerance
+                        && self.through(s, e)
+                        && self.through(t, e)
+                };
+                if meet(p) {
+                    continue;
+                }
+                if key(q) > key(p) {
+                    let known = events
+                        .range(
+                            key(DVec2::new(q.x - self.tolerance, f64::NEG_INFINITY))
+                                ..=key(DVec2::new(q.x + self.tolerance, f64::INFINITY)),
+                        )
+                        .any(|(&(x, y), _)| meet(DVec2::new(x.0, y.0)));
+                    if !known {
+                        events.insert(key(q), vec![]);
+                    }
+                } else if !met[s].iter().any(|&i| crossings[i].segments.contains(&t)) {
+                    // Rounding put the crossing behind the sweep, so the segments only became
+                    // neighbors after it and are in their order after it already
+                    for r in [s, t] {
+                        met[r].push(crossings.len());
+                    }
+                    crossings.push(Crossing {
+                        point: q.as_vec2(),
+                        segments: vec![s.min(t), s.max(t)],
+                    });
+                }
+            }
+        }
+        crossings
+    }
+
+    fn segment(&self, entry: &Entry) -> usize {
+        match entry.key {
+            Key::Segment(s) => s,
+            _ => unreachable!("only segments are kept in the status"),
+        }
+    }
+
+    /// Height of the segment where the sweep cuts it at the point. The sweep line stops at a point
+    /// rather than at an x coordinate, so that it is bent just below the point for vertical
+    /// segments.
+    fn height(&self, segment: usize, at: DVec2) -> f64 {
+        let (a, b) = self.segments[segment];
+        if a.x == b.x {
+            at.y.clamp(a.y, b.y)
+        } else if at.x <= a.x {
+            a.y
+        } else if at.x >= b.x {
+            b.y
+        } else {
+            a.y + (b.y - a.y) * ((at.x - a.x) / (b.x - a.x))
+        }
+    }
+
+    /// Whether the segment goes through the point, within the tolerance of its height.
+    fn through(&self, segment: usize, at: DVec2) -> bool {
+        (self.height(segment, at) - at.y).abs() <= self.tolerance
+    }
+
+    /// Order of the keys just after the sweep has passed the point. Segments through the point
+    /// are put at its height exactly, rather than comparing heights within the tolerance of each
+    /// other, which would not be transitive.
+    fn compare(&self, at: DVec2, first: Key, second: Key) -> Ordering {
+        let height = |key: Key| match key {
+            Key::Segment(s) if !self.through(s, at) => self.height(s, at),
+            _ => at.y,
+        };
+        let ordering = height(first).total_cmp(&height(second));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        match (first, second) {
+            (Key::Below, Key::Below)
+            | (Key::Above, Key::Above)
+            | (Key::Through, _)
+            | (_, Key::Through) => Ordering::Equal,
+            (Key::Below, _) | (_, Key::Above) => Ordering::Less,
+            (Key::Above, _) | (_, Key::Below) => Ordering::Greater,
+            (Key::Segment(s), Key::Segment(t)) => {
+                // Through the same point, the steeper segment is the higher one after it
+                let direction = |s: usize| self.segments[s].1 - self.segments[s].0;
+                orient2d(DVec2::ZERO, direction(s), direction(t))
+                    .total_cmp(&0.0)
+                    .reverse()
+                    .then(s.cmp(&t))
+            }
+        }
+    }
+
+    /// Point where two segments cross inside both of them. Touching at an end point is left to
+    /// the event of that end point.
+    fn crossing(&self, s: usize, t: usize) -> Option<DVec2> {
+        let ((a, b), (c, d)) = (self.segments[s], self.segments[t]);
+        let side = |p: DVec2, q: DVec2, r: DVec2| orient2d(p, q, r).total_cmp(&0.0);
+        let opposite = |x: Ordering, y: Ordering| x != Ordering::Equal && x == y.reverse();
+        if !opposite(side(a, b, c), side(a, b, d)) || !opposite(side(c, d, a), side(c, d, b)) {
+            return None;
+        }
+        let (u, v) = (b - a, d - c);
+        Some(a + u * (orient2d(DVec2::ZERO, c - a, v) / orient2d(DVec2::ZERO, u, v)))
+    }
+}
+
+fn key(point: DVec2) -> (OrderedFloat<f64>, OrderedFloat<f64>) {
+    (OrderedFloat(point.x), OrderedFloat(point.y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn cross(a: (Vec2, Vec2), b: (Vec2, Vec2)) -> bool {
+        let side = |p: Vec2, q: Vec2, r: Vec2| orient2d(p.as_dvec2(), q.as_dvec2(), r.as_dvec2());
+        side(a.0, a.1, b.0) * side(a.0, a.1, b.1) < 0.0
+            && side(b.0, b.1, a.0) * side(b.0, b.1, a.1) < 0.0
+    }
+
+    #[test]
+    fn near_concurrent_crossings() {
+        // Lines through one point rounded to f32 cross within a few ulps of each other, where the
+        // order of the sweep is all rounding
+        let mut rng = StdRng::seed_from_u64(13);
+        let center = Vec2::new(0.3, 0.7);
+        for _ in 0..500 {
+            let segments: Vec<(Vec2, Vec2)> = (0..rng.gen_range(2..12))
+                .map(|_| {
+                    let d = Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+                    (
+                        center - d * rng.gen_range(0.1..2.0),
+                        center + d * rng.gen_range(0.1..2.0),
+                    )
+                })
+                .collect();
+            let found = crossings(&segments);
+            for i in 0..segments.len() {
+                for j in i + 1..segments.len() {
+                    let met = found
+                        .iter()
+                        .any(|c| c.segments.contains(&i) && c.segments.contains(&j));
+                    assert_eq!(met, cross(segments[i], segments[j]), "{segments:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn concurrent_crossings() {
+        let star: Vec<(Vec2, Vec2)> = [(1.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, -2.0), (2.0, 1.0)]
+            .into_iter()
+            .map(|(x, y)| (Vec2::new(-x, -y), Vec2::new(x, y)))
+            .collect();
+        assert_eq!(
+            crossings(&star),
+            [Crossing {
+                point: Vec2::ZERO,
+                segments: vec![0, 1, 2, 3, 4],
+            }]
+        );
+        assert!(crossings(&star[..1]).is_empty());
+    }
+
+    /// Points where two segments with integer end points meet, computed exactly: where they
+    /// cross or touch, or both ends of a collinear overlap.
+    fn meeting(p: (IVec2, IVec2), q: (IVec2, IVec2)) -> Vec<DVec2> {
+        let cross = |o: IVec2, a: IVec2, b: IVec2| (a - o).perp_dot(b - o).signum();
+        let lexicographic = |(a, b): (IVec2, IVec2)| {
+            if (a.x, a.y) <= (b.x, b.y) {
+                (a, b)
+            } else {
+                (b, a)
+            }
+        };
+        let (d1, d2) = (cross(p.0, p.1, q.0), cross(p.0, p.1, q.1));
+        let (d3, d4) = (cross(q.0, q.1, p.0), cross(q.0, q.1, p.1));
+        if d1 == 0 && d2 == 0 {
+            let ((a, b), (c, d)) = (lexicographic(p), lexicographic(q));
+            let low = if (a.x, a.y) >= (c.x, c.y) { a } else { c };
+            let high = if (b.x, b.y) <= (d.x, d.y) { b } else { d };
+            return match (low.x, low.y).cmp(&(high.x, high.y)) {
+                Ordering::Less => vec![low.as_dvec2(), high.as_dvec2()],
+                Ordering::Equal => vec![low.as_dvec2()],
+                Ordering::Greater => vec![],
+            };
+        }
+        if d1 * d2 > 0 || d3 * d4 > 0 {
+            return vec![];
+        }
+        let point = match (d1, d2, d3, d4) {
+            (0, _, _, _) => q.0,
+            (_, 0, _, _) => q.1,
+            (_, _, 0, _) => p.0,
+            (_, _, _, 0) => p.1,
+            _ => {
+                let (a, b) = (p.0.as_dvec2(), p.1.as_dvec2());
+                let (c, d) = (q.0.as_dvec2(), q.1.as_dvec2());
+                let t = (c - a).perp_dot(d - c) / (b - a).perp_dot(d - c);
+                return vec![a + (b - a) * t];
+            }
+        };
+        vec![point.as_dvec2()]
+    }
+
+    /// Checks `self_crossings` against every pair of segments, leaving out consecutive ones that
+    /// only meet at their joint.
+    fn check_polyline(polyline: &[IVec2]) {
+        let kept: Vec<usize> = (0..polyline.len().saturating_sub(1))
+            .filter(|&i| polyline[i] != polyline[i + 1])
+            .collect();
+        let closed = kept.len() > 2 && polyline.first() == polyline.last();
+        let segment = |i: usize| (polyline[i], polyline[i + 1]);
+        let mut expected: Vec<DVec2> = vec![];
+        for (k, &i) in kept.iter().enumerate() {
+            for (l, &j) in kept.iter().enumerate().skip(k + 1) {
+                let joint = if l == k + 1 {
+                    Some(polyline[j].as_dvec2())
+                } else if closed && k == 0 && l == kept.len() - 1 {
+                    Some(polyline[i].as_dvec2())
+                } else {
+                    None
+                };
+                expected.extend(
+                    meeting(segment(i), segment(j))
+                        .into_iter()
+                        .filter(|&p| Some(p) != joint),
+                );
+            }
+        }
+        let near = |p: DVec2, q: DVec2| p.distance(q) <= 1e-5;
+        let vertices: Vec<Vec2> = polyline.iter().map(|p| p.as_vec2()).collect();
+        let found = self_crossings(&vertices);
+        assert_eq!(is_self_intersecting(&vertices), !expected.is_empty());
+        for p in &expected {
+            assert!(
+                found.iter().any(|c| near(c.point.as_dvec2(), *p)),
+                "{p} missing in {found:?} for {polyline:?}"
+            );
+        }
+        for crossing in &found {
+            let p = crossing.point.as_dvec2();
+            assert!(
+                expected.iter().any(|&q| near(p, q)),
+                "{crossing:?} for {polyline:?}"
+            );
+            let through: Vec<usize> = kept
+                .iter()
+                .copied()
+                .filter(|&i| {
+                    let (a, b) = (polyline[i].as_dvec2(), polyline[i + 1].as_dvec2());
+                    let t = ((p - a).dot(b - a) / (b - a).length_squared()).clamp(0.0, 1.0);
+                    near(a + (b - a) * t, p)
+                })
+                .collect();
+            assert_eq!(crossing.segments, through, "{polyline:?}");
+        }
+    }
+
+    #[test]
+    fn polyline_joints() {
+        let polyline = |points: &[(i32, i32)]| -> Vec<IVec2> {
+            points.iter().map(|&(x, y)| IVec2::new(x, y)).collect()
+        };
+        let cases = [
+            // Closed square and triangle, which only meet themselves at the joints
+            (polyline(&[(0, 0), (2, 0), (2, 2), (0, 2), (0, 0)]), false),
+            (polyline(&[(0, 0), (2, 0), (0, 2), (0, 0)]), false),
+            // Figure eight
+            (polyline(&[(0, 0), (2, 2), (2, 0), (0, 2), (0, 0)]), true),
+            // Folding back onto the previous segment, part way and all the way
+            (polyline(&[(0, 0), (4, 0), (2, 0)]), true),
+            (polyline(&[(0, 0), (4, 0), (0, 0)]), true),
+            (polyline(&[(0, 0), (0, 4), (0, 1), (3, 1)]), true),
+            // Touching an earlier segment in its middle, and at its end point
+            (polyline(&[(0, 0), (4, 0), (4, 2), (2, 2), (2, 0)]), true),
+            (
+                polyline(&[(0, 0), (4, 0), (4, 2), (0, 2), (0, 0), (-1, -1)]),
+                true,
+            ),
+            // Running along an earlier segment
+            (
+                polyline(&[(0, 0), (4, 0), (4, 1), (3, 1), (3, 0), (1, 0)]),
+                true,
+            ),
+            // Vertical segments, and a repeated vertex
+            (polyline(&[(0, 0), (0, 3), (1, 3), (1, 3), (1, -1)]), false),
+            (polyline(&[(0, 0), (0, 3), (1, 2), (-1, 1)]), true),
+        ];
+        for (polyline, crosses) in cases {
+            check_polyline(&polyline);
+            let vertices: Vec<Vec2> = polyline.iter().map(|p| p.as_vec2()).collect();
+            assert_eq!(is_self_intersecting(&vertices), crosses, "{polyline:?}");
+        }
+    }
+
+    #[test]
+    fn random_polylines() {
+        // On a small grid, to get vertical segments, overlaps, touches and concurrent crossings
+        let mut rng = StdRng::seed_from_u64(113);
+        for _ in 0..3000 {
+            let size = rng.gen_range(1..6);
+            let mut polyline: Vec<IVec2> = (0..rng.gen_range(2..10))
+                .map(|_| IVec2::new(rng.gen_range(0..=size), rng.gen_range(0..=size)))
+                .collect();
+            if rng.gen_bool(0.3) {
+                // Back along the last segment, part or all of the way
+                let (a, b) = (polyline[polyline.len() - 2], polyline[polyline.len() - 1]);
+                polyline.push(if rng.gen() { a } else { a + (b - a) / 2 });
+            }
+            if rng.gen_bool(0.3) {
+                polyline.push(polyline[0]);
+            }
+            check_polyline(&polyline);
+        }
+    }
+}
@@ -0,0 +1,768 @@
+//! Triangulation of simple polygons with holes. Triangles are counter clockwise triples of indices
+//! into `PolygonWithHoles::vertices`, so they can go straight into a bevy `Mesh` with `mesh`.
+//! Rings may touch themselves and each other at points that are vertices of both, as the results
+//! of `boolean` do, but not in the middle of an edge.
+use super::polygon::PolygonWithHoles;
+use super::predicates::orient2d;
+use bevy::math::DVec2;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::f64::consts::TAU;
+
+/// Cuts off ears, i.e. triangles of consecutive vertices holding no other vertex, one at a time
+/// in O(n^2). Holes are first joined to the outer boundary by a bridge to a visible vertex, which
+/// leaves a single ring going out and back along each bridge. Holes touching the ring at a vertex
+/// are joined to it there instead.
+///
+/// Rings that cross themselves or each other can run out of ears, and then a vertex that is not
+/// an ear is cut off anyway, so the triangles may overlap or stick out of the polygon.
+pub fn ear_clipping(polygon: &PolygonWithHoles) -> Vec<[usize; 3]> {
+    let (points, rings) = rings(polygon);
+    let Some((outer, holes)) = rings.split_first() else {
+        return vec![];
+    };
+    let mut ring = outer.clone();
+    let mut holes = holes.to_vec();
+    // The rightmost hole first, so that the bridges of the others can end on it
+    let rightmost = |hole: &[usize]| {
+        (0..hole.len())
+            .max_by(|&i, &j| compare(points[hole[i]], points[hole[j]]))
+            .unwrap()
+    };
+    holes.sort_by(|a, b| compare(points[b[rightmost(b)]], points[a[rightmost(a)]]));
+    for hole in &holes {
+        let start = rightmost(hole);
+        bridge(&points, &mut ring, hole, start);
+    }
+    clip(&points, &ring)
+}
+
+/// Splits the polygon into pieces that are monotone in y with a sweep from top to bottom, which
+/// adds diagonals below split vertices and above merge vertices, then triangulates each piece
+/// along its two chains. The edges cut by the sweep are kept in a balanced tree, so this is
+/// O(n log n).
+///
+/// Rings that cross themselves or each other leave the edges cut by the sweep without a
+/// consistent order from left to right, so the triangles may overlap or stick out of the polygon
+/// as well.
+pub fn monotone(polygon: &PolygonWithHoles) -> Vec<[usize; 3]> {
+    let (points, rings) = rings(polygon);
+    let (mut next, mut prev) = (vec![0; points.len()], vec![0; points.len()]);
+    for ring in &rings {
+        for (k, &v) in ring.iter().enumerate() {
+            next[v] = ring[(k + 1) % ring.len()];
+            prev[next[v]] = v;
+        }
+    }
+    let above = |u: usize, v: usize| higher(&points, u, v) == Ordering::Greater;
+    let mut sweep = rings.concat();
+    sweep.sort_by(|&u, &v| higher(&points, v, u));
+    // Edges from a vertex to the next one that go down on the left of the polygon, from left to
+    // right where the sweep cuts them, with their helpers
+    let cut = |key: Key| Cut {
+        points: &points,
+        next: &next,
+        key,
+    };
+    let mut status: BTreeMap<Cut, usize> = BTreeMap::new();
+    let mut merge = vec![false; points.len()];
+    let mut diagonals = vec![];
+    for &v in &sweep {
+        let (u, w) = (prev[v], next[v]);
+        let convex = orient2d(points[u], points[v], points[w]) > 0.0;
+        let mut end_edge = || {
+            if let Some(helper) = status.remove(&cut(Key::Edge(u))) {
+                if merge[helper] {
+                    diagonals.push((v, helper));
+                }
+            }
+        };
+        // Helper of the edge left of the vertex
+        let left_of = ..cut(Key::Vertex(v));
+        match (above(v, u), above(v, w)) {
+            // Start or split vertex
+            (true, true) => {
+                if !convex {
+                    if let Some((_, helper)) = status.range_mut(left_of).next_back() {
+                        diagonals.push((v, *helper));
+                        *helper = v;
+                    }
+                }
+                status.insert(cut(Key::Edge(v)), v);
+            }
+            // End or merge vertex
+            (false, false) => {
+                end_edge();
+                if !convex {
+                    merge[v] = true;
+                    if let Some((_, helper)) = status.range_mut(left_of).next_back() {
+                        if merge[*helper] {
+                            diagonals.push((v, *helper));
+                        }
+                        *helper = v;
+                    }
+                }
+            }
+            // On the left chain, with the inside to the right
+            (false, true) => {
+                end_edge();
+                status.insert(cut(Key::Edge(v)), v);
+            }
+            (true, false) => {
+                if let Some((_, helper)) = status.range_mut(left_of).next_back() {
+                    if merge[*helper] {
+                        diagonals.push((v, *helper));
+                    }
+                    *helper = v;
+                }
+            }
+        }
+    }
+    let mut outgoing: Vec<Vec<usize>> = vec![vec![]; points.len()];
+    for &v in &sweep {
+        outgoing[v].push(next[v]);
+    }
+    for &(a, b) in &diagonals {
+        outgoing[a].push(b);
+        outgoing[b].push(a);
+    }
+    let mut triangles = vec![];
+    let mut visited = HashSet::new();
+    for &a in &sweep {
+        for &b in &outgoing[a] {
+            if visited.contains(&(a, b)) {
+                continue;
+            }
+            // Walk the piece on the left, taking at every vertex the edge with the smallest
+            // clockwise angle from the way back, which is the sharpest left turn
+            let (mut u, mut v) = (a, b);
+            let mut piece = vec![];
+            while visited.insert((u, v)) {
+                piece.push(u);
+                let back = points[u] - points[v];
+                let turn = |w: &usize| {
+                    let ahead = points[*w] - points[v];
+                    let angle = (back.y.atan2(back.x) - ahead.y.atan2(ahead.x)).rem_euclid(TAU);
+                    if angle == 0.0 {
+                        TAU
+                    } else {
+                        angle
+                    }
+                };
+                let w = outgoing[v]
+                    .iter()
+                    .min_by(|w, x| turn(w).total_cmp(&turn(x)))
+                    .copied()
+                    .unwrap();
+                (u, v) = (v, w);
+            }
+            triangulate_monotone(&points, &piece, &mut triangles);
+        }
+    }
+    triangles
+}
+
+#[derive(Clone, Copy)]
+enum Key {
+    /// The edge from the vertex to the next one
+    Edge(usize),
+    /// Between the edges left of the vertex and the others
+    Vertex(usize),
+}
+
+/// Key of the sweep status. Edges in the status do not cross, so two of them compare by the side
+/// of one that the upper end of the other is on, taking the one the sweep reached later, and the
+/// order does not depend on where the sweep is now.
+#[derive(Clone, Copy)]
+struct Cut<'a> {
+    points: &'a [DVec2],
+    next: &'a [usize],
+    key: Key,
+}
+
+impl PartialEq for Cut<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Cut<'_> {}
+
+impl PartialOrd for Cut<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Cut<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let points = self.points;
+        // Side of the edge that the point is on, from left to right
+        let side = |e: usize, p: usize| {
+            0.0_f64.total_cmp(&orient2d(points[e], points[self.next[e]], points[p]))
+        };
+        match (self.key, other.key) {
+            (Key::Edge(e), Key::Edge(f)) if e == f => Ordering::Equal,
+            (Key::Edge(e), Key::Edge(f)) => {
+                // Edges meeting at the upper end of the later one go by their lower ends
+                if higher(points, f, e) == Ordering::Less {
+                    side(e, f).then(side(e, self.next[f])).then(e.cmp(&f))
+                } else {
+                    side(f, e)
+                        .then(side(f, self.next[e]))
+                        .reverse()
+                        .then(e.cmp(&f))
+                }
+            }
+            (Key::Edge(e), Key::Vertex(v)) => side(e, v).then(Ordering::Greater),
+            (Key::Vertex(v), Key::Edge(e)) => side(e, v).reverse().then(Ordering::Less),
+            (Key::Vertex(_), Key::Vertex(_)) => Ordering::Equal,
+        }
+    }
+}
+
+/// Mesh of the triangles in the xy plane facing +z.
+pub fn mesh(polygon: &PolygonWithHoles, triangles: &[[usize; 3]]) -> Mesh {
+    let positions: Vec<[f32; 3]> = polygon.vertices().map(|p| [p.x, p.y, 0.0]).collect();
+    let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+    let indices = triangles.iter().flatten().map(|&i| i as u32).collect();
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// All vertices, and the rings as indices into them with repeated vertices dropped, the outer
+/// boundary counter clockwise and holes clockwise. Rings without area are left out.
+fn rings(polygon: &PolygonWithHoles) -> (Vec<DVec2>, Vec<Vec<usize>>) {
+    // Adding zero turns -0 into 0, which would otherwise be ordered below it
+    let points: Vec<DVec2> = polygon.vertices().map(|p| p.as_dvec2() + 0.0).collect();
+    let mut rings = vec![];
+    let mut offset = 0;
+    for (k, ring) in std::iter::once(&polygon.outer)
+        .chain(&polygon.holes)
+        .enumerate()
+    {
+        let mut indices: Vec<usize> = (offset..offset + ring.len()).collect();
+        offset += ring.len();
+        indices.dedup_by(|i, j| points[*i] == points[*j]);
+        while indices.len() > 1 && points[indices[0]] == points[*indices.last().unwrap()] {
+            indices.pop();
+        }
+        let area: f64 = (0..indices.len())
+            .map(|i| {
+                let (a, b) = (indices[i], indices[(i + 1) % indices.len()]);
+                (points[a] - points[indices[0]]).perp_dot(points[b] - points[indices[0]])
+            })
+            .sum();
+        if area == 0.0 {
+            if k == 0 {
+                return (points, vec![]);
+            }
+            continue;
+        }
+        if (area > 0.0) != (k == 0) {
+            indices.reverse();
+        }
+        rings.push(indices);
+    }
+    let rings = join_touching(&points, rings);
+    (points, rings)
+}
+
+/// Joins rings that touch at a vertex into one, which goes round the first up to the vertex,
+/// round the second and on from the vertex again. It touches itself there, like the rings that
+/// `boolean` gives for holes touching the boundary or each other, and the outer boundary stays
+/// first.
+fn join_touching(points: &[DVec2], rings: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+    let (mut next, mut ring_of) = (vec![0; points.len()], vec![0; points.len()]);
+    for (r, ring) in rings.iter().enumerate() {
+        for (k, &v) in ring.iter().enumerate() {
+            next[v] = ring[(k + 1) % ring.len()];
+            ring_of[v] = r;
+        }
+    }
+    // Rings already joined point to the one they were joined into
+    let mut joined: Vec<usize> = (0..rings.len()).collect();
+    let root = |joined: &[usize], mut r: usize| {
+        while joined[r] != r {
+            r = joined[r];
+        }
+        r
+    };
+    let mut order = rings.concat();
+    order.sort_by(|&u, &v| compare(points[u], points[v]));
+    let mut first = 0;
+    for k in 1..order.len() {
+        let (u, v) = (order[first], order[k]);
+        if points[u] != points[v] {
+            first = k;
+            continue;
+        }
+        let (a, b) = (root(&joined, ring_of[u]), root(&joined, ring_of[v]));
+        if a != b {
+            // Each ring goes on where the other would have
+            next.swap(u, v);
+            joined[b] = a;
+        }
+    }
+    let mut visited = vec![false; points.len()];
+    let mut result = vec![];
+    for ring in &rings {
+        let (mut v, mut joined) = (ring[0], vec![]);
+        while !visited[v] {
+            visited[v] = true;
+            joined.push(v);
+            v = next[v];
+        }
+        if !joined.is_empty() {
+            result.push(joined);
+        }
+    }
+    result
+}
+
+/// Splices the hole into the ring at a vertex visible from its vertex at `start`, which is
+/// rightmost in the hole. The ray to the right from there hits an edge of the ring, and either
+/// the end of that edge further right is visible or a reflex vertex in the way is.
+fn bridge(points: &[DVec2], ring: &mut Vec<usize>, hole: &[usize], start: usize) {
+    let m = points[hole[start]];
+    let mut hit: Option<(f64, usize)> = None;
+    for k in 0..ring.len() {
+        let (a, b) = (points[ring[k]], points[ring[(k + 1) % ring.len()]]);
+        if (a.y - m.y) * (b.y - m.y) > 0.0 {
+            continue;
+        }
+        // A vertex on the ray is hit itself, otherwise take the end of the edge further right
+        let (x, end) = match (a.y == m.y, b.y == m.y) {
+            (true, true) if b.x < a.x => (b.x, k + 1),
+            (true, _) => (a.x, k),
+            (false, true) => (b.x, k + 1),
+            (false, false) => {
+                let x = a.x + (m.y - a.y) * (b.x - a.x) / (b.y - a.y);
+                (x, if a.x > b.x { k } else { k + 1 })
+            }
+        };
+        if x >= m.x && hit.is_none_or(|(best, _)| x < best) {
+            hit = Some((x, end % ring.len()));
+        }
+    }
+    let Some((x, end)) = hit else {
+        return;
+    };
+    let (i, p) = (DVec2::new(x, m.y), points[ring[end]]);
+    let mut target = ring[end];
+    if p != i {
+        let side = orient2d(m, i, p).total_cmp(&0.0);
+        let inside = |r: DVec2| {
+            [(m, i), (i, p), (p, m)]
+                .iter()
+                .all(|&(a, b)| orient2d(a, b, r).total_cmp(&0.0) != side.reverse())
+        };
+        let angle = |r: DVec2| (r.y - m.y).abs().atan2(r.x - m.x);
+        target = (0..ring.len())
+            .filter(|&k| {
+                let (u, v, w) = (
+                    ring[(k + ring.len() - 1) % ring.len()],
+                    ring[k],
+                    ring[(k + 1) % ring.len()],
+                );
+                points[v] != p
+                    && orient2d(points[u], points[v], points[w]) < 0.0
+                    && inside(points[v])
+            })
+            .map(|k| ring[k])
+            .min_by(|&u, &v| {
+                let (p, q) = (points[u], points[v]);
+                angle(p)
+                    .total_cmp(&angle(q))
+                    .then(p.distance_squared(m).total_cmp(&q.distance_squared(m)))
+            })
+            .unwrap_or(target);
+    }
+    // A vertex can be in the ring more than once after earlier bridges, so take the time it is
+    // passed with the hole in front
+    let position = (0..ring.len())
+        .filter(|&k| ring[k] == target)
+        .find(|&k| {
+            let (u, v, w) = (
+                ring[(k + ring.len() - 1) % ring.len()],
+                ring[k],
+                ring[(k + 1) % ring.len()],
+            );
+            let (u, v, w) = (points[u], points[v], points[w]);
+            let (after, before) = (orient2d(u, v, m) > 0.0, orient2d(v, w, m) > 0.0);
+            if orient2d(u, v, w) >= 0.0 {
+                after && before
+            } else {
+                after || before
+            }
+        })
+        .or_else(|| ring.iter().position(|&v| v == target))
+        .unwrap();
+    let spliced: Vec<usize> = (0..=hole.len())
+        .map(|k| hole[(start + k) % hole.len()])
+        .chain(std::iter::once(ring[position]))
+        .collect();
+    ring.splice(position + 1..position + 1, spliced);
+}
+
+fn clip(points: &[DVec2], ring: &[usize]) -> Vec<[usize; 3]> {
+    let n = ring.len();
+    let mut triangles = vec![];
+    if n < 3 {
+        return triangles;
+    }
+    let at = |k: usize| points[ring[k]];
+    let mut next: Vec<usize> = (1..=n).map(|k| k % n).collect();
+    let mut prev: Vec<usize> = (0..n).map(|k| (k + n - 1) % n).collect();
+    let (mut remaining, mut current, mut stalled) = (n, 0, 0);
+    while remaining > 2 {
+        let (a, b, c) = (prev[current], current, next[current]);
+        let turn = orient2d(at(a), at(b), at(c));
+        let is_ear = || {
+            let corners = [at(a), at(b), at(c)];
+            let mut k = next[c];
+            while k != a {
+                let p = at(k);
+                // Only reflex vertices can be inside, and those on the corners come from bridges
+                if !corners.contains(&p)
+                    && orient2d(at(prev[k]), p, at(next[k])) <= 0.0
+                    && orient2d(corners[0], corners[1], p) >= 0.0
+                    && orient2d(corners[1], corners[2], p) >= 0.0
+                    && orient2d(corners[2], corners[0], p) >= 0.0
+                {
+                    return false;
+                }
+                k = next[k];
+            }
+            true
+        };
+        // Flat vertices go without a triangle. If nothing was cut for a whole round the polygon
+        // is not simple, and the vertex is cut anyway to get through.
+        if turn == 0.0 || (turn > 0.0 && is_ear()) || stalled > remaining {
+            if turn > 0.0 {
+                triangles.push([ring[a], ring[b], ring[c]]);
+            }
+            next[a] = c;
+            prev[c] = a;
+            remaining -= 1;
+            stalled = 0;
+        } else {
+            stalled += 1;
+        }
+        current = c;
+    }
+    triangles
+}
+
+/// Triangulates a counter clockwise piece that is monotone in y, going down both chains at once
+/// and cutting off every triangle that is inside as soon as its lowest vertex is reached.
+fn triangulate_monotone(points: &[DVec2], piece: &[usize], triangles: &mut Vec<[usize; 3]>) {
+    let n = piece.len();
+    if n < 3 {
+        return;
+    }
+    let mut push = |a: usize, b: usize, c: usize| match orient2d(points[a], points[b], points[c])
+        .total_cmp(&0.0)
+    {
+        Ordering::Greater => triangles.push([a, b, c]),
+        Ordering::Less => triangles.push([a, c, b]),
+        Ordering::Equal => {}
+    };
+    let mut sorted = piece.to_vec();
+    sorted.sort_by(|&u, &v| higher(points, v, u));
+    // Going on from the top leads down the left chain
+    let top = piece.iter().position(|&v| v == sorted[0]).unwrap();
+    let mut on_left = HashSet::new();
+    for k in 0..n {
+        let v = piece[(top + k) % n];
+        if v == sorted[n - 1] {
+            break;
+        }
+        on_left.insert(v);
+    }
+    let mut stack = vec![sorted[0], sorted[1]];
+    for &u in &sorted[2..n - 1] {
+        let last = *stack.last().unwrap();
+        if on_left.contains(&u) != on_left.contains(&last) {
+            for pair in stack.windows(2) {
+                push(u, pair[0], pair[1]);
+            }
+            stack = vec![last, u];
+        } else {
+            let mut last = stack.pop().unwrap();
+            while let Some(&top) = stack.last() {
+                let side = orient2d(points[top], points[u], points[last]);
+                let inside = if on_left.contains(&u) {
+                    side < 0.0
+                } else {
+                    side > 0.0
+                };
+                if !inside {
+                    break;
+                }
+                push(u, last, top);
+                last = stack.pop().unwrap();
+            }
+            stack.push(last);
+            stack.push(u);
+        }
+    }
+    for pair in stack.windows(2) {
+        push(sorted[n - 1], pair[0], pair[1]);
+    }
+}
+
+/// Order of the sweep from top to bottom: by height, then from left to right.
+fn higher(points: &[DVec2], u: usize, v: usize) -> Ordering {
+    let (p, q) = (points[u], points[v]);
+    p.y.total_cmp(&q.y)
+        .then(q.x.total_cmp(&p.x))
+        .then(v.cmp(&u))
+}
+
+fn compare(p: DVec2, q: DVec2) -> Ordering {
+    p.x.total_cmp(&q.x).then(p.y.total_cmp(&q.y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::boolean::difference;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    type Triangulator = fn(&PolygonWithHoles) -> Vec<[usize; 3]>;
+
+    const ALGORITHMS: [Triangulator; 2] = [ear_clipping, monotone];
+
+    /// Counter clockwise triangles inside the polygon that cover its area.
+    fn check(polygon: &PolygonWithHoles) {
+        let vertices: Vec<DVec2> = polygon.vertices().map(|p| p.as_dvec2()).collect();
+        for algorithm in ALGORITHMS {
+            let mut area = 0.0;
+            for triangle in algorithm(polygon) {
+                let [a, b, c] = triangle.map(|i| vertices[i]);
+                assert!(orient2d(a, b, c) > 0.0, "{triangle:?} in {polygon:?}");
+                assert!(polygon.contains(((a + b + c) / 3.0).as_vec2()));
+                area += orient2d(a, b, c) / 2.0;
+            }
+            let expected = polygon.area() as f64;
+            assert!(
+                (area - expected).abs() <= 1e-4 * expected,
+                "{area} != {expected} for {polygon:?}"
+            );
+        }
+    }
+
+    /// Square with every side split into unit steps.
+    fn subdivided(min: Vec2, size: i32) -> Vec<Vec2> {
+        let corners = [IVec2::ZERO, IVec2::X, IVec2::ONE, IVec2::Y].map(|c| c * size);
+        (0..4)
+            .flat_map(|k| {
+                let (a, b) = (corners[k], corners[(k + 1) % 4]);
+                (0..size).map(move |i| min + (a + (b - a) / size * i).as_vec2())
+            })
+            .collect()
+    }
+
+    /// Star shaped ring with one vertex in each of `count` equal sectors around the center.
+    fn star(
+        rng: &mut StdRng,
+        center: Vec2,
+        radii: std::ops::Range<f32>,
+        count: usize,
+    ) -> Vec<Vec2> {
+        (0..count)
+            .map(|k| {
+                let angle = (k as f32 + rng.gen_range(0.0..0.9)) * TAU as f32 / count as f32;
+                center + Vec2::from_angle(angle) * rng.gen_range(radii.clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn collinear_runs_and_holes() {
+        check(&PolygonWithHoles::from(subdivided(Vec2::ZERO, 4)));
+        // Teeth above and below, with the tips and gaps of each side at the same heights
+        let top = (0..=8)
+            .rev()
+            .map(|x| Vec2::new(x as f32, if x % 2 == 0 { 3.0 } else { 1.0 }));
+        let bottom = (0..=8).map(|x| Vec2::new(x as f32, if x % 2 == 0 { 0.0 } else { -2.0 }));
+        check(&PolygonWithHoles::from(
+            bottom.chain(top.clone()).collect::<Vec<_>>(),
+        ));
+        let flat = (0..=8).map(|x| Vec2::new(x as f32, 0.0));
+        check(&PolygonWithHoles::from(flat.chain(top).collect::<Vec<_>>()));
+
+        let holes = [
+            subdivided(Vec2::splat(2.0), 2),
+            vec![
+                Vec2::new(6.0, 2.0),
+                Vec2::new(8.0, 2.0),
+                Vec2::new(7.0, 4.0),
+            ],
+            vec![
+                Vec2::new(7.0, 5.0),
+                Vec2::new(8.0, 6.0),
+                Vec2::new(7.0, 7.0),
+                Vec2::new(6.0, 6.0),
+            ],
+            vec![
+                Vec2::new(2.0, 6.0),
+                Vec2::new(3.0, 6.0),
+                Vec2::new(4.0, 6.0),
+                Vec2::new(3.0, 8.0),
+            ],
+        ];
+        for count in 0..=holes.len() {
+            check(&PolygonWithHoles::new(
+                subdivided(Vec2::ZERO, 10),
+                holes[..count].to_vec(),
+            ));
+        }
+    }
+
+    #[test]
+    fn random_stars_with_holes() {
+        let mut rng = StdRng::seed_from_u64(14);
+        for _ in 0..300 {
+            // Edges of the outer ring stay more than 3.6 from the center, as the sectors are at
+            // most 85 degrees apart, and holes reach no further than 3.5
+            let count = rng.gen_range(8..30);
+            let outer = star(&mut rng, Vec2::ZERO, 5.0..10.0, count);
+            let holes = [-2.0, 2.0]
+                .into_iter()
+                .take(rng.gen_range(0..=2))
+                .map(|x| {
+                    let count = rng.gen_range(3..10);
+                    star(&mut rng, Vec2::new(x, 0.0), 0.5..1.5, count)
+                })
+                .collect();
+            check(&PolygonWithHoles::new(outer, holes));
+        }
+    }
+
+    #[test]
+    fn comb() {
+        // Teeth hanging from a bar with slanted tips, where the sweep cuts every tooth at once
+        let mut comb = vec![];
+        for k in 0..500 {
+            let x = 2.0 * k as f32;
+            comb.extend([
+                Vec2::new(x, 10.0),
+                Vec2::new(x, (k % 5) as f32 / 5.0),
+                Vec2::new(x + 1.0, 1.0 + (k % 7) as f32 / 7.0),
+                Vec2::new(x + 1.0, 10.0),
+            ]);
+        }
+        comb.extend([Vec2::new(999.0, 11.0), Vec2::new(0.0, 11.0)]);
+        check(&PolygonWithHoles::from(comb));
+    }
+
+    #[test]
+    fn touching_holes() {
+        let square = |min: Vec2, size: f32| {
+            PolygonWithHoles::from(vec![
+                min,
+                min + Vec2::X * size,
+                min + Vec2::splat(size),
+                min + Vec2::Y * size,
+            ])
+        };
+        let diamond = |center: Vec2| {
+            PolygonWithHoles::from(vec![
+                center - Vec2::Y,
+                center + Vec2::X,
+                center + Vec2::Y,
+                center - Vec2::X,
+            ])
+        };
+        let outer = [square(Vec2::ZERO, 4.0)];
+        // Touching the boundary, which `difference` gives as a boundary passing the point twice
+        let touching = difference(&outer, &[diamond(Vec2::new(2.0, 1.0))]);
+        assert_eq!(touching.len(), 1);
+        assert!(touching[0].holes.is_empty());
+        check(&touching[0]);
+        // Two holes touching each other, which come out as one hole
+        let holes = [square(Vec2::ONE, 1.0), square(Vec2::splat(2.0), 1.0)];
+        let touching = difference(&outer, &holes);
+        assert_eq!(touching[0].holes.len(), 1);
+        check(&touching[0]);
+        // Separate rings touching at vertices of both, the boundary, a diamond and a square
+        let mut boundary = outer[0].outer.clone();
+        boundary.insert(1, Vec2::new(2.0, 0.0));
+        let holes = vec![diamond(Vec2::new(2.0, 1.0)).outer, holes[1].outer.clone()];
+        check(&PolygonWithHoles::new(boundary, holes));
+
+        // Squares and diamonds on a grid, touching the boundary, each other and leaving pieces
+        // that only meet at points
+        let mut rng = StdRng::seed_from_u64(15);
+        for _ in 0..200 {
+            let holes: Vec<PolygonWithHoles> = (0..rng.gen_range(1..8))
+                .map(|_| {
+                    let at = IVec2::new(rng.gen_range(1..7), rng.gen_range(1..7)).as_vec2();
+                    if rng.gen() {
+                        square(at - Vec2::ONE, rng.gen_range(1..3) as f32)
+                    } else {
+                        diamond(at)
+                    }
+                })
+                .collect();
+            for polygon in difference(&[square(Vec2::ZERO, 8.0)], &holes) {
+                check(&polygon);
+            }
+        }
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        for algorithm in ALGORITHMS {
+            assert!(algorithm(&PolygonWithHoles::default()).is_empty());
+            let line = PolygonWithHoles::from(vec![Vec2::ZERO, Vec2::X, Vec2::X * 2.0]);
+            assert!(algorithm(&line).is_empty());
+        }
+        // Repeated vertices and a closed ring
+        let square = vec![
+            Vec2::ZERO,
+            Vec2::ZERO,
+            Vec2::X,
+            Vec2::ONE,
+            Vec2::Y,
+            Vec2::ZERO,
+        ];
+        check(&PolygonWithHoles::new(square, vec![]));
+    }
+
+    #[test]
+    fn self_crossing_rings() {
+        let bowtie = vec![Vec2::ZERO, Vec2::ONE * 2.0, Vec2::X * 2.0, Vec2::Y * 2.0];
+        let pentagram = (0..5)
+            .map(|k| Vec2::from_angle(k as f32 * 0.8 * std::f32::consts::PI) * 4.0)
+            .collect();
+        let mut rng = StdRng::seed_from_u64(14);
+        let random = (0..200).map(|_| {
+            let n = rng.gen_range(4..12);
+            (0..n)
+                .map(|_| Vec2::new(rng.gen_range(-8..8) as f32, rng.gen_range(-8..8) as f32))
+                .collect()
+        });
+        for ring in [bowtie, pentagram].into_iter().chain(random) {
+            let polygon = PolygonWithHoles::from(ring);
+            let vertices: Vec<DVec2> = polygon.vertices().map(|p| p.as_dvec2()).collect();
+            for algorithm in ALGORITHMS {
+                let triangles = algorithm(&polygon);
+                for [a, b, c] in triangles {
+                    assert!(orient2d(vertices[a], vertices[b], vertices[c]) > 0.0);
+                }
+            }
+            assert!(ear_clipping(&polygon).len() <= vertices.len().saturating_sub(2));
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Voronoi diagram as the dual of the Delaunay triangulation, clipped to a rectangle. The cell of a
+//! site is cut out of the rectangle by the bisectors with its Delaunay neighbors, which are the
+//! only sites that can bound it.
+use super::circle::Disk;
+use super::delaunay::Delaunay;
+use crate::convex::hull::HullError;
+use bevy::math::DVec2;
+use bevy::prelude::*;
+
+pub struct Voronoi {
+    sites: Vec<Vec2>,
+    bounds: Rect,
+    /// Delaunay neighbors of each site, or the next sites along the line when they are collinear
+    neighbors: Vec<Vec<usize>>,
+    cells: Vec<Vec<Vec2>>,
+    /// Index of the first site equal to each site
+    canonical: Vec<usize>,
+}
+
+impl Voronoi {
+    /// Sites may lie outside the bounds, their cells are clipped all the same. Duplicate sites are
+    /// merged into their first occurrence and get an empty cell.
+    pub fn new(sites: &[Vec2], bounds: Rect) -> Result<Self, HullError> {
+        let delaunay = Delaunay::new(sites)?;
+        let mut neighbors = delaunay.adjacency();
+        let canonical: Vec<usize> = (0..sites.len()).map(|i| delaunay.canonical(i)).collect();
+        if delaunay.triangles().is_empty() {
+            // Collinear sites are only bounded by the next ones along the line
+            let mut line: Vec<usize> = (0..sites.len()).filter(|&i| canonical[i] == i).collect();
+            line.sort_by(|&i, &j| {
+                let (a, b) = (sites[i], sites[j]);
+                a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y))
+            });
+            for pair in line.windows(2) {
+                neighbors[pair[0]].push(pair[1]);
+                neighbors[pair[1]].push(pair[0]);
+            }
+        }
+        let cells = (0..sites.len())
+            .map(|i| {
+                if canonical[i] != i {
+                    return vec![];
+                }
+                let corners = [
+                    bounds.min,
+                    Vec2::new(bounds.max.x, bounds.min.y),
+                    bounds.max,
+                    Vec2::new(bounds.min.x, bounds.max.y),
+                ];
+                let cell = neighbors[i]
+                    .iter()
+                    .fold(corners.map(|c| c.as_dvec2()).to_vec(), |cell, &j| {
+                        clip_by_bisector(&cell, sites[i].as_dvec2(), sites[j].as_dvec2())
+                    });
+                cell.into_iter().map(|p| p.as_vec2()).collect()
+            })
+            .collect();
+        Ok(Self {
+            sites: sites.to_vec(),
+            bounds,
+            neighbors,
+            cells,
+            canonical,
+        })
+    }
+
+    pub fn sites(&self) -> &[Vec2] {
+        &self.sites
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    /// Cell of the site in counter clockwise order, empty if it misses the bounds.
+    pub fn cell(&self, site: usize) -> &[Vec2] {
+        &self.cells[site]
+    }
+
+    pub fn cells(&self) -> &[Vec<Vec2>] {
+        &self.cells
+    }
+
+    /// Sites whose cells share an edge with the cell of the site, before clipping.
+    pub fn neighbors(&self, site: usize) -> &[usize] {
+        &self.neighbors[site]
+    }
+
+    /// Index of the site closest to the point, by walking towards it over the Delaunay edges,
+    /// which always leads to the closest site.
+    pub fn nearest(&self, point: Vec2) -> usize {
+        let p = point.as_dvec2();
+        let distance = |i: usize| self.sites[i].as_dvec2().distance_squared(p);
+        let mut nearest = 0;
+        while let Some(&closer) = self.neighbors[nearest]
+            .iter()
+            .find(|&&j| distance(j) < distance(nearest))
+        {
+            nearest = closer;
+        }
+        self.canonical[nearest]
+    }
+
+    /// Largest disk centered in the bounds that holds no site inside. Its center is a vertex of
+    /// some cell: a Voronoi vertex, a crossing of a Voronoi edge with the bounds, or a corner.
+    /// `None` if no cell meets the bounds.
+    pub fn largest_empty_circle(&self) -> Option<Disk> {
+        self.cells
+            .iter()
+            .enumerate()
+            .flat_map(|(i, cell)| cell.iter().map(move |&c| Disk::around(c, &[self.sites[i]])))
+            .max_by(|a, b| a.radius.total_cmp(&b.radius))
+    }
+}
+
+/// Sutherland-Hodgman step, keeps the part of the polygon closer to `site` than to `other`.
+fn clip_by_bisector(polygon: &[DVec2], site: DVec2, other: DVec2) -> Vec<DVec2> {
+    let middle = (site + other) / 2.0;
+    let side = |p: DVec2| (p - middle).dot(other - site);
+    let mut clipped = vec![];
+    for (i, &curr) in polygon.iter().enumerate() {
+        let next = polygon[(i + 1) % polygon.len()];
+        let (curr_side, next_side) = (side(curr), side(next));
+        if curr_side <= 0.0 {
+            clipped.push(curr);
+        }
+        if curr_side * next_side < 0.0 {
+            clipped.push(curr.lerp(next, curr_side / (curr_side - next_side)));
+        }
+    }
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const BOUNDS: Rect = Rect {
+        min: Vec2::new(-10.0, -10.0),
+        max: Vec2::new(10.0, 10.0),
+    };
+
+    fn check(sites: &[Vec2]) {
+        let voronoi = Voronoi::new(sites, BOUNDS).unwrap();
+        let distance = |p: Vec2, i: usize| p.as_dvec2().distance(sites[i].as_dvec2());
+        let mut area = 0.0;
+        for (i, cell) in voronoi.cells().iter().enumerate() {
+            for (j, &curr) in cell.iter().enumerate() {
+                let next = cell[(j + 1) % cell.len()];
+                area += curr.as_dvec2().perp_dot(next.as_dvec2()) / 2.0;
+                // Counter clockwise around the site, and closest to it
+                let side = (next - curr)
+                    .as_dvec2()
+                    .perp_dot((sites[i] - curr).as_dvec2());
+                if BOUNDS.contains(sites[i]) {
+                    assert!(side >= -1e-3, "{i} {side}");
+                }
+                for k in 0..sites.len() {
+                    assert!(distance(curr, i) <= distance(curr, k) + 1e-3, "{i} {k}");
+                }
+            }
+            if BOUNDS.contains(sites[i]) && voronoi.canonical[i] == i {
+                assert!(cell.len() >= 3, "{i}");
+            }
+            for &j in voronoi.neighbors(i) {
+                assert!(voronoi.neighbors(j).contains(&i), "{i} {j}");
+            }
+        }
+        let size = BOUNDS.size();
+        assert!((area - (size.x * size.y) as f64).abs() < 1e-3, "{area}");
+
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..100 {
+            let p = Vec2::new(rng.gen_range(-12.0..12.0), rng.gen_range(-12.0..12.0));
+            let nearest = voronoi.nearest(p);
+            assert_eq!(voronoi.canonical[nearest], nearest);
+            for k in 0..sites.len() {
+                assert!(distance(p, nearest) <= distance(p, k), "{p} {nearest} {k}");
+            }
+        }
+    }
+
+    #[test]
+    fn cells_tile_the_bounds() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for count in [1, 2, 3, 10, 100] {
+            for _ in 0..20 {
+                // Some sites outside of the bounds
+                let sites: Vec<Vec2> = (0..count)
+                    .map(|_| Vec2::new(rng.gen_range(-12.0..12.0), rng.gen_range(-12.0..12.0)))
+                    .collect();
+                check(&sites);
+            }
+        }
+    }
+
+    #[test]
+    fn degenerate_sites() {
+        // Grid with every site twice, full of cocircular sites
+        let grid: Vec<Vec2> = (0..25)
+            .map(|i| Vec2::new((i % 5) as f32 * 4.0 - 8.0, (i / 5) as f32 * 4.0 - 8.0))
+            .collect();
+        check(&[grid.clone(), grid].concat());
+        // Collinear, along an axis and diagonally
+        check(
+            &(-3..4)
+                .map(|i| Vec2::new(i as f32 * 2.0, 1.0))
+                .collect::<Vec<_>>(),
+        );
+        check(
+            &(-3..4)
+                .map(|i| Vec2::splat(i as f32 * 2.0))
+                .collect::<Vec<_>>(),
+        );
+        check(&[Vec2::ZERO, Vec2::ZERO]);
+        assert!(Voronoi::new(&[], BOUNDS).is_err());
+    }
+}
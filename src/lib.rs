@@ -1,3 +1,5 @@
 pub mod convex;
 pub mod game;
+pub mod geometry;
+pub mod sampling;
 pub mod search;
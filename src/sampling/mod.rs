@@ -0,0 +1,173 @@
+//! Point sets that cover a box more evenly than independent uniform samples, which leave clumps
+//! and holes, or that crowd the free space near obstacles. The building blocks work on `[f32; D]`
+//! coordinates in the unit cube or a box at the origin, [`Sampler`] places them in a box and
+//! wraps them for `Vec2` and `Vec3`.
+pub mod obstacle;
+pub mod poisson;
+pub mod sequence;
+pub mod stratified;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sampler {
+    /// Independent uniform samples.
+    Uniform,
+    /// Halton sequence from index 1, shifted by a random offset modulo the box.
+    Halton,
+    /// Sobol sequence from index 0, shifted by a random offset modulo the box.
+    Sobol,
+    /// Jittered grid of about cubic cells, as many as the box shape allows near the count.
+    Stratified,
+    /// Samples at least `radius` apart until the box is full or the count is reached.
+    PoissonDisk { radius: f32 },
+    /// See [`obstacle::gaussian`], the count is the number of attempts.
+    Gaussian { sigma: f32 },
+    /// See [`obstacle::bridge_test`], the count is the number of attempts.
+    BridgeTest { sigma: f32 },
+}
+
+impl Sampler {
+    /// About `count` samples of the box from `min` to `max` where `is_free` holds. Points outside
+    /// the box count as not free for the obstacle samplers.
+    pub fn sample<const D: usize, R: Rng>(
+        &self,
+        count: usize,
+        min: [f32; D],
+        max: [f32; D],
+        is_free: impl Fn([f32; D]) -> bool,
+        rng: &mut R,
+    ) -> Vec<[f32; D]> {
+        let size: [f32; D] = std::array::from_fn(|axis| max[axis] - min[axis]);
+        let place =
+            |unit: [f32; D]| std::array::from_fn(|axis| min[axis] + unit[axis] * size[axis]);
+        let inside = |point: [f32; D]| {
+            (0..D).all(|axis| min[axis] <= point[axis] && point[axis] <= max[axis])
+        };
+        let uniform = |rng: &mut R| place(std::array::from_fn(|_| rng.gen()));
+        let shifted = |rng: &mut R, sequence: &dyn Fn(usize) -> [f32; D]| {
+            let shift: [f32; D] = std::array::from_fn(|_| rng.gen());
+            (0..count)
+                .map(|i| {
+                    let unit = sequence(i);
+                    place(std::array::from_fn(|axis| {
+                        (unit[axis] + shift[axis]).fract()
+                    }))
+                })
+                .collect::<Vec<_>>()
+        };
+        let candidates = match *self {
+            Sampler::Uniform => (0..count).map(|_| uniform(rng)).collect(),
+            Sampler::Halton => shifted(rng, &|i| sequence::halton(i as u64 + 1)),
+            Sampler::Sobol => shifted(rng, &|i| sequence::sobol(i as u32)),
+            Sampler::Stratified => stratified::stratified(stratified::cells_for(size, count), rng)
+                .into_iter()
+                .map(place)
+                .collect(),
+            Sampler::PoissonDisk { radius } => poisson::poisson_disk(size, radius, count, rng)
+                .into_iter()
+                .map(|offset| std::array::from_fn(|axis| min[axis] + offset[axis]))
+                .collect(),
+            Sampler::Gaussian { sigma } => {
+                return obstacle::gaussian(count, sigma, uniform, |p| inside(p) && is_free(p), rng)
+            }
+            Sampler::BridgeTest { sigma } => {
+                return obstacle::bridge_test(
+                    count,
+                    sigma,
+                    uniform,
+                    |p| inside(p) && is_free(p),
+                    rng,
+                )
+            }
+        };
+        candidates.into_iter().filter(|&p| is_free(p)).collect()
+    }
+
+    pub fn sample_2d(
+        &self,
+        count: usize,
+        bounds: Rect,
+        is_free: impl Fn(Vec2) -> bool,
+        rng: &mut impl Rng,
+    ) -> Vec<Vec2> {
+        let (min, max) = (bounds.min.to_array(), bounds.max.to_array());
+        self.sample(count, min, max, |p| is_free(Vec2::from_array(p)), rng)
+            .into_iter()
+            .map(Vec2::from_array)
+            .collect()
+    }
+
+    pub fn sample_3d(
+        &self,
+        count: usize,
+        min: Vec3,
+        max: Vec3,
+        is_free: impl Fn(Vec3) -> bool,
+        rng: &mut impl Rng,
+    ) -> Vec<Vec3> {
+        let (min, max) = (min.to_array(), max.to_array());
+        self.sample(count, min, max, |p| is_free(Vec3::from_array(p)), rng)
+            .into_iter()
+            .map(Vec3::from_array)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const SAMPLERS: [Sampler; 7] = [
+        Sampler::Uniform,
+        Sampler::Halton,
+        Sampler::Sobol,
+        Sampler::Stratified,
+        Sampler::PoissonDisk { radius: 0.3 },
+        Sampler::Gaussian { sigma: 0.2 },
+        Sampler::BridgeTest { sigma: 0.5 },
+    ];
+
+    /// Free outside of a thick wall across the box with a narrow gap in it.
+    fn is_free(p: Vec2) -> bool {
+        (p.x - 1.0).abs() > 1.0 || (p.y - 2.0).abs() < 0.1
+    }
+
+    #[test]
+    fn seeded_samples_repeat() {
+        let bounds = Rect::new(-2.0, 0.0, 4.0, 3.0);
+        for sampler in SAMPLERS {
+            let sample = |seed: u64| {
+                sampler.sample_2d(1000, bounds, is_free, &mut StdRng::seed_from_u64(seed))
+            };
+            let samples = sample(19);
+            assert!(!samples.is_empty(), "{sampler:?}");
+            assert_eq!(samples, sample(19), "{sampler:?}");
+            assert_ne!(samples, sample(20), "{sampler:?}");
+            for p in samples {
+                assert!(bounds.contains(p) && is_free(p), "{sampler:?} {p}");
+            }
+        }
+    }
+
+    #[test]
+    fn counts_and_boxes() {
+        let mut rng = StdRng::seed_from_u64(19);
+        let (min, max) = (Vec3::new(-1.0, 0.0, 2.0), Vec3::new(1.0, 4.0, 3.0));
+        for sampler in [Sampler::Uniform, Sampler::Halton, Sampler::Sobol] {
+            let samples = sampler.sample_3d(100, min, max, |_| true, &mut rng);
+            assert_eq!(samples.len(), 100);
+            assert!(samples
+                .iter()
+                .all(|p| p.cmpge(min).all() && p.cmple(max).all()));
+        }
+        // 4 by 8 by 2 cells of half a unit
+        let samples = Sampler::Stratified.sample_3d(64, min, max, |_| true, &mut rng);
+        assert_eq!(samples.len(), 64);
+        // Only the free ones are kept
+        let samples = Sampler::Halton.sample_3d(100, min, max, |p| p.x < 0.0, &mut rng);
+        assert!((40..=60).contains(&samples.len()), "{}", samples.len());
+    }
+}
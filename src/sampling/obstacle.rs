@@ -0,0 +1,102 @@
+//! Samplers that crowd free space near obstacles, where narrow passages of a roadmap are. Each
+//! attempt draws a uniform point and a second one at a Gaussian offset from it, and only some
+//! attempts give a sample, so far fewer samples come out than attempts go in.
+use rand::Rng;
+
+/// Gaussian sampler of Boor, Overmars and van der Stappen: of two nearby points where exactly one
+/// is free, keeps the free one. Samples end up within about `sigma` of obstacle boundaries.
+pub fn gaussian<const D: usize, R: Rng>(
+    attempts: usize,
+    sigma: f32,
+    mut uniform: impl FnMut(&mut R) -> [f32; D],
+    is_free: impl Fn([f32; D]) -> bool,
+    rng: &mut R,
+) -> Vec<[f32; D]> {
+    (0..attempts)
+        .filter_map(|_| {
+            let first = uniform(rng);
+            let second = offset(first, sigma, rng);
+            match (is_free(first), is_free(second)) {
+                (true, false) => Some(first),
+                (false, true) => Some(second),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Bridge test of Hsu et al.: of two nearby points that are both in obstacles, keeps their
+/// midpoint if it is free. Samples end up in gaps narrower than about `sigma`.
+pub fn bridge_test<const D: usize, R: Rng>(
+    attempts: usize,
+    sigma: f32,
+    mut uniform: impl FnMut(&mut R) -> [f32; D],
+    is_free: impl Fn([f32; D]) -> bool,
+    rng: &mut R,
+) -> Vec<[f32; D]> {
+    (0..attempts)
+        .filter_map(|_| {
+            let first = uniform(rng);
+            if is_free(first) {
+                return None;
+            }
+            let second = offset(first, sigma, rng);
+            let middle = std::array::from_fn(|axis| (first[axis] + second[axis]) / 2.0);
+            (!is_free(second) && is_free(middle)).then_some(middle)
+        })
+        .collect()
+}
+
+/// Point at a normally distributed offset with standard deviation `sigma` along each axis.
+fn offset<const D: usize>(point: [f32; D], sigma: f32, rng: &mut impl Rng) -> [f32; D] {
+    std::array::from_fn(|axis| point[axis] + sigma * normal(rng))
+}
+
+/// Standard normal sample with the Box-Muller transform.
+fn normal(rng: &mut impl Rng) -> f32 {
+    let (u, v) = (1.0 - rng.gen::<f32>(), rng.gen::<f32>());
+    (-2.0 * u.ln()).sqrt() * (std::f32::consts::TAU * v).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn uniform(rng: &mut StdRng) -> [f32; 2] {
+        [rng.gen(), rng.gen()]
+    }
+
+    #[test]
+    fn gaussian_samples_are_free_and_near_obstacles() {
+        let mut rng = StdRng::seed_from_u64(19);
+        // Free outside of a disk in the middle of the unit square
+        let distance = |p: [f32; 2]| ((p[0] - 0.5).powi(2) + (p[1] - 0.5).powi(2)).sqrt() - 0.2;
+        let sigma = 0.02;
+        let samples = gaussian(5000, sigma, uniform, |p| distance(p) > 0.0, &mut rng);
+        assert!(!samples.is_empty());
+        for p in samples {
+            assert!(distance(p) > 0.0);
+            assert!(distance(p) < 6.0 * sigma, "{p:?}");
+        }
+    }
+
+    #[test]
+    fn bridge_samples_are_free_and_in_passages() {
+        let mut rng = StdRng::seed_from_u64(20);
+        // Free in a narrow horizontal passage and in a wide open area at the top
+        let is_free = |p: [f32; 2]| (p[1] - 0.3).abs() < 0.02 || p[1] > 0.7;
+        let samples = bridge_test(5000, 0.05, uniform, is_free, &mut rng);
+        assert!(!samples.is_empty());
+        for p in &samples {
+            assert!(is_free(*p));
+        }
+        // The passage is far smaller but gets most of them
+        let in_passage = samples.iter().filter(|p| p[1] < 0.5).count();
+        assert!(
+            in_passage > samples.len() / 2,
+            "{in_passage} of {}",
+            samples.len()
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Poisson disk sampling with Bridson's algorithm: samples no closer than a radius to each other
+//! that fill the box until no more fit. New samples are tried around active ones, at a distance
+//! between the radius and twice the radius, and a background grid with cells small enough to
+//! hold one sample each answers whether a try is too close. Linear in the number of samples.
+use rand::Rng;
+
+/// Tries around an active sample before it is retired.
+const ATTEMPTS: usize = 30;
+
+/// Samples of the box `[0, size)` at least `radius` apart, at most `max` of them. Starts from a
+/// uniform sample, so the first one is anywhere in the box.
+pub fn poisson_disk<const D: usize>(
+    size: [f32; D],
+    radius: f32,
+    max: usize,
+    rng: &mut impl Rng,
+) -> Vec<[f32; D]> {
+    if max == 0 || radius.is_nan() || radius <= 0.0 || size.iter().any(|s| !s.is_finite()) {
+        return vec![];
+    }
+    // A cell's diagonal is the radius, so it holds at most one sample
+    let cell = radius / (D as f32).sqrt();
+    let cells = size.map(|s| ((s / cell).ceil() as usize).max(1));
+    let mut grid: Vec<usize> = vec![usize::MAX; cells.iter().product()];
+    let index = |point: &[f32; D]| -> [usize; D] {
+        std::array::from_fn(|axis| ((point[axis] / cell) as usize).min(cells[axis] - 1))
+    };
+    let flat = |at: [usize; D]| (0..D).fold(0, |flat, axis| flat * cells[axis] + at[axis]);
+    // Cells away from a sample's cell that can still hold samples within the radius
+    let reach = (D as f32).sqrt().ceil() as usize;
+
+    let mut samples = vec![std::array::from_fn(|axis| rng.gen::<f32>() * size[axis])];
+    grid[flat(index(&samples[0]))] = 0;
+    let mut active = vec![0];
+    while !active.is_empty() && samples.len() < max {
+        let a = rng.gen_range(0..active.len());
+        let center = samples[active[a]];
+        let crowded = |candidate: &[f32; D]| {
+            let at = index(candidate);
+            let span = 2 * reach + 1;
+            (0..span.pow(D as u32)).any(|mut offset| {
+                let mut neighbor = [0; D];
+                for axis in (0..D).rev() {
+                    match (at[axis] + offset % span).checked_sub(reach) {
+                        Some(i) if i < cells[axis] => neighbor[axis] = i,
+                        _ => return false,
+                    }
+                    offset /= span;
+                }
+                let s = grid[flat(neighbor)];
+                s != usize::MAX && distance_squared(&samples[s], candidate) < radius * radius
+            })
+        };
+        let found = (0..ATTEMPTS).find_map(|_| {
+            let candidate = around(center, radius, rng);
+            let inside = (0..D).all(|axis| 0.0 <= candidate[axis] && candidate[axis] < size[axis]);
+            (inside && !crowded(&candidate)).then_some(candidate)
+        });
+        match found {
+            Some(candidate) => {
+                grid[flat(index(&candidate))] = samples.len();
+                active.push(samples.len());
+                samples.push(candidate);
+            }
+            None => {
+                active.swap_remove(a);
+            }
+        }
+    }
+    samples
+}
+
+fn distance_squared<const D: usize>(a: &[f32; D], b: &[f32; D]) -> f32 {
+    (0..D).map(|axis| (a[axis] - b[axis]).powi(2)).sum()
+}
+
+/// Uniform sample of the shell between `radius` and twice the radius around the center, by
+/// rejection from the enclosing cube.
+fn around<const D: usize>(center: [f32; D], radius: f32, rng: &mut impl Rng) -> [f32; D] {
+    loop {
+        let offset: [f32; D] = std::array::from_fn(|_| rng.gen_range(-2.0..2.0));
+        let squared: f32 = offset.iter().map(|o| o * o).sum();
+        if (1.0..=4.0).contains(&squared) {
+            return std::array::from_fn(|axis| center[axis] + offset[axis] * radius);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Samples in the box, at least the radius apart and no more than the maximum.
+    fn check<const D: usize>(size: [f32; D], radius: f32, max: usize, seed: u64) -> Vec<[f32; D]> {
+        let samples = poisson_disk(size, radius, max, &mut StdRng::seed_from_u64(seed));
+        assert!(samples.len() <= max);
+        for (i, a) in samples.iter().enumerate() {
+            assert!((0..D).all(|axis| 0.0 <= a[axis] && a[axis] < size[axis]));
+            for b in &samples[..i] {
+                assert!(distance_squared(a, b) >= radius * radius, "{a:?} {b:?}");
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn spaced_and_filling() {
+        let (size, radius) = ([10.0, 6.0], 0.5);
+        let samples = check(size, radius, usize::MAX, 19);
+        // Nearly every point of the box is within twice the radius of a sample, as tries go that
+        // far out before a sample is retired
+        let probes: Vec<[f32; 2]> = (0..100)
+            .flat_map(|i| (0..60).map(move |j| [i as f32 / 10.0, j as f32 / 10.0]))
+            .collect();
+        let covered = probes
+            .iter()
+            .filter(|p| {
+                samples
+                    .iter()
+                    .any(|s| distance_squared(s, p) <= 4.0 * radius * radius)
+            })
+            .count();
+        assert!(covered as f32 >= 0.99 * probes.len() as f32, "{covered}");
+
+        check([3.0, 2.0, 1.0], 0.3, usize::MAX, 20);
+        assert_eq!(check([10.0, 10.0], 0.1, 50, 21).len(), 50);
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        for (radius, max) in [(0.0, 10), (-1.0, 10), (f32::NAN, 10), (1.0, 0)] {
+            assert!(check([1.0, 1.0], radius, max, 22).is_empty());
+        }
+        assert!(poisson_disk(
+            [f32::INFINITY, 1.0],
+            1.0,
+            10,
+            &mut StdRng::seed_from_u64(23)
+        )
+        .is_empty());
+        // A box smaller than the radius holds a single sample
+        assert_eq!(check([0.5, 0.5], 1.0, 10, 24).len(), 1);
+    }
+}
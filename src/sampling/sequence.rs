@@ -0,0 +1,186 @@
+//! Low discrepancy sequences in the unit cube. Any prefix of them spreads out evenly, so points
+//! can be taken one at a time without the clumps of independent samples.
+
+/// First primes, the bases of the Halton sequence along each axis.
+const PRIMES: [u64; 8] = [2, 3, 5, 7, 11, 13, 17, 19];
+
+/// Primitive polynomial degree `s`, its coefficients `a` and initial direction numbers `m` of the
+/// Sobol sequence along each axis after the first, from Joe and Kuo's `new-joe-kuo-6.21201`.
+const SOBOL: [(u32, u32, &[u32]); 7] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+];
+
+/// Digits of the index in the base, mirrored around the radix point.
+pub fn radical_inverse(mut index: u64, base: u64) -> f32 {
+    let (mut inverse, mut scale) = (0.0_f64, 1.0);
+    while index > 0 {
+        scale /= base as f64;
+        inverse += (index % base) as f64 * scale;
+        index /= base;
+    }
+    inverse as f32
+}
+
+/// Point of the Halton sequence at the index, up to 8 dimensions. Index 0 is the origin.
+pub fn halton<const D: usize>(index: u64) -> [f32; D] {
+    assert!(
+        D <= PRIMES.len(),
+        "Halton sequence up to {} dimensions",
+        PRIMES.len()
+    );
+    std::array::from_fn(|axis| radical_inverse(index, PRIMES[axis]))
+}
+
+/// Point of the Sobol sequence at the index, up to 8 dimensions. Every aligned block of `2^k`
+/// points starting from index 0 has one point in each box of volume `2^-k` of a dyadic grid,
+/// for the first two axes at least.
+pub fn sobol<const D: usize>(index: u32) -> [f32; D] {
+    assert!(
+        D <= SOBOL.len() + 1,
+        "Sobol sequence up to {} dimensions",
+        SOBOL.len() + 1
+    );
+    std::array::from_fn(|axis| {
+        let directions = directions(axis);
+        let bits = (0..32)
+            .filter(|&k| index >> k & 1 == 1)
+            .fold(0, |bits, k| bits ^ directions[k]);
+        (bits as f64 / 2f64.powi(32)) as f32
+    })
+}
+
+/// Direction numbers of the axis, as 32 bit fractions.
+fn directions(axis: usize) -> [u32; 32] {
+    let mut v = [0u32; 32];
+    if axis == 0 {
+        for (k, v) in v.iter_mut().enumerate() {
+            *v = 1 << (31 - k);
+        }
+        return v;
+    }
+    let (s, a, m) = SOBOL[axis - 1];
+    let s = s as usize;
+    for k in 0..32 {
+        v[k] = if k < s {
+            m[k] << (31 - k)
+        } else {
+            // Recurrence from the primitive polynomial x^s + a_1 x^(s-1) + ... + a_(s-1) x + 1
+            (1..s).fold(v[k - s] ^ (v[k - s] >> s), |acc, i| {
+                if a >> (s - 1 - i) & 1 == 1 {
+                    acc ^ v[k - i]
+                } else {
+                    acc
+                }
+            })
+        };
+    }
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Largest difference between the fraction of points in a box from the origin and its
+    /// volume, over boxes with corners on a grid, which estimates the star discrepancy.
+    fn discrepancy(points: &[[f32; 2]]) -> f32 {
+        let steps = 64;
+        let mut worst: f32 = 0.0;
+        for i in 1..=steps {
+            for j in 1..=steps {
+                let (x, y) = (i as f32 / steps as f32, j as f32 / steps as f32);
+                let inside = points.iter().filter(|p| p[0] < x && p[1] < y).count();
+                worst = worst.max((inside as f32 / points.len() as f32 - x * y).abs());
+            }
+        }
+        worst
+    }
+
+    #[test]
+    fn first_terms() {
+        assert_eq!(
+            (0..5).map(halton::<2>).collect::<Vec<_>>(),
+            [
+                [0.0, 0.0],
+                [0.5, 1.0 / 3.0],
+                [0.25, 2.0 / 3.0],
+                [0.75, 1.0 / 9.0],
+                [0.125, 4.0 / 9.0],
+            ]
+        );
+        assert_eq!(radical_inverse(6, 5), 0.24);
+        assert_eq!(
+            (0..5).map(sobol::<2>).collect::<Vec<_>>(),
+            [
+                [0.0, 0.0],
+                [0.5, 0.5],
+                [0.25, 0.75],
+                [0.75, 0.25],
+                [0.125, 0.625],
+            ]
+        );
+    }
+
+    #[test]
+    fn one_point_per_box() {
+        // The first 2^a 3^b Halton points have one in each box of 2^-a by 3^-b
+        for (a, b) in (0..=4).flat_map(|a| (0..=3).map(move |b| (a, b))) {
+            let (columns, rows) = (2usize.pow(a), 3usize.pow(b));
+            let mut seen = vec![false; columns * rows];
+            for i in 0..(columns * rows) as u64 {
+                let [x, y] = halton::<2>(i);
+                let cell = (x * columns as f32) as usize * rows + (y * rows as f32) as usize;
+                assert!(!seen[cell], "{i} in {columns} by {rows}");
+                seen[cell] = true;
+            }
+        }
+        // Every aligned block of 2^k Sobol points has one in each dyadic box of volume 2^-k, of
+        // any shape
+        for k in 0..=8 {
+            for block in 0..4 {
+                for a in 0..=k {
+                    let (columns, rows) = (1 << a, 1 << (k - a));
+                    let mut seen = vec![false; 1 << k];
+                    for i in block << k..(block + 1) << k {
+                        let [x, y] = sobol::<2>(i);
+                        let cell =
+                            (x * columns as f32) as usize * rows + (y * rows as f32) as usize;
+                        assert!(!seen[cell], "{i} in {columns} by {rows}");
+                        seen[cell] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lower_discrepancy_than_uniform() {
+        let n = 1000;
+        let halton: Vec<[f32; 2]> = (1..=n).map(halton).collect();
+        let sobol: Vec<[f32; 2]> = (0..n as u32).map(sobol).collect();
+        let mut rng = StdRng::seed_from_u64(19);
+        let uniform: Vec<[f32; 2]> = (0..n).map(|_| [rng.gen(), rng.gen()]).collect();
+        let (halton, sobol, uniform) = (
+            discrepancy(&halton),
+            discrepancy(&sobol),
+            discrepancy(&uniform),
+        );
+        assert!(halton < 0.01 && sobol < 0.01, "{halton} {sobol}");
+        assert!(uniform > 2.0 * halton.max(sobol), "{uniform}");
+    }
+
+    #[test]
+    fn higher_dimensions_in_the_unit_cube() {
+        for i in 0..1000 {
+            let points = [halton::<8>(i as u64), sobol::<8>(i)];
+            assert!(points.iter().flatten().all(|c| (0.0..1.0).contains(c)));
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Jittered grid: the unit cube is cut into cells and each cell gets one uniform sample, so no
+//! cell is left empty and no two samples share one.
+use rand::Rng;
+
+/// One sample in each cell of a grid with `cells[axis]` cells along each axis, in row major order
+/// with the last axis varying fastest.
+pub fn stratified<const D: usize>(cells: [usize; D], rng: &mut impl Rng) -> Vec<[f32; D]> {
+    let count: usize = cells.iter().product();
+    (0..count)
+        .map(|mut cell| {
+            let mut point = [0.0; D];
+            for axis in (0..D).rev() {
+                let i = cell % cells[axis];
+                cell /= cells[axis];
+                point[axis] = (i as f32 + rng.gen::<f32>()) / cells[axis] as f32;
+            }
+            point
+        })
+        .collect()
+}
+
+/// Cells along each axis of a box with the given side lengths, so that the cells are about cubes
+/// and about `count` in total. At least one along each axis, and only one along flat axes.
+pub fn cells_for<const D: usize>(size: [f32; D], count: usize) -> [usize; D] {
+    let extended: Vec<f32> = size.iter().copied().filter(|&s| s > 0.0).collect();
+    let volume: f32 = extended.iter().product();
+    let side = (volume / count.max(1) as f32).powf(1.0 / extended.len().max(1) as f32);
+    size.map(|s| ((s / side).round() as usize).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn one_sample_per_cell() {
+        let mut rng = StdRng::seed_from_u64(19);
+        let cells = [3, 5, 2];
+        let samples = stratified(cells, &mut rng);
+        assert_eq!(samples.len(), 30);
+        for (k, sample) in samples.iter().enumerate() {
+            let at: [usize; 3] =
+                std::array::from_fn(|axis| (sample[axis] * cells[axis] as f32) as usize);
+            assert_eq!(
+                (at[0] * cells[1] + at[1]) * cells[2] + at[2],
+                k,
+                "{sample:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn cells_are_about_cubes() {
+        assert_eq!(cells_for([1.0, 1.0], 100), [10, 10]);
+        assert_eq!(cells_for([4.0, 1.0], 100), [20, 5]);
+        assert_eq!(cells_for([2.0, 2.0, 2.0], 27), [3, 3, 3]);
+        // Flat axes get a single cell, and there is always at least one
+        assert_eq!(cells_for([1.0, 0.0], 10), [10, 1]);
+        assert_eq!(cells_for([1.0, 1.0], 0), [1, 1]);
+        assert_eq!(cells_for([0.0, 0.0], 10), [1, 1]);
+    }
+}
@@ -0,0 +1,328 @@
+use super::graph::*;
+use super::search::*;
+use ordered_float::OrderedFloat;
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    time::{Duration, Instant},
+};
+
+/// How long an anytime search may keep improving its path.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Budget {
+    Time(Duration),
+    Expansions(usize),
+}
+
+/// Path found by one round of an anytime search.
+#[derive(Clone, Debug)]
+pub struct AnytimeSolution<V = usize> {
+    /// Weight of the heuristic in the round
+    pub weight: f32,
+    /// The path costs at most this many times the cheapest, for a heuristic that never drops by
+    /// more than the edge cost
+    pub bound: f32,
+    pub cost: f32,
+    pub path: Vec<V>,
+    /// Expansions and time since the search began
+    pub expansions: usize,
+    pub elapsed: Duration,
+}
+
+pub struct AnytimeSearchResult<V = usize> {
+    /// Improving paths in the order they were found
+    pub solutions: Vec<AnytimeSolution<V>>,
+    /// Tree at the end of the search, with the last path
+    pub search: CostGuidedTreeSearchResult<V>,
+}
+
+/// Anytime repairing A* of Likhachev, Gordon and Thrun, which first finds a path quickly with a
+/// heavily weighted heuristic, then lowers the weight in steps and repairs the tree for a cheaper
+/// path with a tighter bound each round, until the path is the cheapest or the budget runs out.
+///
+/// A round only expands each vertex once, vertices that get cheaper after their expansion wait
+/// for the next round, which starts from the tree of the previous one. The bound compares the
+/// cost of the path with the lowest cost plus heuristic among vertices still to expand, which no
+/// cheaper path can avoid.
+#[derive(Clone, Copy, Debug)]
+pub struct ARAStar {
+    pub initial_weight: f32,
+    pub weight_step: f32,
+    pub budget: Budget,
+}
+
+impl ARAStar {
+    /// # Panics
+    ///
+    /// If `weight_step` is not positive or is NaN, as the weight would never come down.
+    pub fn try_on<G: SearchGraph>(
+        &self,
+        graph: &G,
+        start_idx: G::Vertex,
+        stop_idx: G::Vertex,
+    ) -> AnytimeSearchResult<G::Vertex> {
+        assert!(self.weight_step > 0.0, "ARA* needs a positive weight step");
+        let began = Instant::now();
+        let mut expansions = 0;
+        let out_of_budget = |expansions: usize| match self.budget {
+            Budget::Time(time) => began.elapsed() >= time,
+            Budget::Expansions(max) => expansions >= max,
+        };
+        // Distances from the start live in the workspace, which is not reset between rounds
+        let mut w = SearchWorkspace::<G::Vertex, f32>::new();
+        w.begin(graph);
+        let mut status = vec![];
+        let start = Status::id(&mut status, &mut w, graph, &start_idx);
+        let stop = Status::id(&mut status, &mut w, graph, &stop_idx);
+        w.reach(start, start_idx.clone(), usize::MAX, 0.0);
+        status[start].open = true;
+        let heuristic = |w: &SearchWorkspace<G::Vertex, f32>, id: usize| {
+            graph.heuristic(&w.vertex(id), &stop_idx)
+        };
+        let mut open_list = vec![start];
+        let mut inconsistent_list = vec![];
+        let mut tree = vec![start];
+        let mut solutions: Vec<AnytimeSolution<G::Vertex>> = vec![];
+        let mut weight = self.initial_weight.max(1.0);
+        let mut round = 0;
+        'rounds: loop {
+            round += 1;
+            let mut fringe = BinaryHeap::new();
+            for id in open_list.drain(..).chain(inconsistent_list.drain(..)) {
+                if status[id].open || status[id].inconsistent {
+                    let key = *w.state(id) + weight * heuristic(&w, id);
+                    fringe.push(Reverse((OrderedFloat(key), id)));
+                    status[id].open = true;
+                    status[id].inconsistent = false;
+                }
+            }
+            // Entries of vertices that were expanded in the meantime are outdated, the lowest
+            // entry of every other vertex has its current key
+            while let Some(&Reverse((OrderedFloat(key), curr))) = fringe.peek() {
+                if !status[curr].open {
+                    fringe.pop();
+                    continue;
+                }
+                if w.is_reached(stop) && *w.state(stop) <= key {
+                    break;
+                }
+                if out_of_budget(expansions) {
+                    break 'rounds;
+                }
+                fringe.pop();
+                status[curr].open = false;
+                status[curr].closed_in = round;
+                expansions += 1;
+                let curr_idx = w.vertex(curr);
+                for (adj_idx, edge_cost) in graph.successors(&curr_idx) {
+                    let adj = Status::id(&mut status, &mut w, graph, &adj_idx);
+                    let dist = *w.state(curr) + edge_cost;
+                    if w.is_reached(adj) {
+                        if *w.state(adj) <= dist {
+                            continue;
+                        }
+                    } else {
+                        tree.push(adj);
+                    }
+                    w.reach(adj, adj_idx, curr, dist);
+                    if status[adj].closed_in == round {
+                        if !status[adj].inconsistent {
+                            status[adj].inconsistent = true;
+                            inconsistent_list.push(adj);
+                        }
+                    } else {
+                        let key = dist + weight * heuristic(&w, adj);
+                        fringe.push(Reverse((OrderedFloat(key), adj)));
+                        status[adj].open = true;
+                    }
+                }
+            }
+            open_list.extend(
+                fringe
+                    .into_iter()
+                    .map(|Reverse((_, id))| id)
+                    .filter(|&id| status[id].open),
+            );
+            open_list.sort_unstable();
+            open_list.dedup();
+            if !w.is_reached(stop) {
+                break;
+            }
+            // Vertices on the path may have got cheaper after their successor on it was reached,
+            // so the path can cost less than the distance of the stop
+            let path = w.path_to(stop);
+            let cost = path
+                .windows(2)
+                .map(|edge| {
+                    graph
+                        .successors(&edge[0])
+                        .filter(|(vertex, _)| *vertex == edge[1])
+                        .map(|(_, cost)| cost)
+                        .fold(f32::INFINITY, f32::min)
+                })
+                .sum::<f32>();
+            let lowest = open_list
+                .iter()
+                .chain(inconsistent_list.iter())
+                .map(|&id| *w.state(id) + heuristic(&w, id))
+                .fold(f32::INFINITY, f32::min);
+            let bound = if cost <= lowest {
+                1.0
+            } else {
+                weight.min(cost / lowest)
+            };
+            if solutions.last().is_none_or(|last| cost < last.cost) {
+                solutions.push(AnytimeSolution {
+                    weight,
+                    bound,
+                    cost,
+                    path,
+                    expansions,
+                    elapsed: began.elapsed(),
+                });
+            } else if let Some(last) = solutions.last_mut() {
+                // No cheaper path, but the same cost is proven closer to the cheapest
+                last.bound = last.bound.min(bound);
+            }
+            if bound <= 1.0 || weight <= 1.0 {
+                break;
+            }
+            weight = (weight - self.weight_step).max(1.0);
+        }
+        let fringe = tree
+            .iter()
+            .filter(|&&id| status[id].open || status[id].inconsistent)
+            .map(|&id| w.vertex(id))
+            .collect();
+        let search = CostGuidedTreeSearchResult {
+            start_idx,
+            stop_idx,
+            reached: !solutions.is_empty(),
+            tree: tree.iter().map(|&id| w.tree_entry(id)).collect(),
+            backward: vec![],
+            fringe,
+            meeting: None,
+            path: solutions.last().map(|solution| solution.path.clone()),
+        };
+        AnytimeSearchResult { solutions, search }
+    }
+}
+
+/// Whether the vertex waits in the open or the inconsistent list of ARA*, and the round that
+/// expanded it last.
+#[derive(Clone, Copy, Default)]
+struct Status {
+    open: bool,
+    inconsistent: bool,
+    closed_in: usize,
+}
+
+impl Status {
+    fn id<G: SearchGraph>(
+        status: &mut Vec<Status>,
+        w: &mut SearchWorkspace<G::Vertex, f32>,
+        graph: &G,
+        vertex: &G::Vertex,
+    ) -> usize {
+        let id = w.id(graph, vertex);
+        if id >= status.len() {
+            status.resize(id + 1, Status::default());
+        }
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::search::tests::{all_pairs, assert_cheapest, random_graph};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn solutions_within_bounds() {
+        let mut rng = StdRng::seed_from_u64(24);
+        let ara = ARAStar {
+            initial_weight: 5.0,
+            weight_step: 1.5,
+            budget: Budget::Expansions(usize::MAX),
+        };
+        for _ in 0..30 {
+            let graph = random_graph(&mut rng, 16, 40);
+            let dist = all_pairs(&graph);
+            for start in 0..16 {
+                for stop in 0..16 {
+                    let result = ara.try_on(&graph, start, stop);
+                    let cheapest = dist[start][stop];
+                    assert_eq!(result.solutions.is_empty(), cheapest.is_infinite());
+                    for solution in &result.solutions {
+                        assert!(solution.bound >= 1.0 && solution.bound <= solution.weight);
+                        let bound = solution.bound as f64 * cheapest;
+                        assert!(solution.cost as f64 <= bound + 1e-4 * bound.max(1.0));
+                    }
+                    assert!(result.solutions.windows(2).all(|s| s[1].cost < s[0].cost));
+                    // Unbounded, the search goes on until the path is the cheapest
+                    let last = result.solutions.last().map(|s| s.path.as_slice());
+                    assert_cheapest(&graph, &dist, (start, stop), last);
+                    assert_eq!(result.search.path.as_deref(), last);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stops_on_the_budget() {
+        let mut rng = StdRng::seed_from_u64(25);
+        let ara = |budget: Budget| ARAStar {
+            initial_weight: 5.0,
+            weight_step: 0.5,
+            budget,
+        };
+        let mut loose = 0;
+        for _ in 0..10 {
+            let graph = random_graph(&mut rng, 100, 300);
+            for _ in 0..20 {
+                let (start, stop) = (rng.gen_range(0..100), rng.gen_range(1..100));
+                let unbounded = ara(Budget::Expansions(usize::MAX)).try_on(&graph, start, stop);
+                let Some(first) = unbounded.solutions.first() else {
+                    continue;
+                };
+                // Just enough for the first round
+                let result = ara(Budget::Expansions(first.expansions)).try_on(&graph, start, stop);
+                assert_eq!(result.solutions.len(), 1);
+                let solution = &result.solutions[0];
+                assert_eq!((&solution.path, solution.cost), (&first.path, first.cost));
+                // Later rounds may have proven the unbounded one tighter
+                assert!(solution.bound >= first.bound);
+                assert!(result.search.reached);
+                assert_eq!(result.search.path.as_ref(), Some(&solution.path));
+                if solution.bound > 1.0 {
+                    loose += 1;
+                }
+                // Too little for any path
+                if start != stop {
+                    let budget = Budget::Expansions(first.expansions - 1);
+                    let result = ara(budget).try_on(&graph, start, stop);
+                    assert!(result.solutions.is_empty());
+                    assert!(!result.search.reached);
+                    assert_eq!(result.search.path, None);
+                }
+            }
+        }
+        assert!(loose > 0);
+        let graph = random_graph(&mut rng, 10, 30);
+        let result = ara(Budget::Time(Duration::ZERO)).try_on(&graph, 0, 1);
+        assert!(result.solutions.is_empty() && !result.search.reached);
+    }
+
+    #[test]
+    #[should_panic(expected = "positive weight step")]
+    fn nan_weight_step() {
+        let graph = random_graph(&mut StdRng::seed_from_u64(26), 4, 4);
+        let ara = ARAStar {
+            initial_weight: 2.0,
+            weight_step: f32::NAN,
+            budget: Budget::Expansions(10),
+        };
+        ara.try_on(&graph, 0, 1);
+    }
+}
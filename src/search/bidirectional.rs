@@ -0,0 +1,263 @@
+use super::graph::*;
+use super::search::*;
+use ordered_float::OrderedFloat;
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+/// Search that grows one tree from the start and one from the stop until they meet. In open
+/// space two trees of half the radius cover less than one that has to reach all the way, along
+/// corridors they do not. The backward tree follows the successors too, so edges have to go both
+/// ways with the same cost, as in [`Graph`].
+///
+/// Each step expands the lowest key vertex of the tree whose lowest key is smaller, and every
+/// edge between the trees is a candidate for the cheapest path. Meeting is not enough, the first
+/// candidate can be beaten by a later one, so the search stops once the lowest keys of both
+/// trees add up to the best candidate, which no path through unexpanded vertices can improve on.
+pub trait BidirectionalSearch {
+    /// Cost of the edge in the search.
+    fn edge_cost(cost: f32) -> f32;
+
+    /// Added to the key of the vertex in the forward tree and subtracted in the backward tree.
+    /// Zero by default.
+    fn potential<G: SearchGraph>(
+        _graph: &G,
+        _vertex: &G::Vertex,
+        _start: &G::Vertex,
+        _stop: &G::Vertex,
+    ) -> f32 {
+        0.0
+    }
+
+    fn try_on<G: SearchGraph>(
+        graph: &G,
+        start_idx: G::Vertex,
+        stop_idx: G::Vertex,
+    ) -> CostGuidedTreeSearchResult<G::Vertex> {
+        Self::try_on_with(&mut Default::default(), graph, start_idx, stop_idx)
+    }
+
+    /// Like [`Self::try_on`], reusing the bookkeeping of the forward and backward trees of
+    /// earlier queries on the same graph.
+    fn try_on_with<G: SearchGraph>(
+        workspaces: &mut [SearchWorkspace<G::Vertex, f32>; 2],
+        graph: &G,
+        start_idx: G::Vertex,
+        stop_idx: G::Vertex,
+    ) -> CostGuidedTreeSearchResult<G::Vertex> {
+        let potential = |vertex: &G::Vertex, side: usize| {
+            let potential = Self::potential(graph, vertex, &start_idx, &stop_idx);
+            if side == 0 {
+                potential
+            } else {
+                -potential
+            }
+        };
+        let mut fringes = [BinaryHeap::new(), BinaryHeap::new()];
+        let mut trees = [vec![], vec![]];
+        for (side, root) in [&start_idx, &stop_idx].into_iter().enumerate() {
+            let w = &mut workspaces[side];
+            w.begin(graph);
+            let id = w.id(graph, root);
+            w.reach(id, root.clone(), usize::MAX, 0.0);
+            fringes[side].push(Reverse((OrderedFloat(potential(root, side)), id)));
+            trees[side].push(id);
+        }
+        let [forward, backward] = &mut *workspaces;
+        // Cheapest path found so far, by its cost and the vertex where the trees meet on it in
+        // the forward and backward bookkeeping
+        let mut best = None;
+        if start_idx == stop_idx {
+            best = Some((0.0, trees[0][0], trees[1][0]));
+        }
+        loop {
+            let mut lowest = [None, None];
+            for (side, w) in [&*forward, &*backward].into_iter().enumerate() {
+                while let Some(&Reverse((key, id))) = fringes[side].peek() {
+                    if !w.is_expanded(id) {
+                        lowest[side] = Some(key.0);
+                        break;
+                    }
+                    fringes[side].pop();
+                }
+            }
+            let (Some(forward_key), Some(backward_key)) = (lowest[0], lowest[1]) else {
+                break;
+            };
+            if matches!(best, Some((cost, _, _)) if forward_key + backward_key >= cost) {
+                break;
+            }
+            let side = if forward_key <= backward_key { 0 } else { 1 };
+            let (w, other) = if side == 0 {
+                (&mut *forward, &mut *backward)
+            } else {
+                (&mut *backward, &mut *forward)
+            };
+            let Reverse((_, curr)) = fringes[side].pop().unwrap();
+            w.expand(curr);
+            let curr_idx = w.vertex(curr);
+            for (adj_idx, edge_cost) in graph.successors(&curr_idx) {
+                let adj = w.id(graph, &adj_idx);
+                if w.is_expanded(adj) {
+                    continue;
+                }
+                let dist = *w.state(curr) + Self::edge_cost(edge_cost);
+                if w.is_reached(adj) {
+                    if *w.state(adj) <= dist {
+                        continue;
+                    }
+                } else {
+                    trees[side].push(adj);
+                }
+                let key = dist + potential(&adj_idx, side);
+                fringes[side].push(Reverse((OrderedFloat(key), adj)));
+                let other_adj = other.id(graph, &adj_idx);
+                if other.is_reached(other_adj) {
+                    let cost = dist + *other.state(other_adj);
+                    if best.is_none_or(|(best, _, _)| cost < best) {
+                        let [forward, backward] = if side == 0 {
+                            [adj, other_adj]
+                        } else {
+                            [other_adj, adj]
+                        };
+                        best = Some((cost, forward, backward));
+                    }
+                }
+                w.reach(adj, adj_idx, curr, dist);
+            }
+        }
+        let meeting = best.map(|(_, meeting, _)| forward.vertex(meeting));
+        let path = best.map(|(_, forward_meeting, backward_meeting)| {
+            let mut path = forward.path_to(forward_meeting);
+            let mut to_stop = backward.path_to(backward_meeting);
+            to_stop.pop();
+            path.extend(to_stop.into_iter().rev());
+            path
+        });
+        let [forward_tree, backward_tree] = trees;
+        let fringe = forward_tree
+            .iter()
+            .filter(|&&id| !forward.is_expanded(id))
+            .map(|&id| forward.vertex(id))
+            .chain(
+                backward_tree
+                    .iter()
+                    .filter(|&&id| !backward.is_expanded(id))
+                    .map(|&id| backward.vertex(id)),
+            )
+            .collect();
+        CostGuidedTreeSearchResult {
+            start_idx,
+            stop_idx,
+            reached: meeting.is_some(),
+            tree: forward_tree
+                .iter()
+                .map(|&id| forward.tree_entry(id))
+                .collect(),
+            backward: backward_tree
+                .iter()
+                .map(|&id| backward.tree_entry(id))
+                .collect(),
+            fringe,
+            meeting,
+            path,
+        }
+    }
+}
+
+/// Bidirectional search with the fewest edges.
+pub struct BidirectionalBFS;
+
+impl BidirectionalSearch for BidirectionalBFS {
+    fn edge_cost(_: f32) -> f32 {
+        1.0
+    }
+}
+
+/// Bidirectional search with the lowest cost.
+pub struct BidirectionalUCS;
+
+impl BidirectionalSearch for BidirectionalUCS {
+    fn edge_cost(cost: f32) -> f32 {
+        cost
+    }
+}
+
+/// Bidirectional search with the lowest cost, guided by the heuristic towards both ends. Each
+/// tree takes half the difference of the heuristics to the two ends as potential, the same for
+/// both so that their keys add up to the cost of a path between them, which is what keeps the
+/// stopping rule correct for a heuristic that never drops by more than the edge cost.
+pub struct BidirectionalAStar;
+
+impl BidirectionalSearch for BidirectionalAStar {
+    fn edge_cost(cost: f32) -> f32 {
+        cost
+    }
+
+    fn potential<G: SearchGraph>(
+        graph: &G,
+        vertex: &G::Vertex,
+        start: &G::Vertex,
+        stop: &G::Vertex,
+    ) -> f32 {
+        (graph.heuristic(vertex, stop) - graph.heuristic(vertex, start)) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::search::tests::{all_pairs, assert_cheapest, random_graph};
+    use crate::search::search::UCS;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn cheapest_paths_on_random_graphs() {
+        let mut rng = StdRng::seed_from_u64(23);
+        let mut workspaces = Default::default();
+        for _ in 0..40 {
+            // Sparse enough that some pairs are disconnected
+            let graph = random_graph(&mut rng, 12, 16);
+            let dist = all_pairs(&graph);
+            for start in 0..12 {
+                for stop in 0..12 {
+                    let ucs = UCS::try_on(&graph, start, stop);
+                    for result in [
+                        BidirectionalUCS::try_on(&graph, start, stop),
+                        BidirectionalAStar::try_on_with(&mut workspaces, &graph, start, stop),
+                    ] {
+                        assert_eq!(result.reached, ucs.reached);
+                        assert_cheapest(&graph, &dist, (start, stop), result.path.as_deref());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_start_and_stop() {
+        let mut rng = StdRng::seed_from_u64(24);
+        let graph = random_graph(&mut rng, 8, 12);
+        for vertex in 0..8 {
+            for result in [
+                BidirectionalUCS::try_on(&graph, vertex, vertex),
+                BidirectionalAStar::try_on(&graph, vertex, vertex),
+            ] {
+                assert_eq!(result.path, Some(vec![vertex]));
+                assert_eq!(result.meeting, Some(vertex));
+            }
+        }
+    }
+
+    #[test]
+    fn disconnected_pair() {
+        let mut rng = StdRng::seed_from_u64(25);
+        let mut graph = random_graph(&mut rng, 10, 30);
+        graph.block_vertex(9);
+        for result in [
+            BidirectionalUCS::try_on(&graph, 0, 9),
+            BidirectionalAStar::try_on(&graph, 9, 0),
+        ] {
+            assert!(!result.reached);
+            assert_eq!((result.path, result.meeting), (None, None));
+        }
+    }
+}
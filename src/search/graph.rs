@@ -1,18 +1,52 @@
 use super::spaces::*;
+use crate::geometry::kdtree::KdTree;
+use crate::sampling::Sampler;
 use bevy::prelude::*;
-use rand::distributions::Standard;
 use rand::{thread_rng, Rng};
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Graph that the searches in [`super::search`] run on, given by the successors of each vertex
+/// with the cost of the edge to them. Vertices can be anything hashable, e.g. indices, grid cells,
+/// puzzle states or poses, and successors can be generated on the fly for implicit graphs.
+pub trait SearchGraph {
+    type Vertex: Clone + Eq + Hash;
+
+    fn successors(&self, vertex: &Self::Vertex) -> impl Iterator<Item = (Self::Vertex, f32)>;
+
+    /// Estimate of the cost from `from` to `to` for A*, which finds the cheapest path as long as
+    /// it never overestimates. None by default.
+    fn heuristic(&self, _from: &Self::Vertex, _to: &Self::Vertex) -> f32 {
+        0.0
+    }
+
+    /// Number of the vertex below [`Self::vertex_count`], for graphs that number their vertices.
+    /// Searches keep their bookkeeping in arrays indexed by it, and hash vertices without it.
+    fn index(&self, _vertex: &Self::Vertex) -> Option<usize> {
+        None
+    }
+
+    fn vertex_count(&self) -> usize {
+        0
+    }
+}
 
 #[derive(Default)]
 pub struct Vertex {
     pub pos: Vec3,
-    pub adjacencies: HashSet<usize>,
+    /// Cost of the edge to each adjacent vertex
+    pub adjacencies: HashMap<usize, f32>,
 }
 
 #[derive(Resource, Default)]
 pub struct Graph {
     pub vertices: Vec<Vertex>,
+    /// Version that the first of the changes follows on
+    base: usize,
+    /// Ends of every edge changed by [`Graph::set_edge_cost`] since the vertices were generated,
+    /// oldest first, for incremental planners to catch up on, apart from those that
+    /// [`Graph::compact`] dropped
+    changes: Vec<[usize; 2]>,
 }
 
 impl Graph {
@@ -22,26 +56,33 @@ impl Graph {
         num_samples: usize,
         edge_len: f32,
     ) {
-        let mut rng = thread_rng();
-        let state_samples: Vec<Vec3> = (&mut rng)
-            .sample_iter(Standard)
-            .take(num_samples)
-            .map(|(x, y, z): (f32, f32, f32)| {
-                Vec3::new(x * space.size.x, y * space.size.y, z * space.size.z) - space.size / 2.0
-            })
-            .filter(|point| point.length() > space.hole_radius)
-            .collect();
-        let mut adjacencies = vec![HashSet::new(); state_samples.len()];
-        for i in 0..(state_samples.len() - 1) {
-            let s1 = state_samples[i];
-            for j in (i + 1)..state_samples.len() {
-                let s2 = state_samples[j];
-                if (s1 - s2).length() <= edge_len {
-                    adjacencies[i].insert(j);
-                    adjacencies[j].insert(i);
-                }
-            }
-        }
+        self.generate_samples_with(
+            space,
+            Sampler::Uniform,
+            num_samples,
+            edge_len,
+            &mut thread_rng(),
+        );
+    }
+
+    /// Samples the space outside the hole with the sampler, then connects every pair of samples
+    /// within `edge_len` of each other.
+    pub fn generate_samples_with(
+        &mut self,
+        space: &CuboidWithHoldSpace,
+        sampler: Sampler,
+        num_samples: usize,
+        edge_len: f32,
+        rng: &mut impl Rng,
+    ) {
+        let state_samples = sampler.sample_3d(
+            num_samples,
+            -space.size / 2.0,
+            space.size / 2.0,
+            |point| point.length() > space.hole_radius,
+            rng,
+        );
+        let adjacencies = connect(&state_samples, edge_len);
         self.vertices = state_samples
             .into_iter()
             .zip(adjacencies.into_iter())
@@ -50,6 +91,49 @@ impl Graph {
                 adjacencies,
             })
             .collect();
+        // Generating counts as a change of its own, so that versions from before are outdated
+        self.base = self.version() + 1;
+        self.changes.clear();
+    }
+
+    /// Sets the cost of the edge both ways, adding it if missing, or removes it for `None`.
+    pub fn set_edge_cost(&mut self, a: usize, b: usize, cost: Option<f32>) {
+        for (from, to) in [(a, b), (b, a)] {
+            match cost {
+                Some(cost) => self.vertices[from].adjacencies.insert(to, cost),
+                None => self.vertices[from].adjacencies.remove(&to),
+            };
+        }
+        self.changes.push([a, b]);
+    }
+
+    /// Removes every edge of the vertex.
+    pub fn block_vertex(&mut self, vertex: usize) {
+        let adjacencies: Vec<usize> = self.vertices[vertex].adjacencies.keys().copied().collect();
+        for adj in adjacencies {
+            self.set_edge_cost(vertex, adj, None);
+        }
+    }
+
+    /// Number of changes so far, to pass to [`Graph::changes_since`] later. It only grows, each
+    /// edge changed and each time the vertices are generated count.
+    pub fn version(&self) -> usize {
+        self.base + self.changes.len()
+    }
+
+    /// Ends of the edges changed after the version, each once per change. None if the vertices
+    /// were generated anew since, which no edge changes lead to, or if the changes were dropped.
+    pub fn changes_since(&self, version: usize) -> Option<&[[usize; 2]]> {
+        let since = version.checked_sub(self.base)?;
+        Some(&self.changes[since.min(self.changes.len())..])
+    }
+
+    /// Drops the changes up to the version, which should be the oldest version that a planner is
+    /// still at, so that they do not pile up. Planners behind it start over on their next update.
+    pub fn compact(&mut self, version: usize) {
+        let dropped = version.saturating_sub(self.base).min(self.changes.len());
+        self.changes.drain(..dropped);
+        self.base += dropped;
     }
 
     pub fn choose_random_vertex_idx(&self) -> usize {
@@ -57,3 +141,90 @@ impl Graph {
         rng.gen_range(0..self.vertices.len())
     }
 }
+
+/// Cost of the edges between every pair of points within `edge_len` of each other, both ways.
+fn connect(points: &[Vec3], edge_len: f32) -> Vec<HashMap<usize, f32>> {
+    // Same test as comparing every pair, `within_radius` uses `Vec3::distance`
+    let tree = KdTree::new(points);
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, &point)| {
+            tree.within_radius(point, edge_len)
+                .into_iter()
+                .filter(|&j| j != i)
+                .map(|j| (j, point.distance(points[j])))
+                .collect()
+        })
+        .collect()
+}
+
+/// Vertices are indices into `vertices`, the heuristic is the distance between positions.
+impl SearchGraph for Graph {
+    type Vertex = usize;
+
+    fn successors(&self, &vertex: &usize) -> impl Iterator<Item = (usize, f32)> {
+        self.vertices[vertex]
+            .adjacencies
+            .iter()
+            .map(|(&adj, &cost)| (adj, cost))
+    }
+
+    fn heuristic(&self, &from: &usize, &to: &usize) -> f32 {
+        (self.vertices[from].pos - self.vertices[to].pos).length()
+    }
+
+    fn index(&self, &vertex: &usize) -> Option<usize> {
+        Some(vertex)
+    }
+
+    fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn check(points: &[Vec3], edge_len: f32) {
+        let adjacencies = connect(points, edge_len);
+        for (i, adjacencies) in adjacencies.iter().enumerate() {
+            let expected: HashMap<usize, f32> = (0..points.len())
+                .filter(|&j| j != i && points[i].distance(points[j]) <= edge_len)
+                .map(|j| (j, points[i].distance(points[j])))
+                .collect();
+            assert_eq!(*adjacencies, expected, "{i}");
+        }
+    }
+
+    #[test]
+    fn radius_adjacency_agrees_with_brute_force() {
+        let mut rng = StdRng::seed_from_u64(18);
+        for count in [0, 1, 2, 10, 300] {
+            for edge_len in [0.0, 0.1, 0.3, 2.0] {
+                let points: Vec<Vec3> = (0..count)
+                    .map(|_| Vec3::new(rng.gen(), rng.gen(), rng.gen()))
+                    .collect();
+                check(&points, edge_len);
+            }
+        }
+    }
+
+    #[test]
+    fn points_exactly_at_the_radius() {
+        // Neighbors on the grid are at exactly 1, 2 or the diagonals, repeated points at 0
+        let grid: Vec<Vec3> = (0..64)
+            .map(|i| Vec3::new((i % 4) as f32, (i / 4 % 4) as f32, (i / 16) as f32))
+            .collect();
+        let doubled = [grid.clone(), grid.clone()].concat();
+        for edge_len in [0.0, 1.0, 2.0_f32.sqrt(), 3.0_f32.sqrt(), 2.0] {
+            check(&grid, edge_len);
+            check(&doubled, edge_len);
+        }
+        let adjacencies = connect(&grid, 1.0);
+        assert_eq!(adjacencies[0].len(), 3);
+        assert_eq!(adjacencies[21].len(), 6);
+    }
+}
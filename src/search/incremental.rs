@@ -0,0 +1,406 @@
+use super::graph::*;
+use super::search::*;
+use ordered_float::OrderedFloat;
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+/// Lifelong planning A* of Koenig and Likhachev, which keeps the distances from the start between
+/// queries and after edges of the graph change only repairs the ones that the change affects,
+/// instead of searching again from scratch. After the vertices of the graph are generated anew it
+/// starts over, and has no path if the start or the goal is no longer a vertex.
+pub struct LPAStar(Planner);
+
+impl LPAStar {
+    pub fn new(graph: &Graph, start: usize, goal: usize) -> Self {
+        Self(Planner::new(graph, start, goal))
+    }
+
+    /// Catches up on the edges changed on the graph since the last update.
+    pub fn update(&mut self, graph: &Graph) {
+        self.0.update(graph);
+    }
+
+    pub fn path(&self, graph: &Graph) -> Option<Vec<usize>> {
+        let mut path = self.0.path(graph)?;
+        path.reverse();
+        Some(path)
+    }
+
+    /// Vertices expanded since the planner was created.
+    pub fn expansions(&self) -> usize {
+        self.0.expansions
+    }
+
+    /// Version of the graph caught up on, the oldest one to keep with [`Graph::compact`].
+    pub fn version(&self) -> usize {
+        self.0.version
+    }
+
+    pub fn search(&self, graph: &Graph) -> CostGuidedTreeSearchResult {
+        let tree = self.0.tree(graph);
+        CostGuidedTreeSearchResult {
+            start_idx: self.0.root,
+            stop_idx: self.0.target,
+            reached: self.0.reached(),
+            tree,
+            backward: vec![],
+            fringe: self.0.fringe(),
+            meeting: None,
+            path: self.path(graph),
+        }
+    }
+}
+
+/// D* Lite of Koenig and Likhachev, the same repair as [`LPAStar`] for an agent that moves on to
+/// new starts as it follows the path. It keeps the distances to the goal instead, which stay
+/// valid whatever the start, and only the heuristic towards the start has to be made up for.
+pub struct DStarLite(Planner);
+
+impl DStarLite {
+    pub fn new(graph: &Graph, start: usize, goal: usize) -> Self {
+        Self(Planner::new(graph, goal, start))
+    }
+
+    /// Moves the agent, taken into account at the next update.
+    pub fn move_to(&mut self, start: usize) {
+        self.0.target = start;
+    }
+
+    /// Catches up on the agent moves and on the edges changed on the graph since the last update.
+    pub fn update(&mut self, graph: &Graph) {
+        self.0.update(graph);
+    }
+
+    pub fn path(&self, graph: &Graph) -> Option<Vec<usize>> {
+        self.0.path(graph)
+    }
+
+    /// Vertices expanded since the planner was created.
+    pub fn expansions(&self) -> usize {
+        self.0.expansions
+    }
+
+    /// Version of the graph caught up on, the oldest one to keep with [`Graph::compact`].
+    pub fn version(&self) -> usize {
+        self.0.version
+    }
+
+    pub fn search(&self, graph: &Graph) -> CostGuidedTreeSearchResult {
+        CostGuidedTreeSearchResult {
+            start_idx: self.0.target,
+            stop_idx: self.0.root,
+            reached: self.0.reached(),
+            tree: vec![],
+            backward: self.0.tree(graph),
+            fringe: self.0.fringe(),
+            meeting: None,
+            path: self.path(graph),
+        }
+    }
+}
+
+type Key = (OrderedFloat<f32>, OrderedFloat<f32>);
+
+/// Distances from the root towards the target on a graph with edges both ways, kept up to date
+/// as edges change and the target moves.
+///
+/// Each vertex has its distance `g` and the lookahead `rhs`, the lowest distance of a neighbor
+/// plus the edge to it. Vertices where the two differ are inconsistent and wait to be expanded by
+/// key, distance plus heuristic towards the target, until the target is consistent and no key is
+/// lower. Keys of queued vertices are not updated when the target moves, `km` adds up the
+/// heuristic between the targets so that all keys stay lower bounds, and outdated keys are
+/// requeued when they come up.
+struct Planner {
+    root: usize,
+    target: usize,
+    last_target: usize,
+    km: f32,
+    g: Vec<f32>,
+    rhs: Vec<f32>,
+    /// Key of each queued vertex, heap entries with another key are outdated
+    key: Vec<Option<Key>>,
+    queue: BinaryHeap<Reverse<(Key, usize)>>,
+    version: usize,
+    expansions: usize,
+}
+
+impl Planner {
+    fn new(graph: &Graph, root: usize, target: usize) -> Self {
+        let mut planner = Self {
+            root,
+            target,
+            last_target: target,
+            km: 0.0,
+            g: vec![],
+            rhs: vec![],
+            key: vec![],
+            queue: BinaryHeap::new(),
+            version: graph.version(),
+            expansions: 0,
+        };
+        planner.restart(graph);
+        planner
+    }
+
+    /// Forgets all distances and computes them from scratch, unless the root or the target is not
+    /// a vertex of the graph.
+    fn restart(&mut self, graph: &Graph) {
+        let count = graph.vertices.len();
+        self.last_target = self.target;
+        self.km = 0.0;
+        self.g = vec![f32::INFINITY; count];
+        self.rhs = vec![f32::INFINITY; count];
+        self.key = vec![None; count];
+        self.queue.clear();
+        self.version = graph.version();
+        if self.root < count && self.target < count {
+            self.rhs[self.root] = 0.0;
+            self.update_vertex(graph, self.root);
+            self.compute(graph);
+        }
+    }
+
+    fn key(&self, graph: &Graph, vertex: usize) -> Key {
+        let dist = self.g[vertex].min(self.rhs[vertex]);
+        (
+            OrderedFloat(dist + graph.heuristic(&vertex, &self.target) + self.km),
+            OrderedFloat(dist),
+        )
+    }
+
+    fn update_vertex(&mut self, graph: &Graph, vertex: usize) {
+        if vertex != self.root {
+            // A self loop would keep the distance the vertex had before the change
+            self.rhs[vertex] = graph
+                .successors(&vertex)
+                .filter(|&(adj, _)| adj != vertex)
+                .map(|(adj, cost)| self.g[adj] + cost)
+                .fold(f32::INFINITY, f32::min);
+        }
+        self.key[vertex] = (self.g[vertex] != self.rhs[vertex]).then(|| self.key(graph, vertex));
+        if let Some(key) = self.key[vertex] {
+            self.queue.push(Reverse((key, vertex)));
+        }
+    }
+
+    fn top(&mut self) -> Option<(Key, usize)> {
+        while let Some(&Reverse((key, vertex))) = self.queue.peek() {
+            if self.key[vertex] == Some(key) {
+                return Some((key, vertex));
+            }
+            self.queue.pop();
+        }
+        None
+    }
+
+    fn compute(&mut self, graph: &Graph) {
+        while let Some((key, vertex)) = self.top() {
+            if key >= self.key(graph, self.target) && self.g[self.target] == self.rhs[self.target] {
+                break;
+            }
+            let current = self.key(graph, vertex);
+            if key < current {
+                self.key[vertex] = Some(current);
+                self.queue.push(Reverse((current, vertex)));
+                continue;
+            }
+            self.key[vertex] = None;
+            self.expansions += 1;
+            if self.g[vertex] > self.rhs[vertex] {
+                self.g[vertex] = self.rhs[vertex];
+            } else {
+                self.g[vertex] = f32::INFINITY;
+                self.update_vertex(graph, vertex);
+            }
+            for (adj, _) in graph.successors(&vertex) {
+                self.update_vertex(graph, adj);
+            }
+        }
+    }
+
+    fn update(&mut self, graph: &Graph) {
+        let count = graph.vertices.len();
+        let ends = [self.root, self.last_target, self.target];
+        match graph.changes_since(self.version) {
+            Some(changes) if ends.iter().all(|&vertex| vertex < count) => {
+                self.km += graph.heuristic(&self.last_target, &self.target);
+                self.last_target = self.target;
+                for &[a, b] in changes {
+                    self.update_vertex(graph, a);
+                    self.update_vertex(graph, b);
+                }
+                self.version = graph.version();
+                self.compute(graph);
+            }
+            // Generated anew, or the ends were not vertices at the last restart
+            _ => self.restart(graph),
+        }
+    }
+
+    fn reached(&self) -> bool {
+        self.g.get(self.target).is_some_and(|g| g.is_finite())
+    }
+
+    /// Neighbor that the vertex reaches the root through most cheaply.
+    fn parent(&self, graph: &Graph, vertex: usize) -> Option<usize> {
+        graph
+            .successors(&vertex)
+            .filter(|&(adj, _)| adj != vertex && self.g[adj].is_finite())
+            .min_by_key(|&(adj, cost)| OrderedFloat(self.g[adj] + cost))
+            .map(|(adj, _)| adj)
+    }
+
+    /// From the target to the root.
+    fn path(&self, graph: &Graph) -> Option<Vec<usize>> {
+        if !self.reached() {
+            return None;
+        }
+        let mut path = vec![self.target];
+        while *path.last().unwrap() != self.root {
+            path.push(self.parent(graph, *path.last().unwrap())?);
+            if path.len() > self.g.len() {
+                return None;
+            }
+        }
+        Some(path)
+    }
+
+    fn tree(&self, graph: &Graph) -> Vec<(usize, Option<usize>)> {
+        (0..self.g.len())
+            .filter(|&vertex| self.g[vertex].is_finite())
+            .map(|vertex| {
+                let parent = (vertex != self.root)
+                    .then(|| self.parent(graph, vertex))
+                    .flatten();
+                (vertex, parent)
+            })
+            .collect()
+    }
+
+    fn fringe(&self) -> Vec<usize> {
+        (0..self.key.len())
+            .filter(|&vertex| self.key[vertex].is_some())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampling::Sampler;
+    use crate::search::search::tests::{all_pairs, assert_cheapest, random_graph};
+    use crate::search::spaces::CuboidWithHoldSpace;
+    use bevy::prelude::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Blocks a vertex inside the path, or adds an edge as cheap as the heuristic allows when
+    /// there is none, so that paths get both dearer and cheaper. The edge may be a self loop.
+    fn change(rng: &mut StdRng, graph: &mut Graph, path: Option<Vec<usize>>) {
+        match path {
+            Some(path) if path.len() > 2 => {
+                graph.block_vertex(path[rng.gen_range(1..path.len() - 1)])
+            }
+            _ => {
+                let count = graph.vertices.len();
+                let (a, b) = (rng.gen_range(0..count), rng.gen_range(0..count));
+                graph.set_edge_cost(a, b, Some(graph.heuristic(&a, &b)));
+            }
+        }
+    }
+
+    #[test]
+    fn lpa_star_matches_search_from_scratch() {
+        let mut rng = StdRng::seed_from_u64(25);
+        for _ in 0..30 {
+            let mut graph = random_graph(&mut rng, 20, 50);
+            let mut lpa = LPAStar::new(&graph, 0, 19);
+            for _ in 0..8 {
+                let path = lpa.path(&graph);
+                assert_cheapest(&graph, &all_pairs(&graph), (0, 19), path.as_deref());
+                change(&mut rng, &mut graph, path);
+                lpa.update(&graph);
+            }
+        }
+    }
+
+    #[test]
+    fn d_star_lite_matches_search_from_scratch() {
+        let mut rng = StdRng::seed_from_u64(26);
+        for _ in 0..30 {
+            let mut graph = random_graph(&mut rng, 20, 50);
+            let mut at = 0;
+            let mut dstar = DStarLite::new(&graph, at, 19);
+            for _ in 0..8 {
+                let path = dstar.path(&graph);
+                assert_cheapest(&graph, &all_pairs(&graph), (at, 19), path.as_deref());
+                // Take a step along the path, then change the graph ahead
+                if let Some(&next) = path.as_ref().and_then(|path| path.get(1)) {
+                    at = next;
+                    dstar.move_to(at);
+                }
+                let path = dstar.path(&graph);
+                change(&mut rng, &mut graph, path);
+                dstar.update(&graph);
+            }
+        }
+    }
+
+    #[test]
+    fn start_over_after_generating() {
+        let mut rng = StdRng::seed_from_u64(27);
+        let mut graph = random_graph(&mut rng, 200, 800);
+        let mut lpa = LPAStar::new(&graph, 0, 10);
+        let mut dstar = DStarLite::new(&graph, 10, 0);
+        let mut gone = LPAStar::new(&graph, 0, 199);
+        let version = graph.version();
+        let space = CuboidWithHoldSpace {
+            size: Vec3::new(6.0, 12.0, 0.5),
+            hole_radius: 6.0 / 2.1,
+        };
+        graph.generate_samples_with(&space, Sampler::Uniform, 100, 2.0, &mut rng);
+        assert!(graph.version() > version);
+        assert_eq!(graph.changes_since(version), None);
+        let dist = all_pairs(&graph);
+        lpa.update(&graph);
+        dstar.update(&graph);
+        gone.update(&graph);
+        assert_cheapest(&graph, &dist, (0, 10), lpa.path(&graph).as_deref());
+        assert_cheapest(&graph, &dist, (10, 0), dstar.path(&graph).as_deref());
+        // The goal is not a vertex any more
+        assert_eq!(gone.path(&graph), None);
+    }
+
+    #[test]
+    fn compacted_changes() {
+        let mut rng = StdRng::seed_from_u64(28);
+        for _ in 0..30 {
+            let mut graph = random_graph(&mut rng, 20, 50);
+            let mut lpa = LPAStar::new(&graph, 0, 19);
+            let mut dstar = DStarLite::new(&graph, 19, 0);
+            // Not kept track of by the compactions, so it starts over
+            let mut behind = LPAStar::new(&graph, 19, 0);
+            for _ in 0..8 {
+                let version = graph.version();
+                let path = lpa.path(&graph);
+                change(&mut rng, &mut graph, path);
+                let path = dstar.path(&graph);
+                change(&mut rng, &mut graph, path);
+                lpa.update(&graph);
+                assert_eq!(
+                    graph.changes_since(dstar.version()).unwrap().len(),
+                    graph.version() - version
+                );
+                graph.compact(lpa.version().min(dstar.version()));
+                dstar.update(&graph);
+                graph.compact(lpa.version().min(dstar.version()));
+                assert_eq!(graph.changes_since(version), None);
+                assert_eq!(graph.changes_since(graph.version()), Some(&[][..]));
+
+                let dist = all_pairs(&graph);
+                assert_cheapest(&graph, &dist, (0, 19), lpa.path(&graph).as_deref());
+                assert_cheapest(&graph, &dist, (19, 0), dstar.path(&graph).as_deref());
+                behind.update(&graph);
+                assert_cheapest(&graph, &dist, (19, 0), behind.path(&graph).as_deref());
+            }
+        }
+    }
+}
@@ -1,4 +1,7 @@
+pub mod anytime;
+pub mod bidirectional;
 pub mod graph;
+pub mod incremental;
 pub mod path;
 pub mod search;
 pub mod spaces;
@@ -1,5 +1,6 @@
 use super::graph::*;
 use super::search::*;
+use crate::geometry::sweep::is_self_intersecting;
 use bevy::prelude::*;
 
 #[derive(Resource, Default)]
@@ -9,7 +10,7 @@ pub struct Path {
 
 impl Path {
     pub fn generate_for<'a>(&mut self, graph: &'a Graph, ts: &CostGuidedTreeSearchResult) {
-        let vertices = match ts.path_to_stop(graph) {
+        let vertices = match ts.path_to_stop() {
             None => vec![],
             Some(path) => path
                 .into_iter()
@@ -18,4 +19,41 @@ impl Path {
         };
         self.vertices = vertices;
     }
+
+    /// Whether the path crosses itself when seen along the z axis.
+    pub fn is_self_intersecting(&self) -> bool {
+        let polyline: Vec<Vec2> = self.vertices.iter().map(|v| v.truncate()).collect();
+        is_self_intersecting(&polyline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_intersecting_seen_along_z() {
+        let path = |points: &[[f32; 3]]| Path {
+            vertices: points.iter().map(|&p| Vec3::from(p)).collect(),
+        };
+        // Crossing in the plane at different heights
+        let crossing = path(&[
+            [0.0, 0.0, 0.0],
+            [2.0, 2.0, 0.0],
+            [2.0, 0.0, 1.0],
+            [0.0, 2.0, 5.0],
+        ]);
+        assert!(crossing.is_self_intersecting());
+        // Going back over itself, and coming back to the start
+        assert!(path(&[[0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [1.0, 0.0, 3.0]]).is_self_intersecting());
+        let closed = path(&[
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0],
+        ]);
+        assert!(!closed.is_self_intersecting());
+        assert!(!path(&[[0.0, 0.0, 0.0], [1.0, 1.0, 1.0]]).is_self_intersecting());
+        assert!(!Path::default().is_self_intersecting());
+    }
 }
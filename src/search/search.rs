@@ -3,65 +3,206 @@ use bevy::prelude::*;
 use ordered_float::OrderedFloat;
 use std::{
     cmp::{Ordering, Reverse},
-    collections::{BinaryHeap, HashMap, HashSet},
+    collections::{BinaryHeap, HashMap},
+    hash::Hash,
 };
 
+/// Search tree over the vertices of a [`SearchGraph`], by default the indices of a [`Graph`].
 #[derive(Resource, Default)]
-pub struct CostGuidedTreeSearchResult {
-    pub start_idx: usize,
-    pub stop_idx: usize,
+pub struct CostGuidedTreeSearchResult<V = usize> {
+    pub start_idx: V,
+    pub stop_idx: V,
     pub reached: bool,
-    pub parent_map: HashMap<usize, Option<usize>>,
-    pub fringe: HashSet<usize>,
+    /// Every vertex in the tree with its parent, none for the start
+    pub tree: Vec<(V, Option<V>)>,
+    /// Tree grown from the stop by bidirectional searches, with parents towards the stop
+    pub backward: Vec<(V, Option<V>)>,
+    /// Vertices in the trees that were not expanded
+    pub fringe: Vec<V>,
+    /// Vertex where the trees of bidirectional searches met on the path
+    pub meeting: Option<V>,
+    /// From the start to the stop, if reached
+    pub path: Option<Vec<V>>,
 }
 
-impl<'a> CostGuidedTreeSearchResult {
-    pub fn path_to_stop(&self, graph: &'a Graph) -> Option<Vec<usize>> {
-        assert!(self.stop_idx <= graph.vertices.len() - 1);
-        if self.stop_idx == self.start_idx {
-            return Some(vec![self.start_idx]);
+impl<V: Clone> CostGuidedTreeSearchResult<V> {
+    pub fn path_to_stop(&self) -> Option<Vec<V>> {
+        self.path.clone()
+    }
+}
+
+/// Per vertex bookkeeping of tree searches, kept between queries so that it is allocated once.
+///
+/// Vertices are numbered by [`SearchGraph::index`] where the graph provides it and by a hash map
+/// otherwise, and everything else lives in arrays indexed by that number. Each slot is stamped
+/// with the query that wrote it and counts as empty for any other, so a query starts by bumping
+/// the generation instead of clearing the arrays.
+pub struct SearchWorkspace<V, S> {
+    generation: u32,
+    /// Query that last reached each vertex
+    reached: Vec<u32>,
+    /// Query that last expanded each vertex
+    expanded: Vec<u32>,
+    parent: Vec<usize>,
+    state: Vec<Option<S>>,
+    vertex: Vec<Option<V>>,
+    /// Numbers of vertices the graph does not number, from its vertex count onwards
+    ids: HashMap<V, usize>,
+    ids_from: usize,
+}
+
+impl<V, S> Default for SearchWorkspace<V, S> {
+    fn default() -> Self {
+        Self {
+            generation: 0,
+            reached: vec![],
+            expanded: vec![],
+            parent: vec![],
+            state: vec![],
+            vertex: vec![],
+            ids: HashMap::new(),
+            ids_from: 0,
         }
-        let mut idx = self.stop_idx;
-        let mut path = vec![idx];
-        while let Some(&Some(parent_idx)) = self.parent_map.get(&idx) {
-            path.push(parent_idx);
-            idx = parent_idx;
+    }
+}
+
+impl<V: Clone + Eq + Hash, S> SearchWorkspace<V, S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(super) fn begin<G: SearchGraph<Vertex = V>>(&mut self, graph: &G) {
+        if self.ids_from != graph.vertex_count() {
+            self.ids.clear();
+            self.ids_from = graph.vertex_count();
+        }
+        self.generation = match self.generation.checked_add(1) {
+            Some(generation) => generation,
+            None => {
+                // Stamps of the previous round could be mistaken for current ones
+                self.reached.fill(0);
+                self.expanded.fill(0);
+                1
+            }
+        };
+    }
+
+    pub(super) fn id<G: SearchGraph<Vertex = V>>(&mut self, graph: &G, vertex: &V) -> usize {
+        let id = match graph.index(vertex) {
+            Some(id) => id,
+            None => {
+                let next = self.ids_from + self.ids.len();
+                *self.ids.entry(vertex.clone()).or_insert(next)
+            }
+        };
+        if id >= self.reached.len() {
+            let len = (id + 1).max(graph.vertex_count());
+            self.reached.resize(len, 0);
+            self.expanded.resize(len, 0);
+            self.parent.resize(len, usize::MAX);
+            self.state.resize_with(len, || None);
+            self.vertex.resize(len, None);
         }
-        let path: Vec<usize> = path.into_iter().rev().collect();
-        match path.len() {
-            1 => None,
-            _ => Some(path),
+        id
+    }
+
+    pub(super) fn is_reached(&self, id: usize) -> bool {
+        self.reached[id] == self.generation
+    }
+
+    pub(super) fn is_expanded(&self, id: usize) -> bool {
+        self.expanded[id] == self.generation
+    }
+
+    pub(super) fn reach(&mut self, id: usize, vertex: V, parent: usize, state: S) {
+        self.reached[id] = self.generation;
+        self.vertex[id] = Some(vertex);
+        self.parent[id] = parent;
+        self.state[id] = Some(state);
+    }
+
+    pub(super) fn expand(&mut self, id: usize) {
+        self.expanded[id] = self.generation;
+    }
+
+    pub(super) fn parent(&self, id: usize) -> Option<usize> {
+        (self.parent[id] != usize::MAX).then_some(self.parent[id])
+    }
+
+    /// The vertex with its parent, as in [`CostGuidedTreeSearchResult::tree`].
+    pub(super) fn tree_entry(&self, id: usize) -> (V, Option<V>) {
+        (
+            self.vertex(id),
+            self.parent(id).map(|parent| self.vertex(parent)),
+        )
+    }
+
+    /// Vertices from the root of the tree to the vertex.
+    pub(super) fn path_to(&self, id: usize) -> Vec<V> {
+        let mut path = vec![id];
+        while let Some(parent) = self.parent(*path.last().unwrap()) {
+            path.push(parent);
         }
+        path.into_iter().rev().map(|id| self.vertex(id)).collect()
+    }
+
+    pub(super) fn vertex(&self, id: usize) -> V {
+        self.vertex[id].clone().unwrap()
+    }
+
+    pub(super) fn state(&self, id: usize) -> &S {
+        self.state[id].as_ref().unwrap()
     }
 }
 
+/// Search that grows a tree from the start vertex, always expanding the fringe vertex of lowest
+/// cost. The implementations only differ in how they cost a vertex from its parent.
+///
+/// Until a vertex is expanded, reaching it at a lower cost moves it under the new parent. The
+/// heap is not updated in place, outdated entries are skipped once their vertex is expanded. With
+/// costs that add up edge costs, plus for A* a heuristic that never drops by more than the edge
+/// cost, expanded vertices have their cheapest path in the tree, so UCS and A* find shortest paths.
 pub trait CostGuidedWaveTreeSearch<Cost: Ord>: Sized {
-    fn as_start(my_vertex_state: Vec3, stop_vertex_state: Vec3) -> Self;
+    fn as_start<G: SearchGraph>(graph: &G, vertex: &G::Vertex, stop: &G::Vertex) -> Self;
 
-    fn as_adj(
-        prev_vertex_state: Vec3,
-        my_vertex_state: Vec3,
-        stop_vertex_state: Vec3,
+    fn as_adj<G: SearchGraph>(
+        graph: &G,
+        vertex: &G::Vertex,
+        stop: &G::Vertex,
+        edge_cost: f32,
         parent: &Self,
     ) -> Self;
 
     fn cost(&self) -> Cost;
 
-    fn try_on<'a>(
-        graph: &'a Graph,
-        start_idx: usize,
-        stop_idx: usize,
-    ) -> CostGuidedTreeSearchResult {
-        assert!(start_idx < graph.vertices.len());
-        assert!(stop_idx < graph.vertices.len());
-        let start_search_state =
-            Self::as_start(graph.vertices[start_idx].pos, graph.vertices[stop_idx].pos);
-        let collec_alloc_size = graph.vertices.len() as usize;
-        let mut parent_map = HashMap::with_capacity(collec_alloc_size);
-        parent_map.insert(start_idx, None);
-
-        let mut fringe = BinaryHeap::with_capacity(collec_alloc_size);
+    fn try_on<G: SearchGraph>(
+        graph: &G,
+        start_idx: G::Vertex,
+        stop_idx: G::Vertex,
+    ) -> CostGuidedTreeSearchResult<G::Vertex> {
+        Self::try_on_with(&mut SearchWorkspace::new(), graph, start_idx, stop_idx)
+    }
+
+    /// Like [`Self::try_on`], reusing the bookkeeping of earlier queries on the same graph.
+    fn try_on_with<G: SearchGraph>(
+        workspace: &mut SearchWorkspace<G::Vertex, Self>,
+        graph: &G,
+        start_idx: G::Vertex,
+        stop_idx: G::Vertex,
+    ) -> CostGuidedTreeSearchResult<G::Vertex> {
+        let start_search_state = Self::as_start(graph, &start_idx, &stop_idx);
+        Self::try_from_with(workspace, graph, start_search_state, start_idx, stop_idx)
+    }
 
+    /// Like [`Self::try_on_with`] from the given state of the start vertex, which passes on
+    /// settings chosen at runtime, like the weight of [`WeightedAStar`], to the other vertices.
+    fn try_from_with<G: SearchGraph>(
+        workspace: &mut SearchWorkspace<G::Vertex, Self>,
+        graph: &G,
+        start_search_state: Self,
+        start_idx: G::Vertex,
+        stop_idx: G::Vertex,
+    ) -> CostGuidedTreeSearchResult<G::Vertex> {
         struct CostOrdAndIndex<Cost: Ord> {
             idx: usize,
             cost: Cost,
@@ -87,53 +228,64 @@ pub trait CostGuidedWaveTreeSearch<Cost: Ord>: Sized {
             }
         }
 
+        let w = workspace;
+        w.begin(graph);
+        let start = w.id(graph, &start_idx);
+        let stop = w.id(graph, &stop_idx);
+        let mut fringe = BinaryHeap::new();
         fringe.push(Reverse(CostOrdAndIndex {
-            idx: start_idx,
+            idx: start,
             cost: start_search_state.cost(),
         }));
-        let mut tree = HashMap::with_capacity(collec_alloc_size);
-        tree.insert(start_idx, start_search_state);
-        while let Some(Reverse(CostOrdAndIndex { idx: curr_idx, .. })) = fringe.pop() {
-            if curr_idx == stop_idx {
-                return CostGuidedTreeSearchResult {
-                    start_idx,
-                    stop_idx,
-                    parent_map,
-                    fringe: fringe
-                        .into_sorted_vec()
-                        .into_iter()
-                        .map(|Reverse(CostOrdAndIndex { idx, .. })| idx)
-                        .collect(),
-                    reached: true,
-                };
+        w.reach(start, start_idx.clone(), usize::MAX, start_search_state);
+        let mut tree = vec![start];
+        let mut reached = false;
+        while let Some(Reverse(CostOrdAndIndex { idx: curr, .. })) = fringe.pop() {
+            if w.is_expanded(curr) {
+                continue;
+            }
+            w.expand(curr);
+            if curr == stop {
+                reached = true;
+                break;
             }
-            for &adj_idx in graph.vertices[curr_idx].adjacencies.iter() {
-                if let None = tree.get(&adj_idx) {
-                    let adj_search_state = Self::as_adj(
-                        graph.vertices[curr_idx].pos,
-                        graph.vertices[adj_idx].pos,
-                        graph.vertices[stop_idx].pos,
-                        &tree[&curr_idx],
-                    );
-                    parent_map.insert(adj_idx, Some(curr_idx));
-                    fringe.push(Reverse(CostOrdAndIndex {
-                        idx: adj_idx,
-                        cost: adj_search_state.cost(),
-                    }));
-                    tree.insert(adj_idx, adj_search_state);
+            let curr_idx = w.vertex(curr);
+            for (adj_idx, edge_cost) in graph.successors(&curr_idx) {
+                let adj = w.id(graph, &adj_idx);
+                if w.is_expanded(adj) {
+                    continue;
                 }
+                let adj_search_state =
+                    Self::as_adj(graph, &adj_idx, &stop_idx, edge_cost, w.state(curr));
+                let adj_cost = adj_search_state.cost();
+                if w.is_reached(adj) {
+                    if w.state(adj).cost() <= adj_cost {
+                        continue;
+                    }
+                } else {
+                    tree.push(adj);
+                }
+                fringe.push(Reverse(CostOrdAndIndex {
+                    idx: adj,
+                    cost: adj_cost,
+                }));
+                w.reach(adj, adj_idx, curr, adj_search_state);
             }
         }
+        let path = reached.then(|| w.path_to(stop));
         CostGuidedTreeSearchResult {
             start_idx,
             stop_idx,
-            parent_map,
-            fringe: fringe
-                .into_sorted_vec()
-                .into_iter()
-                .map(|Reverse(CostOrdAndIndex { idx, .. })| idx)
+            reached,
+            fringe: tree
+                .iter()
+                .filter(|&&id| !w.is_expanded(id))
+                .map(|&id| w.vertex(id))
                 .collect(),
-            reached: false,
+            tree: tree.iter().map(|&id| w.tree_entry(id)).collect(),
+            backward: vec![],
+            meeting: None,
+            path,
         }
     }
 }
@@ -144,11 +296,11 @@ pub struct DFS {
 }
 
 impl CostGuidedWaveTreeSearch<isize> for DFS {
-    fn as_start(_: Vec3, _: Vec3) -> Self {
+    fn as_start<G: SearchGraph>(_: &G, _: &G::Vertex, _: &G::Vertex) -> Self {
         Self { order: -0 }
     }
 
-    fn as_adj(_: Vec3, _: Vec3, _: Vec3, parent: &Self) -> Self {
+    fn as_adj<G: SearchGraph>(_: &G, _: &G::Vertex, _: &G::Vertex, _: f32, parent: &Self) -> Self {
         Self {
             order: parent.order - 1,
         }
@@ -165,13 +317,13 @@ pub struct BFS {
 }
 
 impl CostGuidedWaveTreeSearch<usize> for BFS {
-    fn as_start(_: Vec3, _: Vec3) -> Self {
+    fn as_start<G: SearchGraph>(_: &G, _: &G::Vertex, _: &G::Vertex) -> Self {
         Self {
             jumps_from_start: 0,
         }
     }
 
-    fn as_adj(_: Vec3, _: Vec3, _: Vec3, parent: &Self) -> Self {
+    fn as_adj<G: SearchGraph>(_: &G, _: &G::Vertex, _: &G::Vertex, _: f32, parent: &Self) -> Self {
         Self {
             jumps_from_start: parent.jumps_from_start + 1,
         }
@@ -183,33 +335,93 @@ impl CostGuidedWaveTreeSearch<usize> for BFS {
 }
 
 #[derive(Resource, Default)]
-pub struct WeightableAStar<const NUM: usize, const DEN: usize> {
+pub struct UCS {
+    dist_from_start: f32,
+}
+
+impl CostGuidedWaveTreeSearch<OrderedFloat<f32>> for UCS {
+    fn as_start<G: SearchGraph>(_: &G, _: &G::Vertex, _: &G::Vertex) -> Self {
+        Self {
+            dist_from_start: 0.0,
+        }
+    }
+
+    fn as_adj<G: SearchGraph>(
+        _: &G,
+        _: &G::Vertex,
+        _: &G::Vertex,
+        edge_cost: f32,
+        parent: &Self,
+    ) -> Self {
+        Self {
+            dist_from_start: parent.dist_from_start + edge_cost,
+        }
+    }
+
+    fn cost(&self) -> OrderedFloat<f32> {
+        OrderedFloat(self.dist_from_start)
+    }
+}
+
+/// A* with the heuristic scaled by a weight chosen at runtime, 1 for plain A* from
+/// [`CostGuidedWaveTreeSearch::try_on`]. Weights above 1 head for the stop more greedily and
+/// expand fewer vertices, for paths that cost at most the weight times the cheapest as long as the
+/// heuristic never overestimates. See [`super::anytime::ARAStar`] to improve on such a path.
+#[derive(Resource, Default)]
+pub struct WeightedAStar {
     dist_from_start: f32,
     total_cost: f32,
+    weight: f32,
 }
 
-impl<const NUM: usize, const DEN: usize> CostGuidedWaveTreeSearch<OrderedFloat<f32>>
-    for WeightableAStar<NUM, DEN>
-{
-    fn as_start(my_vertex_state: Vec3, stop_vertex_state: Vec3) -> Self {
+impl WeightedAStar {
+    pub fn as_weighted_start<G: SearchGraph>(
+        graph: &G,
+        vertex: &G::Vertex,
+        stop: &G::Vertex,
+        weight: f32,
+    ) -> Self {
         Self {
             dist_from_start: 0.0,
-            total_cost: 0.0 + (my_vertex_state - stop_vertex_state).length(),
+            total_cost: 0.0 + graph.heuristic(vertex, stop) * weight,
+            weight,
         }
     }
 
-    fn as_adj(
-        prev_vertex_state: Vec3,
-        my_vertex_state: Vec3,
-        stop_vertex_state: Vec3,
+    pub fn try_on_weighted<G: SearchGraph>(
+        graph: &G,
+        start_idx: G::Vertex,
+        stop_idx: G::Vertex,
+        weight: f32,
+    ) -> CostGuidedTreeSearchResult<G::Vertex> {
+        let start_search_state = Self::as_weighted_start(graph, &start_idx, &stop_idx, weight);
+        Self::try_from_with(
+            &mut SearchWorkspace::new(),
+            graph,
+            start_search_state,
+            start_idx,
+            stop_idx,
+        )
+    }
+}
+
+impl CostGuidedWaveTreeSearch<OrderedFloat<f32>> for WeightedAStar {
+    fn as_start<G: SearchGraph>(graph: &G, vertex: &G::Vertex, stop: &G::Vertex) -> Self {
+        Self::as_weighted_start(graph, vertex, stop, 1.0)
+    }
+
+    fn as_adj<G: SearchGraph>(
+        graph: &G,
+        vertex: &G::Vertex,
+        stop: &G::Vertex,
+        edge_cost: f32,
         parent: &Self,
     ) -> Self {
-        let dist_from_start =
-            parent.dist_from_start + (prev_vertex_state - my_vertex_state).length();
+        let dist_from_start = parent.dist_from_start + edge_cost;
         Self {
             dist_from_start,
-            total_cost: dist_from_start
-                + (my_vertex_state - stop_vertex_state).length() * (NUM as f32 / DEN as f32),
+            total_cost: dist_from_start + graph.heuristic(vertex, stop) * parent.weight,
+            weight: parent.weight,
         }
     }
 
@@ -218,6 +430,259 @@ impl<const NUM: usize, const DEN: usize> CostGuidedWaveTreeSearch<OrderedFloat<f
     }
 }
 
-pub type UCS = WeightableAStar<0, 1>;
-pub type AStar = WeightableAStar<1, 1>;
-pub type AStarWeighted2 = WeightableAStar<2, 1>;
+pub type AStar = WeightedAStar;
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// Random points joined by random edges that cost at least the distance between their ends,
+    /// so that the heuristic never overestimates.
+    pub(crate) fn random_graph(rng: &mut StdRng, count: usize, edges: usize) -> Graph {
+        let mut graph = Graph::default();
+        for _ in 0..count {
+            graph.vertices.push(Vertex {
+                pos: Vec3::new(rng.gen(), rng.gen(), rng.gen()) * 10.0,
+                adjacencies: HashMap::new(),
+            });
+        }
+        for _ in 0..edges {
+            let (a, b) = (rng.gen_range(0..count), rng.gen_range(0..count));
+            if a != b {
+                let cost = graph.heuristic(&a, &b) * rng.gen_range(1.0..3.0);
+                graph.set_edge_cost(a, b, Some(cost));
+            }
+        }
+        graph
+    }
+
+    /// Cheapest cost between every pair of vertices by Floyd-Warshall.
+    pub(crate) fn all_pairs(graph: &Graph) -> Vec<Vec<f64>> {
+        let count = graph.vertices.len();
+        let mut dist = vec![vec![f64::INFINITY; count]; count];
+        for (a, vertex) in graph.vertices.iter().enumerate() {
+            dist[a][a] = 0.0;
+            for (&b, &cost) in &vertex.adjacencies {
+                dist[a][b] = dist[a][b].min(cost as f64);
+            }
+        }
+        for k in 0..count {
+            for a in 0..count {
+                for b in 0..count {
+                    dist[a][b] = dist[a][b].min(dist[a][k] + dist[k][b]);
+                }
+            }
+        }
+        dist
+    }
+
+    /// Checks that the path runs from the start to the stop along edges of the graph for the
+    /// cheapest cost, or that there is none when the stop cannot be reached.
+    pub(crate) fn assert_cheapest(
+        graph: &Graph,
+        dist: &[Vec<f64>],
+        (start, stop): (usize, usize),
+        path: Option<&[usize]>,
+    ) {
+        let cheapest = dist[start][stop];
+        let Some(path) = path else {
+            assert!(cheapest.is_infinite(), "no path from {start} to {stop}");
+            return;
+        };
+        assert_eq!((path.first(), path.last()), (Some(&start), Some(&stop)));
+        let cost: f64 = path
+            .windows(2)
+            .map(|edge| graph.vertices[edge[0]].adjacencies[&edge[1]] as f64)
+            .sum();
+        assert!(
+            (cost - cheapest).abs() <= 1e-4 * cheapest.max(1.0),
+            "{path:?} from {start} to {stop} costs {cost}, not {cheapest}"
+        );
+    }
+
+    #[test]
+    fn cheapest_paths_on_random_graphs() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let mut ucs = SearchWorkspace::new();
+        let mut astar = SearchWorkspace::new();
+        for _ in 0..40 {
+            let graph = random_graph(&mut rng, 12, 24);
+            let dist = all_pairs(&graph);
+            for start in 0..12 {
+                for stop in 0..12 {
+                    let result = UCS::try_on_with(&mut ucs, &graph, start, stop);
+                    assert_eq!(result.reached, dist[start][stop].is_finite());
+                    assert_cheapest(&graph, &dist, (start, stop), result.path.as_deref());
+                    let result = AStar::try_on_with(&mut astar, &graph, start, stop);
+                    assert_cheapest(&graph, &dist, (start, stop), result.path.as_deref());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn weighted_paths_within_the_weight() {
+        let mut rng = StdRng::seed_from_u64(24);
+        let mut dearer = 0;
+        for _ in 0..40 {
+            let graph = random_graph(&mut rng, 12, 24);
+            let dist = all_pairs(&graph);
+            for (start, row) in dist.iter().enumerate() {
+                for (stop, &cheapest) in row.iter().enumerate() {
+                    for weight in [1.0, 1.5, 2.0, 100.0] {
+                        let result = WeightedAStar::try_on_weighted(&graph, start, stop, weight);
+                        assert_eq!(result.reached, cheapest.is_finite());
+                        let Some(path) = result.path else {
+                            continue;
+                        };
+                        assert_eq!((path[0], path[path.len() - 1]), (start, stop));
+                        let cost: f64 = path
+                            .windows(2)
+                            .map(|edge| graph.vertices[edge[0]].adjacencies[&edge[1]] as f64)
+                            .sum();
+                        let bound = weight as f64 * cheapest;
+                        assert!(cost <= bound + 1e-4 * bound.max(1.0), "{weight} {cost}");
+                        if cost > cheapest + 1e-4 {
+                            dearer += 1;
+                        }
+                    }
+                }
+            }
+        }
+        // The weight does make the search greedier
+        assert!(dearer > 0);
+    }
+
+    #[test]
+    fn cheaper_path_found_later() {
+        // The direct edge reaches 1 first, the detour through 2 is cheaper and found after it
+        let mut graph = Graph::default();
+        for _ in 0..3 {
+            graph.vertices.push(Vertex::default());
+        }
+        graph.set_edge_cost(0, 1, Some(10.0));
+        graph.set_edge_cost(0, 2, Some(1.0));
+        graph.set_edge_cost(2, 1, Some(1.0));
+        assert_eq!(UCS::try_on(&graph, 0, 1).path, Some(vec![0, 2, 1]));
+        assert_eq!(AStar::try_on(&graph, 0, 1).path, Some(vec![0, 2, 1]));
+        let tree = UCS::try_on(&graph, 0, 1).tree;
+        assert!(tree.contains(&(1, Some(2))));
+    }
+
+    /// Grid with walls whose cells are found on the fly and have no index, so searches number
+    /// them by hash. Going up or down costs twice as much as going sideways.
+    struct Maze {
+        walls: Vec<Vec<bool>>,
+    }
+
+    impl Maze {
+        fn is_free(&self, cell: IVec2) -> bool {
+            let (width, height) = (self.walls[0].len() as i32, self.walls.len() as i32);
+            (0..width).contains(&cell.x)
+                && (0..height).contains(&cell.y)
+                && !self.walls[cell.y as usize][cell.x as usize]
+        }
+
+        fn cells(&self) -> Vec<IVec2> {
+            (0..self.walls.len() as i32)
+                .flat_map(|y| (0..self.walls[0].len() as i32).map(move |x| IVec2::new(x, y)))
+                .filter(|&cell| self.is_free(cell))
+                .collect()
+        }
+
+        /// Cheapest costs from the cell, and fewest steps, by relaxing every cell until nothing
+        /// changes.
+        fn optima(&self, from: IVec2) -> HashMap<IVec2, (f32, usize)> {
+            let mut optima = HashMap::from([(from, (0.0, 0))]);
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for cell in self.cells() {
+                    for (adj, cost) in self.successors(&cell) {
+                        let Some(&(c, s)) = optima.get(&cell) else {
+                            continue;
+                        };
+                        let best = optima.entry(adj).or_insert((f32::INFINITY, usize::MAX));
+                        if c + cost < best.0 || s + 1 < best.1 {
+                            *best = (best.0.min(c + cost), best.1.min(s + 1));
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            optima
+        }
+    }
+
+    impl SearchGraph for Maze {
+        type Vertex = IVec2;
+
+        fn successors(&self, &cell: &IVec2) -> impl Iterator<Item = (IVec2, f32)> {
+            [
+                (IVec2::X, 1.0),
+                (IVec2::NEG_X, 1.0),
+                (IVec2::Y, 2.0),
+                (IVec2::NEG_Y, 2.0),
+            ]
+            .into_iter()
+            .map(move |(step, cost)| (cell + step, cost))
+            .filter(|&(adj, _)| self.is_free(adj))
+        }
+
+        fn heuristic(&self, from: &IVec2, to: &IVec2) -> f32 {
+            let d = (*to - *from).abs();
+            d.x as f32 + 2.0 * d.y as f32
+        }
+    }
+
+    #[test]
+    fn implicit_graph_without_indices() {
+        let mut rng = StdRng::seed_from_u64(20);
+        for _ in 0..10 {
+            let maze = Maze {
+                walls: (0..6)
+                    .map(|_| (0..8).map(|_| rng.gen_bool(0.3)).collect())
+                    .collect(),
+            };
+            let cells = maze.cells();
+            let (mut bfs, mut ucs, mut astar) = (
+                SearchWorkspace::new(),
+                SearchWorkspace::new(),
+                SearchWorkspace::new(),
+            );
+            for &start in &cells {
+                let optima = maze.optima(start);
+                for &stop in &cells {
+                    // Panics unless every step is a move in the maze
+                    let cost = |path: &[IVec2]| {
+                        path.windows(2)
+                            .map(|step| {
+                                let mut moves = maze.successors(&step[0]);
+                                moves.find(|&(adj, _)| adj == step[1]).unwrap().1
+                            })
+                            .sum::<f32>()
+                    };
+                    let optimum = optima.get(&stop);
+                    let result = BFS::try_on_with(&mut bfs, &maze, start, stop);
+                    assert_eq!(result.reached, optimum.is_some());
+                    if let Some(path) = &result.path {
+                        assert_eq!((path[0], *path.last().unwrap()), (start, stop));
+                        assert_eq!(path.len() - 1, optimum.unwrap().1);
+                        assert!(cost(path) >= optimum.unwrap().0);
+                    }
+                    for result in [
+                        UCS::try_on_with(&mut ucs, &maze, start, stop),
+                        AStar::try_on_with(&mut astar, &maze, start, stop),
+                    ] {
+                        assert_eq!(result.reached, optimum.is_some());
+                        if let Some(path) = &result.path {
+                            assert_eq!((path[0], *path.last().unwrap()), (start, stop));
+                            assert_eq!(cost(path), optimum.unwrap().0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}